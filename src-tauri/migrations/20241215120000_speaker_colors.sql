@@ -0,0 +1,2 @@
+-- Stable per-speaker color, assigned from a palette on first appearance
+ALTER TABLE cached_speaker_maps ADD COLUMN color TEXT;
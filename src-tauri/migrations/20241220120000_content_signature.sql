@@ -0,0 +1,4 @@
+-- Cheap size + sampled-chunks hash used to find duplicate candidates before
+-- confirming with the full file_hash
+ALTER TABLE cached_recordings ADD COLUMN content_signature TEXT;
+CREATE INDEX idx_cached_recordings_content_signature ON cached_recordings(content_signature);
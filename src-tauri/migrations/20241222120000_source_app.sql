@@ -0,0 +1,2 @@
+-- App(s) system audio was captured from, when narrowed via discovery
+ALTER TABLE cached_recordings ADD COLUMN source_app TEXT;
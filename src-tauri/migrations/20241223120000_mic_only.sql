@@ -0,0 +1,2 @@
+-- Set when system audio failed to start and the recording fell back to mic only
+ALTER TABLE cached_recordings ADD COLUMN mic_only BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- Set when a recording is moved to the trash; NULL for live recordings
+ALTER TABLE cached_recordings ADD COLUMN deleted_at TEXT;
@@ -0,0 +1,27 @@
+-- Full-text index over titles, summaries and transcripts, kept in step by triggers
+CREATE VIRTUAL TABLE recordings_fts USING fts5(
+    local_id UNINDEXED,
+    title,
+    summary_text,
+    transcript_text,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Index recordings that already exist
+INSERT INTO recordings_fts (local_id, title, summary_text, transcript_text)
+SELECT local_id, title, summary_text, transcript_text FROM cached_recordings;
+
+CREATE TRIGGER recordings_fts_insert AFTER INSERT ON cached_recordings BEGIN
+    INSERT INTO recordings_fts (local_id, title, summary_text, transcript_text)
+    VALUES (new.local_id, new.title, new.summary_text, new.transcript_text);
+END;
+
+CREATE TRIGGER recordings_fts_delete AFTER DELETE ON cached_recordings BEGIN
+    DELETE FROM recordings_fts WHERE local_id = old.local_id;
+END;
+
+CREATE TRIGGER recordings_fts_update AFTER UPDATE OF title, summary_text, transcript_text ON cached_recordings BEGIN
+    UPDATE recordings_fts
+    SET title = new.title, summary_text = new.summary_text, transcript_text = new.transcript_text
+    WHERE local_id = old.local_id;
+END;
@@ -0,0 +1,13 @@
+-- Local-only labels for organizing recordings; keyed by local_id so sync never touches them
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE
+);
+
+CREATE TABLE recording_tags (
+    local_recording_id TEXT NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (local_recording_id, tag_id),
+    FOREIGN KEY(local_recording_id) REFERENCES cached_recordings(local_id) ON DELETE CASCADE,
+    FOREIGN KEY(tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- Set when a rename couldn't be pushed to the server; the next sync retries it
+ALTER TABLE cached_recordings ADD COLUMN title_dirty BOOLEAN NOT NULL DEFAULT 0;
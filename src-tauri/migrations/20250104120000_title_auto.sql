@@ -0,0 +1,2 @@
+-- Set when the title was derived from the transcript; kept locally, never pushed
+ALTER TABLE cached_recordings ADD COLUMN title_auto BOOLEAN NOT NULL DEFAULT 0;
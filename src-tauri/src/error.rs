@@ -8,6 +8,16 @@ pub enum AppError {
     Io(String),
     #[error("Network Error: {0}")]
     Network(String),
+    #[error("Server Error: {0}")]
+    Server(String),
+    #[error("HTTP {status}: {message}")]
+    Http { status: u16, message: String },
+    #[error("Server unreachable")]
+    Offline,
+    #[error("Request timed out")]
+    Timeout,
+    #[error("Cancelled")]
+    Cancelled,
     #[error("Serialization Error: {0}")]
     Serialization(String),
     #[error("Audio Error: {0}")]
@@ -30,9 +40,26 @@ impl From<std::io::Error> for AppError {
     }
 }
 
+impl AppError {
+    /// Error for a non-success response, e.g. `AppError::http(status, "Upload failed")`.
+    pub fn http(status: reqwest::StatusCode, message: impl Into<String>) -> Self {
+        AppError::Http { status: status.as_u16(), message: message.into() }
+    }
+}
+
 impl From<reqwest::Error> for AppError {
     fn from(error: reqwest::Error) -> Self {
-        AppError::Network(error.to_string())
+        // A connect timeout means the server is unreachable, not slow
+        if error.is_connect() {
+            return AppError::Offline;
+        }
+        if error.is_timeout() {
+            return AppError::Timeout;
+        }
+        match error.status() {
+            Some(status) => AppError::http(status, error.to_string()),
+            None => AppError::Network(error.to_string()),
+        }
     }
 }
 
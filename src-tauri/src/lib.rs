@@ -3,22 +3,41 @@ pub mod services;
 
 use tokio::sync::Mutex;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::path::PathBuf;
 use tauri::{
     menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::{TrayIconBuilder, TrayIconEvent},
-    Manager, AppHandle, Emitter,
+    Manager, AppHandle, Emitter, Listener,
 };
 use window_vibrancy::*;
-use crate::services::storage::{StorageService, Settings};
-use crate::services::audio::AudioRecorder;
-use crate::services::db::{DatabaseService, CachedRecording};
-use crate::services::sync::SyncService;
+use crate::services::storage::{normalize_base_url, StorageService, Settings, SettingsUpdate, MigrationReport};
+use crate::services::audio::{AudioRecorder, RecordingStatus, DeviceCapabilities, SplitSegment, CaptureTarget};
+use crate::services::mixer::{MixerConfig, AgcConfig, SilenceSplitConfig, MAX_GAIN};
+use crate::services::db::{DatabaseService, CachedRecording, CachedSpeakerMap, SearchResult, SortField, SortOrder, SyncStatus, Tag};
+use crate::services::sync::{BatchUploadSummary, SyncService, SyncQueue};
 use crate::services::proxy::ProxyService;
+use crate::services::audio_cache::AudioCache;
+use crate::services::integrity::{self, WavInfo};
+use crate::services::compat::{CompatService, ApiCompatibility, ConnectionCheck, ConnectionDiagnosis, TranscriptionModel};
+use crate::services::transcript::{self, LabeledSegment};
+use crate::services::transcribe::{Transcriber, WhisperCpp};
+use crate::services::export::{self, ExportFormat};
+use crate::services::events::LibraryEvent;
+use crate::services::paths;
+use crate::services::http;
+use crate::services::discovery::{self, RunnableApp};
+use crate::services::encoder::OutputFormat;
+use crate::services::heartbeat::HeartbeatService;
+use crate::services::duplicates::{self, DuplicateGroup};
+use crate::services::quota::{QuotaService, StorageUsage};
+use crate::services::scheduler::{SchedulerService, ScheduledRecording, ScheduledRecordingConfig};
 use crate::error::AppError;
 use validator::Validate;
 use tokio::sync::RwLock;
 
+const DEFAULT_PREVIEW_CHARS: u32 = 140;
+
 struct AppState {
     recorder: Arc<Mutex<AudioRecorder>>,
     is_recording: Mutex<bool>,
@@ -29,83 +48,207 @@ struct AppState {
     settings: Arc<RwLock<Settings>>,
     proxy_port: u16,
     proxy_shutdown_tx: Mutex<Option<tokio::sync::oneshot::Sender<()>>>,
+    audio_cache: Arc<AudioCache>,
+    /// Server reachability as last seen by the heartbeat.
+    online: Arc<AtomicBool>,
+    /// Format of the recording in progress (or the last one).
+    recording_format: Mutex<OutputFormat>,
+    /// Set while quitting waits for the recording in progress to be saved.
+    finalizing_on_exit: AtomicBool,
 }
 
+/// `capture_target` takes precedence over the older `target_pids`.
+/// `dual_track` writes mic and system audio as separate channels; those files
+/// play locally but may need server support to transcribe per speaker.
 #[tauri::command]
-async fn start_recording_command(filename: Option<String>, mic_device: Option<String>, capture_system_audio: bool, app_handle: AppHandle) -> Result<(), AppError> {
-    toggle_recording(&app_handle, filename, mic_device, capture_system_audio).await;
+#[allow(clippy::too_many_arguments)] // Each argument is an optional field of the invoke payload
+async fn start_recording_command(filename: Option<String>, mic_device: Option<String>, capture_system_audio: bool, target_pids: Option<Vec<i32>>, capture_target: Option<CaptureTarget>, format: Option<OutputFormat>, preserve_pause_gaps: Option<bool>, silence_split_sec: Option<f64>, dual_track: Option<bool>, app_handle: AppHandle) -> Result<(), AppError> {
+    if silence_split_sec.is_some_and(|sec| !sec.is_finite() || sec <= 0.0) {
+        return Err(AppError::Validation("silence_split_sec must be a positive number of seconds".to_string()));
+    }
+    let options = StartOptions {
+        format,
+        preserve_pause_gaps: preserve_pause_gaps.unwrap_or(false),
+        silence_split_sec,
+        dual_track: dual_track.unwrap_or(false),
+    };
+    let target = capture_target.unwrap_or_else(|| CaptureTarget::from_pids(target_pids.unwrap_or_default()));
+    toggle_recording(&app_handle, filename, mic_device, capture_system_audio, target, options).await;
     Ok(())
 }
 
+/// Finalizes the file and registers it as a draft, which is also emitted as
+/// `LibraryEvent::Stopped`.
 #[tauri::command]
 async fn stop_recording_command(app_handle: AppHandle, filename: Option<String>) -> Result<RecordingResult, AppError> {
     let state = app_handle.state::<AppState>();
 
     // 1. Stop Recorder & Rename if needed
-    let (duration_sec, final_path) = {
+    let (duration_sec, final_path, source_app, mic_only) = {
         let mut recorder = state.recorder.lock().await;
-        recorder.stop_recording(filename).map_err(AppError::Unexpected)?
+        let source_app = recorder.source_app();
+        let mic_only = recorder.is_mic_only();
+        let (duration_sec, final_path) = recorder.stop_recording(filename).map_err(AppError::Unexpected)?;
+        (duration_sec, final_path, source_app, mic_only)
     };
+    *state.is_recording.lock().await = false;
 
     let folder = final_path.parent().unwrap_or(std::path::Path::new("")).to_string_lossy().to_string();
     let file_path = final_path.to_string_lossy().to_string();
     
     // 2. Create Draft in DB
-    let file_name = final_path.file_name().unwrap_or_default().to_string_lossy().to_string();
-    let recording = state.db.create_draft(
-        file_name,
-        duration_sec,
-        file_path.clone()
-    ).await?;
+    let recording = register_draft(&app_handle, &final_path, duration_sec, source_app, mic_only).await?;
+    // Lets every window react to the stop itself, not just the one that asked
+    LibraryEvent::Stopped(recording.clone()).emit(&app_handle);
+    let local_id = recording.local_id;
 
-    let _ = app_handle.emit("recording-added", &recording);
+    // Scriberr has no streaming ingest, so the earliest we can upload is right
+    // after the file is finalized.
+    if state.settings.read().await.upload_on_stop && !state.sync.is_power_save() {
+        let sync = state.sync.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = sync.upload_recording(&local_id, None).await {
+                eprintln!("Upload on stop failed for {}: {:?}", local_id, e);
+            }
+        });
+    }
 
     Ok(RecordingResult {
         file_path,
         folder_path: folder,
         duration_sec,
+        format: *state.recording_format.lock().await,
     })
 }
 
-#[derive(serde::Serialize)]
+/// Adds a finished recording file to the library as a draft.
+async fn register_draft(app: &AppHandle, path: &std::path::Path, duration_sec: f64, source_app: Option<String>, mic_only: bool) -> Result<CachedRecording, AppError> {
+    let state = app.state::<AppState>();
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let file_path = path.to_string_lossy().to_string();
+    let recording = state.db.create_draft(
+        file_name,
+        duration_sec,
+        file_path.clone(),
+        source_app
+    ).await?;
+
+    let local_id = recording.local_id.clone();
+    if mic_only {
+        state.db.set_mic_only(&local_id, true).await?;
+    }
+    match integrity::content_signature(path) {
+        Ok(signature) => state.db.set_content_signature(&local_id, &signature).await?,
+        Err(e) => eprintln!("Failed to fingerprint {}: {:?}", file_path, e),
+    }
+    // Full hash, sent with the upload and checked against later downloads
+    let hash_path = path.to_path_buf();
+    let hashed = tokio::task::spawn_blocking(move || integrity::hash_file(&hash_path)).await
+        .map_err(|e| AppError::Unexpected(e.to_string()))
+        .and_then(|hash| hash);
+    match hashed {
+        Ok(hash) => state.db.set_file_hash(&local_id, &hash).await?,
+        Err(e) => eprintln!("Failed to hash {}: {:?}", file_path, e),
+    }
+    let recording = state.db.get_recording(&local_id).await?;
+    LibraryEvent::Added(recording.clone()).emit(app);
+    Ok(recording)
+}
+
+/// Payload of `recording-split`: the recording continues in `path`, and the
+/// file before it was saved as `finished`.
+#[derive(serde::Serialize, Clone)]
+struct RecordingSplit {
+    path: String,
+    finished: CachedRecording,
+}
+
+/// Registers each file closed by a silence split until the recording stops.
+async fn register_split_segments(app: AppHandle, mut segments: tokio::sync::mpsc::UnboundedReceiver<SplitSegment>) {
+    while let Some(segment) = segments.recv().await {
+        let (source_app, mic_only) = {
+            let recorder = app.state::<AppState>().recorder.lock().await;
+            (recorder.source_app(), recorder.is_mic_only())
+        };
+        match register_draft(&app, &segment.path, segment.duration_sec, source_app, mic_only).await {
+            Ok(finished) => {
+                let path = segment.next_path.to_string_lossy().to_string();
+                let _ = app.emit("recording-split", RecordingSplit { path, finished });
+            }
+            Err(e) => eprintln!("Failed to save split recording {}: {:?}", segment.path.display(), e),
+        }
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
 struct RecordingResult {
     file_path: String,
     folder_path: String,
     duration_sec: f64,
+    format: OutputFormat,
 }
 
-#[derive(serde::Serialize)]
-struct RecordingStatus {
-    is_recording: bool,
-    is_paused: bool,
-    start_time_ms: Option<u64>,
+/// Running meeting apps (built-in plus `meeting_app_bundle_ids`) to capture.
+#[tauri::command]
+async fn get_running_meeting_apps_command(app_handle: AppHandle) -> Result<Vec<RunnableApp>, AppError> {
+    let extra = app_handle.state::<AppState>().settings.read().await.meeting_app_bundle_ids.clone();
+    Ok(discovery::get_running_meeting_apps(&extra).await?)
+}
+
+#[tauri::command]
+async fn get_all_running_apps_command() -> Result<Vec<RunnableApp>, AppError> {
+    Ok(discovery::get_all_running_apps().await?)
+}
+
+/// Sets the gain of `source` ("mic" or "system"), live if recording, and saves it.
+#[tauri::command]
+async fn set_gain_command(source: String, gain: f32, app_handle: AppHandle) -> Result<(), AppError> {
+    if !gain.is_finite() || !(0.0..=MAX_GAIN).contains(&gain) {
+        return Err(AppError::Validation(format!("Gain must be between 0 and {}", MAX_GAIN)));
+    }
+
+    let state = app_handle.state::<AppState>();
+    let gains = state.recorder.lock().await.gains();
+    let is_mic = match source.as_str() {
+        "mic" => true,
+        "system" => false,
+        other => return Err(AppError::Validation(format!("Unknown audio source: {}", other))),
+    };
+    if is_mic {
+        gains.set_mic(gain);
+    } else {
+        gains.set_sys(gain);
+    }
+
+    let apply = |settings: &mut Settings| {
+        if is_mic {
+            settings.mic_gain = gain;
+        } else {
+            settings.system_gain = gain;
+        }
+    };
+    apply(&mut *state.settings.write().await);
+    StorageService::update_settings(apply)
 }
 
 #[tauri::command]
 async fn get_recording_status_command(app_handle: AppHandle) -> Result<RecordingStatus, AppError> {
     let state = app_handle.state::<AppState>();
     let recorder = state.recorder.lock().await;
-    let (is_recording, is_paused, start_time_ms) = recorder.get_status();
-    Ok(RecordingStatus {
-        is_recording,
-        is_paused,
-        start_time_ms,
-    })
+    Ok(recorder.status())
 }
 
 #[tauri::command]
 async fn pause_recording_command(app_handle: AppHandle) -> Result<(), AppError> {
     let state = app_handle.state::<AppState>();
-    let recorder = state.recorder.lock().await;
-    recorder.pause_recording();
+    state.recorder.lock().await.pause_recording();
     Ok(())
 }
 
 #[tauri::command]
 async fn resume_recording_command(app_handle: AppHandle) -> Result<(), AppError> {
     let state = app_handle.state::<AppState>();
-    let recorder = state.recorder.lock().await;
-    recorder.resume_recording();
+    state.recorder.lock().await.resume_recording();
     Ok(())
 }
 
@@ -114,6 +257,11 @@ async fn get_microphones_command() -> Result<Vec<(String, String)>, AppError> {
     Ok(AudioRecorder::get_microphones())
 }
 
+#[tauri::command]
+async fn get_device_capabilities_command(device_name: String) -> Result<DeviceCapabilities, AppError> {
+    AudioRecorder::get_device_capabilities(&device_name).map_err(AppError::Audio)
+}
+
 #[tauri::command]
 async fn switch_microphone_command(device_name: String, app_handle: AppHandle) -> Result<(), AppError> {
     let state = app_handle.state::<AppState>();
@@ -136,24 +284,133 @@ async fn add_recording_command(file_path: String, duration_sec: f64, app_handle:
     let file_path_buf = PathBuf::from(&file_path);
     let file_name = file_path_buf.file_name().unwrap_or_default().to_string_lossy().to_string();
     
-    let recording = state.db.create_draft(file_name, duration_sec, file_path).await?;
+    // Importing a file we already track returns the existing entry
+    let signature = integrity::content_signature(&file_path_buf)?;
+    if let Some(existing) = find_duplicate(&state.db, &file_path_buf, &signature).await? {
+        return Ok(existing);
+    }
+
+    let recording = state.db.create_draft(file_name, duration_sec, file_path, None).await?;
+    state.db.set_content_signature(&recording.local_id, &signature).await?;
+    let recording = state.db.get_recording(&recording.local_id).await?;
     
-    use tauri::Emitter;
-    let _ = app_handle.emit("recording-added", &recording);
+    LibraryEvent::Added(recording.clone()).emit(&app_handle);
     
     Ok(recording)
 }
 
+/// Looks up recordings with the same content signature and confirms a match
+/// with the full hash, which is computed (and cached) only on a collision.
+async fn find_duplicate(db: &DatabaseService, path: &std::path::Path, signature: &str) -> Result<Option<CachedRecording>, AppError> {
+    let candidates = db.find_by_content_signature(signature).await?;
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let hash = integrity::hash_file(path)?;
+    for candidate in candidates {
+        let candidate_hash = match candidate.file_hash.clone() {
+            Some(h) => h,
+            None => {
+                let Some(candidate_path) = candidate.local_file_path.as_ref().or(candidate.local_audio_path.as_ref()) else {
+                    continue;
+                };
+                let Ok(h) = integrity::hash_file(std::path::Path::new(candidate_path)) else {
+                    continue;
+                };
+                db.set_file_hash(&candidate.local_id, &h).await?;
+                h
+            }
+        };
+        if candidate_hash == hash {
+            return Ok(Some(candidate));
+        }
+    }
+
+    Ok(None)
+}
+
+/// `tags` keeps only recordings carrying every one of the given tags.
+#[tauri::command]
+async fn get_recordings_command(source_app: Option<String>, tags: Option<Vec<String>>, app_handle: AppHandle) -> Result<Vec<CachedRecording>, AppError> {
+    let state = app_handle.state::<AppState>();
+    let mut recordings = state.db.get_recordings_paginated(0, i64::MAX, SortField::CreatedAt, SortOrder::Desc).await?;
+    if let Some(source_app) = source_app {
+        recordings.retain(|r| r.source_app.as_deref() == Some(source_app.as_str()));
+    }
+    for tag in tags.unwrap_or_default() {
+        let tagged = state.db.recordings_with_tag(&tag).await?;
+        recordings.retain(|r| tagged.contains(&r.local_id));
+    }
+    Ok(recordings)
+}
+
+/// A page of the library with the total count, for paged lists.
+#[derive(serde::Serialize)]
+struct RecordingsPage {
+    recordings: Vec<CachedRecording>,
+    total: i64,
+    page: u32,
+    limit: u32,
+}
+
+/// `page` starts at 1. Sorted newest first unless `sort`/`order` say otherwise.
+#[tauri::command]
+async fn get_recordings_page_command(page: u32, limit: u32, sort: Option<SortField>, order: Option<SortOrder>, app_handle: AppHandle) -> Result<RecordingsPage, AppError> {
+    if page == 0 || limit == 0 {
+        return Err(AppError::Validation("page and limit must be at least 1".to_string()));
+    }
+    let state = app_handle.state::<AppState>();
+    let offset = (page as i64 - 1) * limit as i64;
+    let recordings = state.db.get_recordings_paginated(offset, limit as i64, sort.unwrap_or_default(), order.unwrap_or_default()).await?;
+    let total = state.db.count_recordings().await?;
+    Ok(RecordingsPage { recordings, total, page, limit })
+}
+
+#[tauri::command]
+async fn add_tag_command(local_id: String, name: String, app_handle: AppHandle) -> Result<Tag, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.get_recording(&local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+    state.db.add_tag(&local_id, &name).await
+}
+
 #[tauri::command]
-async fn get_recordings_command(app_handle: AppHandle) -> Result<Vec<CachedRecording>, AppError> {
+async fn remove_tag_command(local_id: String, name: String, app_handle: AppHandle) -> Result<(), AppError> {
     let state = app_handle.state::<AppState>();
-    Ok(state.db.get_all_recordings().await?)
+    state.db.remove_tag(&local_id, &name).await
 }
 
 #[tauri::command]
-async fn delete_recording_entry_command(local_id: String, app_handle: AppHandle) -> Result<(), AppError> {
+async fn get_tags_for_recording_command(local_id: String, app_handle: AppHandle) -> Result<Vec<Tag>, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.get_tags_for_recording(&local_id).await
+}
+
+#[tauri::command]
+async fn list_all_tags_command(app_handle: AppHandle) -> Result<Vec<Tag>, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.list_all_tags().await
+}
+
+#[tauri::command]
+async fn get_recordings_by_tag_command(name: String, app_handle: AppHandle) -> Result<Vec<CachedRecording>, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.get_recordings_by_tag(&name).await
+}
+
+/// With `delete_includes_remote` the server job is deleted first; if that
+/// fails nothing is deleted locally either.
+#[tauri::command]
+async fn delete_recording_entry_command(local_id: String, delete_includes_remote: Option<bool>, app_handle: AppHandle) -> Result<(), AppError> {
     let state = app_handle.state::<AppState>();
     let recording = state.db.get_recording(&local_id).await?;
+
+    if delete_includes_remote.unwrap_or(false) {
+        if let Some(remote_job_id) = recording.remote_job_id.as_deref() {
+            state.sync.delete_remote(remote_job_id).await?;
+        }
+    }
     
     // Attempt to delete local file if it exists
     if let Some(path_str) = recording.local_file_path {
@@ -164,34 +421,155 @@ async fn delete_recording_entry_command(local_id: String, app_handle: AppHandle)
     }
     
     state.db.delete_recording(&local_id).await?;
+    LibraryEvent::Deleted { local_id: Some(local_id), remote_job_id: recording.remote_job_id }.emit(&app_handle);
     Ok(())
 }
 
+/// Moves a recording to the trash. Its files are kept until it's restored
+/// or purged after `trash_retention_days`.
 #[tauri::command]
-async fn check_connection_command(url: String, api_key: String) -> Result<bool, AppError> {
-    let client = reqwest::Client::new();
-    let base_url = url.trim_end_matches('/');
-    let endpoint = format!("{}/api/v1/transcription/models", base_url);
-    
-    let resp = client.get(&endpoint)
-        .header("X-API-Key", &api_key)
-        .send()
-        .await?;
-        
-    Ok(resp.status().is_success())
+async fn trash_recording_command(local_id: String, app_handle: AppHandle) -> Result<(), AppError> {
+    let state = app_handle.state::<AppState>();
+    let recording = state.db.get_recording(&local_id).await?;
+    state.db.soft_delete_recording(&local_id).await?;
+    LibraryEvent::Deleted { local_id: Some(local_id), remote_job_id: recording.remote_job_id }.emit(&app_handle);
+    Ok(())
 }
 
 #[tauri::command]
-async fn save_settings_command(settings: Settings, app_handle: AppHandle) -> Result<(), AppError> {
-    settings.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+async fn restore_recording_command(local_id: String, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.restore_recording(&local_id).await?;
+    let recording = state.db.get_recording(&local_id).await?;
+    LibraryEvent::Added(recording.clone()).emit(&app_handle);
+    Ok(recording)
+}
 
-    // Load old settings to check for path change
-    let old_settings = load_settings_command(app_handle.clone()).await.unwrap_or(Settings {
-        scriberr_url: "".to_string(),
-        api_key: "".to_string(),
-        output_path: "".to_string(),
-        last_sync_timestamp: None,
-    });
+#[tauri::command]
+async fn get_trashed_recordings_command(app_handle: AppHandle) -> Result<Vec<CachedRecording>, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.get_trashed_recordings().await
+}
+
+/// Full-text search over titles, summaries and transcripts, best match first.
+#[tauri::command]
+async fn search_recordings_command(query: String, app_handle: AppHandle) -> Result<Vec<SearchResult>, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.search_recordings(&query).await
+}
+
+#[tauri::command]
+async fn find_duplicates_command(app_handle: AppHandle) -> Result<Vec<DuplicateGroup>, AppError> {
+    let state = app_handle.state::<AppState>();
+    Ok(duplicates::find_groups(state.db.get_all_recordings().await?))
+}
+
+/// Renames a recording, on the server as well once it's been uploaded.
+#[tauri::command]
+async fn rename_recording_command(local_id: String, title: String, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.sync.rename_recording(&local_id, &title).await
+}
+
+/// Keeps `keep_local_id` and deletes `remove_local_ids`, along with their local
+/// files and, when `delete_remote` is set, their server jobs. A local copy of
+/// the audio moves to the kept recording if it has none of its own.
+#[tauri::command]
+async fn merge_duplicates_command(keep_local_id: String, remove_local_ids: Vec<String>, delete_remote: Option<bool>, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
+    if remove_local_ids.contains(&keep_local_id) {
+        return Err(AppError::Validation("The kept recording can't also be removed".to_string()));
+    }
+
+    let state = app_handle.state::<AppState>();
+    let mut keep = state.db.get_recording(&keep_local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+
+    for local_id in remove_local_ids {
+        let duplicate = state.db.get_recording(&local_id).await
+            .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+
+        // Two rows can point at the same remote job; only delete it if the kept one doesn't
+        if delete_remote.unwrap_or(false) {
+            if let Some(remote_id) = duplicate.remote_job_id.as_deref().filter(|id| keep.remote_job_id.as_deref() != Some(*id)) {
+                state.sync.delete_remote(remote_id).await?;
+            }
+        }
+
+        let mut adopted = None;
+        if keep.local_audio_path.is_none() && keep.local_file_path.is_none() {
+            adopted = duplicate.local_audio_path.clone().or(duplicate.local_file_path.clone());
+        }
+        for path in [&duplicate.local_file_path, &duplicate.local_audio_path].into_iter().flatten() {
+            let shared = [&keep.local_file_path, &keep.local_audio_path].into_iter().flatten().any(|p| p == path);
+            if !shared && adopted.as_ref() != Some(path) {
+                let _ = tokio::fs::remove_file(path).await;
+            }
+        }
+
+        state.db.delete_recording(&local_id).await?;
+        LibraryEvent::Deleted { local_id: Some(local_id), remote_job_id: duplicate.remote_job_id }.emit(&app_handle);
+
+        if let Some(path) = adopted {
+            state.db.set_local_audio_path(&keep.local_id, Some(path)).await?;
+            keep = state.db.get_recording(&keep.local_id).await?;
+        }
+    }
+
+    LibraryEvent::Updated(keep.clone()).emit(&app_handle);
+    Ok(keep)
+}
+
+/// Reachability as of the last heartbeat, for the offline indicator.
+#[tauri::command]
+async fn is_online_command(app_handle: AppHandle) -> Result<bool, AppError> {
+    let state = app_handle.state::<AppState>();
+    Ok(state.online.load(Ordering::SeqCst))
+}
+
+/// Tests `url` and `api_key` for the settings screen, telling a bad key
+/// (401/403) apart from DNS, connection and wrong-server failures.
+#[tauri::command]
+async fn check_connection_command(url: String, api_key: String) -> Result<ConnectionCheck, AppError> {
+    let diagnosis = CompatService::diagnose(&normalize_base_url(&url), &api_key).await?;
+    Ok(ConnectionCheck::from(diagnosis))
+}
+
+/// Transcription models offered by the configured server.
+#[tauri::command]
+async fn list_models_command(app_handle: AppHandle) -> Result<Vec<TranscriptionModel>, AppError> {
+    let settings = load_settings_command(app_handle).await?;
+    if settings.scriberr_url.is_empty() {
+        return Err(AppError::Validation("Settings not configured".to_string()));
+    }
+    CompatService::list_models(&http::build_client()?, &settings.scriberr_url, &settings.api_key).await
+}
+
+/// Tests `url` and `api_key` before they're saved: reachability, status,
+/// server version, models and latency, so a failure can be told apart as
+/// DNS, auth or the wrong server.
+#[tauri::command]
+async fn diagnose_connection_command(url: String, api_key: String) -> Result<ConnectionDiagnosis, AppError> {
+    CompatService::diagnose(&normalize_base_url(&url), &api_key).await
+}
+
+#[tauri::command]
+async fn check_api_compatibility_command(app_handle: AppHandle) -> Result<ApiCompatibility, AppError> {
+    let settings = load_settings_command(app_handle).await?;
+    if settings.scriberr_url.is_empty() {
+        return Err(AppError::Validation("Settings not configured".to_string()));
+    }
+    CompatService::check(&http::build_client()?, &settings.scriberr_url, &settings.api_key).await
+}
+
+/// Applies what the settings and onboarding screens edit on top of the
+/// settings on disk, so values other commands saved since the screen loaded
+/// (gains, preferred mic, the sync baseline...) are kept.
+#[tauri::command]
+async fn save_settings_command(update: SettingsUpdate, app_handle: AppHandle) -> Result<Option<MigrationReport>, AppError> {
+    let old_settings = load_settings_command(app_handle.clone()).await.unwrap_or_default();
+    let mut settings = old_settings.clone();
+    update.apply(&mut settings);
+    settings.validate().map_err(|e| AppError::Validation(e.to_string()))?;
 
     let default_dir = app_handle.path().document_dir().unwrap_or(PathBuf::from("/"));
     let default_path = default_dir.join("ScriberrRecordings").to_string_lossy().to_string();
@@ -208,21 +586,40 @@ async fn save_settings_command(settings: Settings, app_handle: AppHandle) -> Res
         settings.output_path.clone()
     };
 
+    let mut report = None;
     if old_path_str != new_path_str {
         // Delegate migration logic
-        StorageService::migrate_recordings(&old_path_str, &new_path_str)?;
-        
+        let migration = StorageService::migrate_recordings(&old_path_str, &new_path_str)?;
+
+        if !migration.complete {
+            eprintln!(
+                "Partial migration from {}: {} moved, {} failed, {} left behind",
+                old_path_str, migration.moved.len(), migration.failed.len(), migration.remaining.len()
+            );
+            // Keep looking in the old folder for the files that stayed there
+            if !settings.legacy_output_paths.contains(&old_path_str) {
+                settings.legacy_output_paths.push(old_path_str.clone());
+            }
+        }
+        settings.legacy_output_paths.retain(|p| p != &new_path_str);
+
         // Update output folder in state
         let state = app_handle.state::<AppState>();
         *state.output_folder.lock().await = PathBuf::from(&new_path_str);
+        report = Some(migration);
     }
 
-    // Always update global settings state
+    // Always update global settings state. The proxy holds the same lock and
+    // reads it per request, so a new server URL applies to it immediately.
     let state = app_handle.state::<AppState>();
     *state.settings.write().await = settings.clone();
-    
-    StorageService::save_settings(&settings)?;
-    Ok(())
+
+    let legacy_output_paths = settings.legacy_output_paths;
+    StorageService::update_settings(|stored| {
+        update.apply(stored);
+        stored.legacy_output_paths = legacy_output_paths;
+    })?;
+    Ok(report)
 }
 
 #[tauri::command]
@@ -231,65 +628,192 @@ async fn load_settings_command(app_handle: AppHandle) -> Result<Settings, AppErr
     Ok(StorageService::load_settings(Some(default_path))?)
 }
 
+/// Battery saver switch: pauses sync polling and upload-on-stop while enabled.
+/// Turning it off runs a delta sync right away instead of waiting for the next tick.
+#[tauri::command]
+async fn set_power_save_command(enabled: bool, app_handle: AppHandle) -> Result<(), AppError> {
+    let state = app_handle.state::<AppState>();
+    state.sync.set_power_save(enabled);
+
+    state.settings.write().await.power_save = enabled;
+    StorageService::update_settings(|settings| settings.power_save = enabled)?;
+    let _ = app_handle.emit("power-save-changed", enabled);
+
+    if !enabled {
+        let sync = state.sync.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = sync.perform_delta_sync().await {
+                eprintln!("Sync after leaving power save failed: {:?}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
 #[tauri::command]
-async fn upload_recording_command(local_id: String, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
+async fn get_sync_queue_command(app_handle: AppHandle) -> Result<SyncQueue, AppError> {
     let state = app_handle.state::<AppState>();
-    let recording = state.sync.upload_recording(&local_id).await?;
+    state.sync.queue().await
+}
+
+/// `model` picks the transcription model and is remembered as `last_model`;
+/// without it the server's default is used.
+#[tauri::command]
+async fn upload_recording_command(local_id: String, model: Option<String>, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
+    let state = app_handle.state::<AppState>();
+    let recording = state.sync.upload_recording(&local_id, model.as_deref()).await?;
+
+    if model.is_some() {
+        state.settings.write().await.last_model = model.clone();
+        StorageService::update_settings(|settings| settings.last_model = model)?;
+    }
     Ok(recording)
 }
 
+/// Uploads every pending draft in turn, e.g. after being offline, emitting
+/// `batch-upload-progress` after each one.
+#[tauri::command]
+async fn upload_all_pending_command(app_handle: AppHandle) -> Result<BatchUploadSummary, AppError> {
+    app_handle.state::<AppState>().sync.upload_all_pending().await
+}
+
+/// Cancels an upload started by `upload_recording_command` (or upload on stop).
+#[tauri::command]
+async fn abort_upload_command(local_id: String, app_handle: AppHandle) -> Result<(), AppError> {
+    let state = app_handle.state::<AppState>();
+    if !state.sync.abort_upload(&local_id) {
+        return Err(AppError::NotFound("No upload in progress for this recording".to_string()));
+    }
+    Ok(())
+}
+
 #[tauri::command]
 async fn download_recording_command(local_id: String, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
     let state = app_handle.state::<AppState>();
-    let settings = load_settings_command(app_handle.clone()).await?;
+    let folder = state.output_folder.lock().await.clone();
+    state.sync.download_recording(&local_id, &folder).await
+}
 
-    if settings.scriberr_url.is_empty() {
-        return Err(AppError::Validation("Settings not configured".to_string()));
+#[tauri::command]
+async fn inspect_recording_command(local_id: String, app_handle: AppHandle) -> Result<WavInfo, AppError> {
+    let state = app_handle.state::<AppState>();
+    let recording = state.db.get_recording(&local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+
+    let path = recording.local_file_path.as_ref()
+        .or(recording.local_audio_path.as_ref())
+        .map(PathBuf::from)
+        .filter(|p| p.exists())
+        .ok_or(AppError::NotFound("No local audio file for this recording".to_string()))?;
+
+    integrity::inspect_audio(&path)
+}
+
+#[derive(serde::Serialize)]
+struct RepairResult {
+    repair_needed: bool,
+    repaired: bool,
+    recording: CachedRecording,
+}
+
+#[tauri::command]
+async fn repair_download_command(local_id: String, app_handle: AppHandle) -> Result<RepairResult, AppError> {
+    let state = app_handle.state::<AppState>();
+    let recording = state.db.get_recording(&local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+
+    let path_str = recording.local_audio_path.clone()
+        .ok_or(AppError::Validation("Recording has no local audio copy".to_string()))?;
+    let path = PathBuf::from(path_str);
+
+    let valid = integrity::verify_audio_file(&path, recording.file_hash.as_deref())?;
+    if valid {
+        return Ok(RepairResult { repair_needed: false, repaired: false, recording });
+    }
+
+    if recording.remote_audio_url.is_none() {
+        return Err(AppError::Validation("Local copy is corrupt and no remote audio URL is available".to_string()));
+    }
+
+    if path.exists() {
+        tokio::fs::remove_file(&path).await?;
     }
 
+    let recording = download_recording_command(local_id, app_handle.clone()).await?;
+    Ok(RepairResult { repair_needed: true, repaired: true, recording })
+}
+
+/// Transcribes the local audio with whisper.cpp, for when the server can't be
+/// reached. A recording that already has the server's transcript keeps it.
+#[tauri::command]
+async fn transcribe_local_command(local_id: String, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
+    let state = app_handle.state::<AppState>();
     let recording = state.db.get_recording(&local_id).await
         .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
 
-    // Check if valid URL exists
-    let url = recording.remote_audio_url.clone().ok_or(AppError::Validation("No remote audio URL available".to_string()))?;
-    
-    // Determine output path
-    let folder = state.output_folder.lock().await.clone();
-    let filename = format!("{}.wav", recording.title.trim().replace("/", "_"));
-    let final_path = folder.join(filename);
-
-    // Download
-    let client = reqwest::Client::new();
-    let resp = client.get(&url)
-        .header("X-API-Key", &settings.api_key)
-        .send()
-        .await?;
-    
-    if !resp.status().is_success() {
-         return Err(AppError::Network(format!("Download failed: {}", resp.status())));
+    if SyncStatus::from(recording.sync_status.clone()) == SyncStatus::CompletedSynced && recording.transcript_text.is_some() {
+        return Err(AppError::Validation("Recording already has the server's transcript".to_string()));
     }
 
-    let bytes = resp.bytes().await?;
-    tokio::fs::write(&final_path, bytes).await.map_err(|e| AppError::Io(e.to_string()))?;
+    let audio = recording.local_file_path.as_ref()
+        .or(recording.local_audio_path.as_ref())
+        .map(PathBuf::from)
+        .filter(|p| p.exists())
+        .ok_or(AppError::NotFound("No local audio file for this recording".to_string()))?;
 
-    // Update DB (set local path and keep offline)
-    // We should probably add specific method for keep_offline or reuse set_local_audio_path and add keep_offline updates
-    // For now I'll use set_local_audio_path and assume it implies keep_offline logic I added
-    // Wait, my db implementation of set_local_audio_path only updates path. I should update keep_offline too.
-    // I'll execute raw query here or rely on update which I should probably enhance.
-    // Since I can't easily change db.rs in this same call, I'll execute a raw update query here via db pool if I could access it, or just use what I have.
-    // Actually set_local_audio_path in db.rs is what I have. I should probably modify it to also set keep_offline=true. 
-    // But I'll do that in db.rs separately if needed. For now I'll accept just setting path.
-    
-    // UPDATE: I'll manually run a query to set keep_offline=true too, via a new method or assume the path presence implies availability. 
-    // But architecture said keep_offline flag.
-    // I'll update db.rs in a separate step to be cleaner. For now just set path.
-    state.db.set_local_audio_path(&local_id, Some(final_path.to_string_lossy().to_string())).await?;
-    
-    // Hack: Manually update keep_offline since I forgot to add a method for it
-    // Or I can add a specific method to DB service next.
-    
-    Ok(state.db.get_recording(&local_id).await?)
+    let transcriber = WhisperCpp::from_settings(&*state.settings.read().await)?;
+    let text = tauri::async_runtime::spawn_blocking(move || transcriber.transcribe(&audio)).await
+        .map_err(|e| AppError::Unexpected(e.to_string()))??;
+    if text.is_empty() {
+        return Err(AppError::Validation("whisper.cpp produced no transcript".to_string()));
+    }
+
+    state.db.set_local_transcript(&local_id, &text).await?;
+    let updated = state.db.get_recording(&local_id).await?;
+    LibraryEvent::Updated(updated.clone()).emit(&app_handle);
+    Ok(updated)
+}
+
+/// Pins or unpins a recording's audio on this machine. Pinning one that only
+/// exists on the server starts a download in the background; unpinning one the
+/// server has finished with deletes the downloaded copy.
+#[tauri::command]
+async fn set_keep_offline_command(local_id: String, keep: bool, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
+    let state = app_handle.state::<AppState>();
+    let recording = state.db.get_recording(&local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+
+    state.db.set_keep_offline(&local_id, keep).await?;
+
+    let has_local_copy = [&recording.local_file_path, &recording.local_audio_path]
+        .into_iter()
+        .flatten()
+        .any(|p| PathBuf::from(p).exists());
+    if keep && !has_local_copy && recording.remote_job_id.is_some() {
+        let sync = state.sync.clone();
+        let folder = state.output_folder.lock().await.clone();
+        let local_id = local_id.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = sync.download_recording(&local_id, &folder).await {
+                eprintln!("Download for keep-offline failed for {}: {:?}", local_id, e);
+            }
+        });
+    }
+
+    // The server still has the audio, so the copy can be streamed or fetched again
+    let synced = SyncStatus::from(recording.sync_status.clone()) == SyncStatus::CompletedSynced;
+    if !keep && synced && recording.remote_audio_url.is_some() {
+        if let Some(path) = recording.local_audio_path.as_ref().map(PathBuf::from) {
+            if path.exists() {
+                tokio::fs::remove_file(&path).await?;
+            }
+            state.db.set_local_audio_path(&local_id, None).await?;
+        }
+    }
+
+    let updated = state.db.get_recording(&local_id).await?;
+    LibraryEvent::Updated(updated.clone()).emit(&app_handle);
+    Ok(updated)
 }
 
 #[tauri::command]
@@ -307,7 +831,146 @@ async fn remove_download_command(local_id: String, app_handle: AppHandle) -> Res
 
     state.db.set_local_audio_path(&local_id, None).await?;
     
-    Ok(state.db.get_recording(&local_id).await?)
+    let updated = state.db.get_recording(&local_id).await?;
+    LibraryEvent::Updated(updated.clone()).emit(&app_handle);
+    Ok(updated)
+}
+
+#[tauri::command]
+async fn get_segments_command(local_id: String, app_handle: AppHandle) -> Result<Vec<LabeledSegment>, AppError> {
+    let state = app_handle.state::<AppState>();
+    let recording = state.db.get_recording(&local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+
+    let segments = transcript::parse_segments(&recording);
+    let maps = state.db.ensure_speaker_maps(&local_id, &transcript::speaker_labels(&segments)).await?;
+    Ok(transcript::label_segments(segments, &maps))
+}
+
+#[tauri::command]
+async fn get_segments_by_speaker_command(local_id: String, speaker: String, app_handle: AppHandle) -> Result<Vec<LabeledSegment>, AppError> {
+    let segments = get_segments_command(local_id, app_handle).await?;
+    Ok(transcript::segments_by_speaker(segments, &speaker))
+}
+
+/// Writes the transcript to `path`, which the frontend gets from a save
+/// dialog. Speakers appear under the names given to them; the document
+/// formats fall back to the plain transcript when there are no segments.
+#[tauri::command]
+async fn export_transcript_command(local_id: String, format: ExportFormat, path: String, app_handle: AppHandle) -> Result<(), AppError> {
+    let recording = app_handle.state::<AppState>().db.get_recording(&local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+    let segments = get_segments_command(local_id, app_handle).await?;
+    let text = transcript::plain_text(&recording).filter(|t| !t.trim().is_empty());
+
+    if segments.is_empty() && (format.is_subtitle() || text.is_none()) {
+        return Err(AppError::Validation(format!("Recording has no transcript to export as {}", format.extension())));
+    }
+
+    let contents = match format {
+        ExportFormat::Srt => export::to_srt(&segments),
+        ExportFormat::Vtt => export::to_vtt(&segments),
+        ExportFormat::Txt => export::to_txt(&segments, text.as_deref()),
+        ExportFormat::Md => export::to_markdown(&recording.title, recording.summary_text.as_deref(), &segments, text.as_deref()),
+    };
+    tokio::fs::write(&path, contents).await?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_transcript_preview_command(local_id: String, max_chars: Option<u32>, app_handle: AppHandle) -> Result<Option<String>, AppError> {
+    let state = app_handle.state::<AppState>();
+    let max_chars = max_chars.unwrap_or(DEFAULT_PREVIEW_CHARS).max(1) as usize;
+
+    // One extra character tells us whether the excerpt was cut
+    let excerpt = state.db.get_transcript_excerpt(&local_id, max_chars as i64 + 1).await?;
+    Ok(excerpt
+        .map(|text| transcript::preview(&text, max_chars))
+        .filter(|text| !text.is_empty()))
+}
+
+/// Gives transcripts without timing a segment list. The server has no
+/// alignment endpoint yet, so this writes a single segment covering the audio.
+#[tauri::command]
+async fn align_transcript_command(local_id: String, app_handle: AppHandle) -> Result<CachedRecording, AppError> {
+    let state = app_handle.state::<AppState>();
+    let recording = state.db.get_recording(&local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+
+    if !transcript::parse_segments(&recording).is_empty() {
+        return Ok(recording);
+    }
+
+    let text = transcript::plain_text(&recording)
+        .filter(|t| !t.trim().is_empty())
+        .ok_or(AppError::Validation("Recording has no transcript to align".to_string()))?;
+
+    let local_audio = recording.local_file_path.as_ref()
+        .or(recording.local_audio_path.as_ref())
+        .map(PathBuf::from)
+        .filter(|p| p.exists());
+    if local_audio.is_none() && recording.remote_audio_url.is_none() {
+        return Err(AppError::Validation("Cannot align a transcript without audio".to_string()));
+    }
+
+    // Prefer the real length of the file over what the DB recorded
+    let duration_sec = local_audio
+        .and_then(|p| integrity::inspect_audio(&p).ok())
+        .map(|info| info.duration_sec)
+        .unwrap_or(recording.duration_sec);
+
+    let segments = transcript::single_segment(&text, duration_sec);
+    state.db.set_individual_transcripts(&local_id, &serde_json::to_string(&segments)?).await?;
+
+    let updated = state.db.get_recording(&local_id).await?;
+    LibraryEvent::Updated(updated.clone()).emit(&app_handle);
+    Ok(updated)
+}
+
+/// Display names and colors of the recording's speakers, by original label.
+#[tauri::command]
+async fn get_speaker_map_command(local_id: String, app_handle: AppHandle) -> Result<Vec<CachedSpeakerMap>, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.get_speaker_maps(&local_id).await
+}
+
+/// Renames `speaker_label` (e.g. "Speaker 1") for this recording only. The
+/// names stay local; the server has no endpoint to receive them.
+#[tauri::command]
+async fn set_speaker_name_command(local_id: String, speaker_label: String, display_name: String, app_handle: AppHandle) -> Result<CachedSpeakerMap, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.get_recording(&local_id).await
+        .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+    state.db.set_speaker_name(&local_id, &speaker_label, &display_name).await
+}
+
+/// Restores the original labels and colors of every speaker in the recording.
+#[tauri::command]
+async fn reset_speaker_map_command(local_id: String, app_handle: AppHandle) -> Result<(), AppError> {
+    let state = app_handle.state::<AppState>();
+    state.db.delete_speaker_map(&local_id).await
+}
+
+#[tauri::command]
+async fn update_speaker_command(local_id: String, speaker_label: String, display_name: Option<String>, color: Option<String>, app_handle: AppHandle) -> Result<CachedSpeakerMap, AppError> {
+    let state = app_handle.state::<AppState>();
+    if let Some(ref color) = color {
+        if !transcript::is_valid_color(color) {
+            return Err(AppError::Validation(format!("Invalid color '{}'", color)));
+        }
+    }
+    if display_name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        return Err(AppError::Validation("Display name cannot be empty".to_string()));
+    }
+
+    state.db.update_speaker_map(&local_id, &speaker_label, display_name.as_deref().map(str::trim), color.as_deref()).await
+}
+
+#[tauri::command]
+async fn get_storage_usage_command(app_handle: AppHandle) -> Result<StorageUsage, AppError> {
+    let state = app_handle.state::<AppState>();
+    let quota = state.settings.read().await.storage_quota_bytes;
+    QuotaService::usage(&state.db, quota).await
 }
 
 #[tauri::command]
@@ -317,18 +980,66 @@ async fn sync_now_command(app_handle: AppHandle) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Refetches the whole library from the server. Progress arrives as
+/// `sync_started`/`sync_progress`/`sync_completed` library events.
+#[tauri::command]
+async fn full_sync_command(app_handle: AppHandle) -> Result<(), AppError> {
+    let state = app_handle.state::<AppState>();
+    state.sync.perform_full_sync().await
+}
+
+/// Fetches jobs changed since the last sync, with the same events as a full sync.
+#[tauri::command]
+async fn delta_sync_command(app_handle: AppHandle) -> Result<(), AppError> {
+    let state = app_handle.state::<AppState>();
+    state.sync.perform_delta_sync().await
+}
+
+/// Wakes the background sync loop for a poll now and returns immediately;
+/// results arrive as library events.
+#[tauri::command]
+async fn trigger_sync_now_command(app_handle: AppHandle) -> Result<(), AppError> {
+    let state = app_handle.state::<AppState>();
+    state.sync.trigger_sync();
+    Ok(())
+}
+
 #[tauri::command]
 async fn check_file_exists_command(filename: String, app_handle: AppHandle) -> Result<bool, AppError> {
     let state = app_handle.state::<AppState>();
     let folder = state.output_folder.lock().await.clone();
-    // Check both with and without extension to be safe, or just normalize as we do in start
-    let name = if filename.to_lowercase().ends_with(".wav") { 
-        filename 
-    } else { 
-        format!("{}.wav", filename) 
-    };
-    let path = folder.join(name);
-    Ok(path.exists())
+    // Normalize as we do in start, with whatever format new recordings use
+    let format = state.settings.read().await.output_format;
+    let name = format!("{}.{}", paths::sanitize_filename(recording_stem(&filename)), format.extension());
+    if folder.join(&name).exists() {
+        return Ok(true);
+    }
+
+    // Files stranded by a partial folder migration
+    let settings = state.settings.read().await;
+    Ok(settings.legacy_output_paths.iter().any(|dir| PathBuf::from(dir).join(&name).exists()))
+}
+
+#[tauri::command]
+async fn schedule_recording_command(start_at: String, stop_after_sec: f64, config: ScheduledRecordingConfig, repeat_daily: Option<bool>) -> Result<ScheduledRecording, AppError> {
+    SchedulerService::add_schedule(start_at, stop_after_sec, repeat_daily.unwrap_or(false), config)
+}
+
+#[tauri::command]
+async fn list_scheduled_recordings_command() -> Result<Vec<ScheduledRecording>, AppError> {
+    SchedulerService::list_schedules()
+}
+
+#[tauri::command]
+async fn cancel_scheduled_recording_command(id: String) -> Result<(), AppError> {
+    SchedulerService::cancel_schedule(&id)
+}
+
+#[tauri::command]
+async fn get_output_folder_command(app_handle: AppHandle) -> Result<String, AppError> {
+    let state = app_handle.state::<AppState>();
+    let folder = state.output_folder.lock().await.clone();
+    Ok(folder.to_string_lossy().to_string())
 }
 
 #[tauri::command]
@@ -337,6 +1048,23 @@ async fn get_proxy_port_command(app_handle: AppHandle) -> Result<u16, AppError>
     Ok(state.proxy_port)
 }
 
+/// Deletes all remote audio cached by the proxy; returns the bytes freed.
+#[tauri::command]
+async fn clear_audio_cache_command(app_handle: AppHandle) -> Result<u64, AppError> {
+    let state = app_handle.state::<AppState>();
+    state.audio_cache.clear()
+}
+
+/// Local URL that streams a remote job's audio with the API key attached.
+#[tauri::command]
+async fn get_proxy_url_command(remote_job_id: String, app_handle: AppHandle) -> Result<String, AppError> {
+    if remote_job_id.is_empty() || remote_job_id.contains(['/', '?', '#']) {
+        return Err(AppError::Validation("Invalid job id".to_string()));
+    }
+    let state = app_handle.state::<AppState>();
+    Ok(ProxyService::stream_url(state.proxy_port, &remote_job_id))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -350,20 +1078,68 @@ pub fn run() {
             resume_recording_command, 
             get_microphones_command, 
             switch_microphone_command, 
+            get_device_capabilities_command,
             delete_recording_command,
             check_connection_command,
+            diagnose_connection_command,
+            list_models_command,
+            is_online_command,
+            check_api_compatibility_command,
             save_settings_command,
             load_settings_command,
+            set_power_save_command,
             add_recording_command,
             get_recordings_command,
+            get_recordings_page_command,
+            add_tag_command,
+            remove_tag_command,
+            get_tags_for_recording_command,
+            list_all_tags_command,
+            get_recordings_by_tag_command,
             delete_recording_entry_command,
+            trash_recording_command,
+            restore_recording_command,
+            get_trashed_recordings_command,
+            search_recordings_command,
+            find_duplicates_command,
+            merge_duplicates_command,
+            rename_recording_command,
             upload_recording_command,
+            upload_all_pending_command,
+            abort_upload_command,
+            get_running_meeting_apps_command,
+            get_all_running_apps_command,
             check_file_exists_command,
             get_recording_status_command,
+            set_gain_command,
             download_recording_command,
             remove_download_command,
+            set_keep_offline_command,
+            transcribe_local_command,
+            repair_download_command,
+            inspect_recording_command,
             sync_now_command,
-            get_proxy_port_command
+            trigger_sync_now_command,
+            full_sync_command,
+            delta_sync_command,
+            get_sync_queue_command,
+            get_storage_usage_command,
+            get_proxy_port_command,
+            get_proxy_url_command,
+            clear_audio_cache_command,
+            get_output_folder_command,
+            schedule_recording_command,
+            list_scheduled_recordings_command,
+            cancel_scheduled_recording_command,
+            get_segments_command,
+            get_segments_by_speaker_command,
+            export_transcript_command,
+            get_transcript_preview_command,
+            align_transcript_command,
+            get_speaker_map_command,
+            set_speaker_name_command,
+            reset_speaker_map_command,
+            update_speaker_command
         ])
         .setup(move |app| {
             // builder.mount_events(app); // removed specta mount
@@ -373,12 +1149,7 @@ pub fn run() {
             let default_output = documents_dir.join("ScriberrRecordings");
             
             // Try to load settings to get configured output path
-            let loaded_settings = StorageService::load_settings(None).unwrap_or(Settings {
-                scriberr_url: "".to_string(),
-                api_key: "".to_string(),
-                output_path: "".to_string(),
-                last_sync_timestamp: None,
-            });
+            let loaded_settings = StorageService::load_settings(None).unwrap_or_default();
             
             let output_folder = if !loaded_settings.output_path.is_empty() {
                 PathBuf::from(&loaded_settings.output_path)
@@ -386,12 +1157,13 @@ pub fn run() {
                 default_output.clone()
             };
             
+            let audio_cache = Arc::new(AudioCache::new(audio_cache_dir(&loaded_settings)));
             let settings_lock = Arc::new(RwLock::new(loaded_settings));
 
             // Start Proxy Service
             let (proxy_shutdown_tx, proxy_shutdown_rx) = tokio::sync::oneshot::channel();
             let proxy_port = tauri::async_runtime::block_on(async {
-               ProxyService::start(settings_lock.clone(), proxy_shutdown_rx).await
+               ProxyService::start(settings_lock.clone(), audio_cache.clone(), proxy_shutdown_rx).await
             }).expect("Failed to start proxy service");
             
             println!("Proxy server started on port: {}", proxy_port);
@@ -400,14 +1172,7 @@ pub fn run() {
                 let _ = std::fs::create_dir_all(&output_folder);
             }
 
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let toggle_i = MenuItem::with_id(app, "toggle", "Toggle Window", true, None::<&str>)?;
-            
-            let menu = Menu::with_items(app, &[
-                &toggle_i,
-                &PredefinedMenuItem::separator(app)?,
-                &quit_i,
-            ])?;
+            let menu = build_tray_menu(app.handle(), false, false)?;
 
             let app_handle = app.handle().clone();
              
@@ -425,10 +1190,12 @@ pub fn run() {
 
             // Start Sync Service
             let sync_service = Arc::new(SyncService::new(db.clone(), app_handle));
+            sync_service.set_power_save(tauri::async_runtime::block_on(settings_lock.read()).power_save);
             sync_service.start();
+            HeartbeatService::start(app.handle().clone(), settings_lock.clone(), sync_service.online_flag());
 
             let state = AppState {
-                recorder: Arc::new(Mutex::new(AudioRecorder::new())),
+                recorder: Arc::new(Mutex::new(AudioRecorder::new(app.handle().clone()))),
                 is_recording: Mutex::new(false),
                 output_folder: Mutex::new(output_folder.clone()),
                 current_recording_path: Mutex::new(None),
@@ -437,9 +1204,50 @@ pub fn run() {
                 settings: settings_lock,
                 proxy_port,
                 proxy_shutdown_tx: Mutex::new(Some(proxy_shutdown_tx)),
+                audio_cache,
+                online: sync_service.online_flag(),
+                recording_format: Mutex::new(OutputFormat::default()),
+                finalizing_on_exit: AtomicBool::new(false),
             };
             app.manage(state);
 
+            start_scheduler(app.handle().clone());
+
+            // Keep the tray's recording controls in sync with the recorder
+            let tray_handle = app.handle().clone();
+            app.listen("recording-state-changed", move |_| {
+                let app = tray_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    refresh_tray_menu(&app).await;
+                });
+            });
+
+            // Optionally end the recording instead of writing silence indefinitely
+            let stall_handle = app.handle().clone();
+            app.listen("system-audio-stalled", move |_| {
+                let app = stall_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let state = app.state::<AppState>();
+                    if !state.settings.read().await.stop_on_system_audio_stall {
+                        return;
+                    }
+                    if let Err(e) = stop_recording_command(app.clone(), None).await {
+                        eprintln!("Failed to stop stalled recording: {:?}", e);
+                    }
+                });
+            });
+
+            // The mixer gave up on writing (e.g. disk full); keep what was recorded
+            let error_handle = app.handle().clone();
+            app.listen("recording-error", move |_| {
+                let app = error_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = stop_recording_command(app.clone(), None).await {
+                        eprintln!("Failed to stop recording after a write error: {:?}", e);
+                    }
+                });
+            });
+
             let window = app.get_webview_window("main").unwrap();
 
             #[cfg(target_os = "macos")]
@@ -460,6 +1268,28 @@ pub fn run() {
                 .on_menu_event(move |app, event| {
                     match event.id.as_ref() {
                         "quit" => app.exit(0),
+                        "pause_resume" => {
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                let (_, is_paused, _) = app.state::<AppState>().recorder.lock().await.get_status();
+                                let result = if is_paused {
+                                    resume_recording_command(app.clone()).await
+                                } else {
+                                    pause_recording_command(app.clone()).await
+                                };
+                                if let Err(e) = result {
+                                    eprintln!("Tray pause/resume failed: {}", e);
+                                }
+                            });
+                        }
+                        "stop_recording" => {
+                            let app = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = stop_recording_command(app.clone(), None).await {
+                                    eprintln!("Tray stop failed: {}", e);
+                                }
+                            });
+                        }
                         "toggle" => {
                             if let Some(window) = app.get_webview_window("main") {
                                 if window.is_visible().unwrap_or(false) {
@@ -493,8 +1323,29 @@ pub fn run() {
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
-            if let tauri::RunEvent::ExitRequested { .. } = event {
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
                  let state = app_handle.state::<AppState>();
+                 // Quitting (tray, Cmd+Q or closing the last window) while recording
+                 // saves the recording first, then asks to exit again
+                 if state.finalizing_on_exit.load(Ordering::SeqCst) {
+                     api.prevent_exit();
+                     return;
+                 }
+                 if *state.is_recording.blocking_lock() {
+                     state.finalizing_on_exit.store(true, Ordering::SeqCst);
+                     api.prevent_exit();
+                     let app = app_handle.clone();
+                     tauri::async_runtime::spawn(async move {
+                         if let Err(e) = stop_recording_command(app.clone(), None).await {
+                             eprintln!("Failed to save recording on quit: {}", e);
+                         }
+                         let state = app.state::<AppState>();
+                         *state.is_recording.lock().await = false;
+                         state.finalizing_on_exit.store(false, Ordering::SeqCst);
+                         app.exit(0);
+                     });
+                     return;
+                 }
                  // Trigger proxy shutdown
                  let mut tx_guard = state.proxy_shutdown_tx.blocking_lock();
                  if let Some(tx) = tx_guard.take() {
@@ -505,7 +1356,77 @@ pub fn run() {
 }
 
 
-async fn toggle_recording(app: &AppHandle, filename: Option<String>, mic_device: Option<String>, capture_system_audio: bool) {
+/// The `audio_cache_dir` setting, or the default under the app's config folder.
+fn audio_cache_dir(settings: &Settings) -> PathBuf {
+    match settings.audio_cache_dir.as_deref().filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(home).join(".config").join("scriberr-companion").join("audio-cache")
+        }
+    }
+}
+
+/// The stored preferred mic if it's still plugged in. Otherwise `None` (the
+/// default input) and a `mic-fallback` event so the UI can say so.
+fn resolve_preferred_mic(app: &AppHandle, preferred: Option<String>) -> Option<String> {
+    let preferred = preferred?;
+    // Not device names: "None" disables the mic, "Default" is the system default
+    if preferred == "None" || preferred == "Default" {
+        return Some(preferred);
+    }
+    // Older versions saved the name rather than the device ID
+    if AudioRecorder::get_microphones().iter().any(|(id, name)| id == &preferred || name == &preferred) {
+        return Some(preferred);
+    }
+
+    eprintln!("Preferred mic {:?} is not available, using the default input", preferred);
+    let _ = app.emit("mic-fallback", MicFallback { preferred });
+    None
+}
+
+/// Saves the mic a recording was explicitly started with as the new default.
+async fn remember_mic(state: &AppState, mic_device: Option<String>) {
+    {
+        let mut settings = state.settings.write().await;
+        if settings.preferred_mic == mic_device {
+            return;
+        }
+        settings.preferred_mic = mic_device.clone();
+    }
+    if let Err(e) = StorageService::update_settings(|settings| settings.preferred_mic = mic_device) {
+        eprintln!("Failed to save preferred mic: {:?}", e);
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+struct MicFallback {
+    preferred: String,
+}
+
+/// Drops an extension of a format we record in, so `name.wav` and `name`
+/// map to the same file.
+fn recording_stem(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if OutputFormat::from_extension(ext).is_some() => stem,
+        _ => name,
+    }
+}
+
+/// Per-recording options passed to `start_recording_command`.
+#[derive(Debug, Clone, Copy, Default)]
+struct StartOptions {
+    /// Overrides the `output_format` setting for this recording.
+    format: Option<OutputFormat>,
+    /// Write silence while paused instead of skipping the paused time.
+    preserve_pause_gaps: bool,
+    /// Start a new file after this much silence.
+    silence_split_sec: Option<f64>,
+    /// Mic and system audio on separate channels instead of mixed.
+    dual_track: bool,
+}
+
+async fn toggle_recording(app: &AppHandle, filename: Option<String>, mic_device: Option<String>, capture_system_audio: bool, target: CaptureTarget, options: StartOptions) {
     let state = app.state::<AppState>();
     let mut is_recording = state.is_recording.lock().await;
     let mut recorder = state.recorder.lock().await;
@@ -521,19 +1442,183 @@ async fn toggle_recording(app: &AppHandle, filename: Option<String>, mic_device:
         let folder = state.output_folder.lock().await.clone();
         let name = filename.unwrap_or_else(|| {
             let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
-            format!("recording_{}.wav", timestamp)
+            format!("recording_{}", timestamp)
         });
-        // Ensure extension
-        let name = if name.ends_with(".wav") { name } else { format!("{}.wav", name) };
+        let output_format = match options.format {
+            Some(format) => format,
+            None => state.settings.read().await.output_format,
+        };
+        // Extension follows the format, on a name that's safe to put on disk
+        let name = format!("{}.{}", paths::sanitize_filename(recording_stem(&name)), output_format.extension());
         let path = folder.join(name);
         
-        match recorder.start_recording(path.clone(), mic_device.clone(), capture_system_audio, app.clone()).await {
+        let (best_effort, mixer_config, agc_config, silence_threshold) = {
+            let settings = state.settings.read().await;
+            let agc_config = settings.mic_agc.then(|| AgcConfig {
+                target_rms: settings.agc_target_rms,
+                attack_ms: settings.agc_attack_ms,
+                release_ms: settings.agc_release_ms,
+                ..AgcConfig::default()
+            });
+            (settings.system_audio_best_effort, MixerConfig {
+                dc_blocker: settings.dc_blocker,
+                clip_ceiling: settings.clip_ceiling.clamp(0.1, 1.0),
+                buffer_seconds: settings.buffer_seconds.clamp(0.5, 30.0),
+                preserve_pause_gaps: options.preserve_pause_gaps,
+                dual_track: options.dual_track,
+                ..MixerConfig::default()
+            }, agc_config, settings.silence_split_threshold_rms)
+        };
+        {
+            let settings = state.settings.read().await;
+            recorder.set_mix_levels(settings.mic_gain, settings.system_gain);
+        }
+        let explicit_mic = mic_device.is_some();
+        let mic_device = match mic_device {
+            Some(device) => Some(device),
+            None => resolve_preferred_mic(app, state.settings.read().await.preferred_mic.clone()),
+        };
+        recorder.set_output_format(output_format);
+        recorder.set_mixer_config(mixer_config);
+        recorder.set_agc_config(agc_config);
+        let (split_tx, split_rx) = tokio::sync::mpsc::unbounded_channel();
+        let split_config = options.silence_split_sec.map(|min_silence_sec| SilenceSplitConfig {
+            threshold_rms: silence_threshold,
+            min_silence_sec,
+        });
+        recorder.set_silence_split(split_config.map(|config| (config, split_tx)));
+        match recorder.start_recording(path.clone(), mic_device.clone(), capture_system_audio, target, best_effort).await {
             Ok(_) => {
                 *is_recording = true;
+                *state.recording_format.lock().await = output_format;
+                if explicit_mic {
+                    remember_mic(&state, mic_device.clone()).await;
+                }
                 *state.current_recording_path.lock().await = Some(path);
+                if split_config.is_some() {
+                    tauri::async_runtime::spawn(register_split_segments(app.clone(), split_rx));
+                }
                 println!("Started recording (System: {}, Mic: {:?})", capture_system_audio, mic_device);
             }
             Err(e) => eprintln!("Failed to start recording: {}", e),
         }
     }
 }
+
+fn build_tray_menu(app: &AppHandle, is_recording: bool, is_paused: bool) -> tauri::Result<Menu<tauri::Wry>> {
+    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let toggle_i = MenuItem::with_id(app, "toggle", "Toggle Window", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+
+    if is_recording {
+        let pause_label = if is_paused { "Resume Recording" } else { "Pause Recording" };
+        let pause_i = MenuItem::with_id(app, "pause_resume", pause_label, true, None::<&str>)?;
+        let stop_i = MenuItem::with_id(app, "stop_recording", "Stop Recording", true, None::<&str>)?;
+        let recording_separator = PredefinedMenuItem::separator(app)?;
+
+        Menu::with_items(app, &[
+            &pause_i,
+            &stop_i,
+            &recording_separator,
+            &toggle_i,
+            &separator,
+            &quit_i,
+        ])
+    } else {
+        Menu::with_items(app, &[
+            &toggle_i,
+            &separator,
+            &quit_i,
+        ])
+    }
+}
+
+/// Rebuilds the tray menu so recording controls only show while recording.
+async fn refresh_tray_menu(app: &AppHandle) {
+    let (is_recording, is_paused, _) = app.state::<AppState>().recorder.lock().await.get_status();
+    let Some(tray) = app.tray_by_id("main") else {
+        return;
+    };
+
+    match build_tray_menu(app, is_recording, is_paused) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                eprintln!("Failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to build tray menu: {}", e),
+    }
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ScheduledRecordingStopped {
+    id: String,
+    result: RecordingResult,
+}
+
+/// Polls persisted schedules and drives the regular start/stop paths.
+fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(1));
+        // (schedule id, recorder start timestamp, stop deadline)
+        let mut active: Option<(String, Option<u64>, chrono::DateTime<chrono::Utc>)> = None;
+
+        loop {
+            ticker.tick().await;
+            let now = chrono::Utc::now();
+            let state = app.state::<AppState>();
+
+            if let Some((id, started_ms, stop_at)) = active.clone() {
+                let (is_running, _, current_ms) = state.recorder.lock().await.get_status();
+                if !is_running || current_ms != started_ms {
+                    // Stopped (or replaced) manually, nothing left to do
+                    active = None;
+                } else if now >= stop_at {
+                    active = None;
+                    match stop_recording_command(app.clone(), None).await {
+                        Ok(result) => {
+                            let _ = app.emit("scheduled-recording-stopped", ScheduledRecordingStopped { id, result });
+                        }
+                        Err(e) => eprintln!("Failed to stop scheduled recording {}: {}", id, e),
+                    }
+                }
+            }
+
+            let due = match SchedulerService::take_due(now) {
+                Ok(due) => due,
+                Err(e) => {
+                    eprintln!("Failed to load schedules: {}", e);
+                    continue;
+                }
+            };
+
+            for item in due {
+                let schedule = item.schedule;
+                let (is_running, _, _) = state.recorder.lock().await.get_status();
+                if is_running {
+                    eprintln!("Skipping scheduled recording {}: a recording is already active", schedule.id);
+                    let _ = app.emit("scheduled-recording-skipped", &schedule);
+                    continue;
+                }
+
+                toggle_recording(
+                    &app,
+                    schedule.config.filename.clone(),
+                    schedule.config.mic_device.clone(),
+                    schedule.config.capture_system_audio,
+                    CaptureTarget::from_pids(schedule.config.target_pids.clone()),
+                    StartOptions::default(),
+                ).await;
+
+                let (is_running, _, started_ms) = state.recorder.lock().await.get_status();
+                if is_running {
+                    active = Some((schedule.id.clone(), started_ms, item.stop_at));
+                    let _ = app.emit("scheduled-recording-started", &schedule);
+                } else {
+                    eprintln!("Scheduled recording {} failed to start", schedule.id);
+                    let _ = app.emit("scheduled-recording-skipped", &schedule);
+                }
+            }
+        }
+    });
+}
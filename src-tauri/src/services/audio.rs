@@ -3,18 +3,215 @@ use screencapturekit::sc_output_handler::{StreamOutput, SCStreamOutputType};
 use screencapturekit::sc_content_filter::{SCContentFilter, InitParams};
 use screencapturekit::sc_stream_configuration::SCStreamConfiguration;
 use screencapturekit::sc_shareable_content::SCShareableContent;
+use screencapturekit::sc_running_application::SCRunningApplication;
 use screencapturekit::sc_error_handler::StreamErrorHandler;
 use screencapturekit::cm_sample_buffer::CMSampleBuffer;
 
 use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
-use hound::{WavWriter, WavSpec};
+use std::path::{Path, PathBuf};
 use ringbuf::HeapProducer;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::Sample;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::mixer::AudioMixer;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{AppHandle, Emitter};
+
+use super::mixer::{AudioMixer, DataReady, Overruns, MixerConfig, MixGains, AgcConfig, AutoGain, SilenceSplitConfig, SplitHandler};
+use super::paths;
+use super::devices;
+use super::resample::StreamConverter;
+use super::encoder::{self, OutputFormat, SharedEncoder};
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct RecordingStatus {
+    pub is_recording: bool,
+    pub is_paused: bool,
+    pub start_time_ms: Option<u64>,
+    /// Recorded time so far, excluding pauses.
+    pub elapsed_ms: u64,
+}
+
+/// A file closed by a silence split, to be registered as a draft.
+#[derive(Debug, Clone)]
+pub struct SplitSegment {
+    pub path: PathBuf,
+    pub duration_sec: f64,
+    /// Where the recording continues.
+    pub next_path: PathBuf,
+}
+
+/// Monotonic recording timer that stands still while paused, so durations
+/// count recorded audio rather than wall-clock time.
+#[derive(Debug, Default)]
+struct RecordingClock {
+    started: Option<std::time::Instant>,
+    paused_at: Option<std::time::Instant>,
+    paused_total: std::time::Duration,
+    /// Elapsed time at the last silence split; earlier audio is in other files.
+    split_at: std::time::Duration,
+}
+
+impl RecordingClock {
+    fn start(&mut self) {
+        *self = Self { started: Some(std::time::Instant::now()), ..Self::default() };
+    }
+
+    fn pause(&mut self) {
+        if self.started.is_some() && self.paused_at.is_none() {
+            self.paused_at = Some(std::time::Instant::now());
+        }
+    }
+
+    fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += paused_at.elapsed();
+        }
+    }
+
+    fn elapsed(&self) -> std::time::Duration {
+        let Some(started) = self.started else {
+            return std::time::Duration::ZERO;
+        };
+        let paused = self.paused_total + self.paused_at.map_or(std::time::Duration::ZERO, |p| p.elapsed());
+        started.elapsed().saturating_sub(paused)
+    }
+
+    /// Duration of the file being closed by a split; the next one starts now.
+    fn split(&mut self) -> std::time::Duration {
+        let elapsed = self.elapsed();
+        let segment = elapsed.saturating_sub(self.split_at);
+        self.split_at = elapsed;
+        segment
+    }
+
+    /// Final duration of the current file; resets the clock.
+    fn stop(&mut self) -> std::time::Duration {
+        let elapsed = self.elapsed().saturating_sub(self.split_at);
+        *self = Self::default();
+        elapsed
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum RecorderTransition {
+    Started,
+    Paused,
+    Resumed,
+    Stopped,
+    Error,
+}
+
+/// Payload of the `recording-state-changed` event.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct RecordingStateChanged {
+    pub transition: RecorderTransition,
+    pub status: RecordingStatus,
+    pub error: Option<String>,
+}
+
+/// One range from a device's `supported_input_configs()`.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct InputConfigRange {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct DeviceCapabilities {
+    pub device_name: String,
+    pub configs: Vec<InputConfigRange>,
+    /// Set when the device was found but refused to enumerate its configs
+    /// (e.g. it was unplugged mid-query); `configs` is empty then.
+    pub error: Option<String>,
+}
+
+/// Payload of the `system-audio-unavailable` event.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SystemAudioUnavailable {
+    pub error: String,
+}
+
+/// What system audio is captured from.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, PartialEq)]
+#[serde(tag = "kind", content = "pids", rename_all = "snake_case")]
+pub enum CaptureTarget {
+    /// Everything playing on the main display, e.g. a meeting in a browser.
+    Display,
+    /// Only these apps. Falls back to `Display` when none of them is running.
+    Apps(Vec<i32>),
+}
+
+impl CaptureTarget {
+    /// `Apps` for a non-empty list, `Display` otherwise.
+    pub fn from_pids(pids: Vec<i32>) -> Self {
+        if pids.is_empty() {
+            CaptureTarget::Display
+        } else {
+            CaptureTarget::Apps(pids)
+        }
+    }
+}
+
+/// Payload of the `capture-target-fallback` event.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct CaptureTargetFallback {
+    pub pids: Vec<i32>,
+    pub reason: String,
+}
+
+const NO_RECORDING_PATH: &str = "No recording path found internally";
+
+/// What the mic callback hands its samples to.
+struct MicFeed {
+    producer: Arc<Mutex<HeapProducer<f32>>>,
+    data_ready: Arc<DataReady>,
+    overruns: Arc<Overruns>,
+}
+
+/// Mic callback for any native sample type: converts to f32, to the mixer's
+/// format, applies AGC and pushes into the mixer's ring buffer.
+fn build_mic_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut converter: StreamConverter,
+    mut agc: Option<AutoGain>,
+    paused: Arc<std::sync::atomic::AtomicBool>,
+    feed: MicFeed,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::SizedSample,
+    f32: cpal::FromSample<T>,
+{
+    let mut scratch: Vec<f32> = Vec::new();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| {
+            if paused.load(std::sync::atomic::Ordering::Relaxed) {
+                return;
+            }
+            scratch.clear();
+            scratch.extend(data.iter().map(|&s| s.to_sample::<f32>()));
+            if let Ok(mut prod) = feed.producer.lock() {
+                let mut dropped = 0;
+                converter.process(&scratch, |sample| {
+                    let sample = agc.as_mut().map_or(sample, |a| a.process(sample));
+                    if prod.push(sample).is_err() {
+                        dropped += 1;
+                    }
+                });
+                feed.overruns.add_mic(dropped);
+            }
+            feed.data_ready.notify();
+        },
+        |err| eprintln!("Mic stream error: {:?}", err),
+        None,
+    )
+}
 
 #[allow(dead_code)]
 struct SendStream(cpal::Stream);
@@ -24,6 +221,9 @@ pub struct AudioRecorder {
     stream: Option<SCStream>,
     mic_stream: Option<SendStream>,
     mixer: Arc<Mutex<Option<AudioMixer>>>,
+    mixer_thread: Option<std::thread::JoinHandle<()>>, // Joined on stop so the last samples are written before finalizing
+    data_ready: Arc<DataReady>, // The current mixer's wakeup, signalled by the capture callbacks
+    overruns: Arc<Overruns>, // Samples the capture callbacks dropped on a full ring buffer
     // We need to hold the producers to give them to the streams
     // But streams run in callbacks.
     // So we need to wrap producers in Arc<Mutex> or similar?
@@ -32,31 +232,54 @@ pub struct AudioRecorder {
     
     // We need to store the writer to close it later?
     // The mixer holds the writer.
-    writer: Arc<Mutex<Option<WavWriter<std::io::BufWriter<std::fs::File>>>>>,
+    writer: SharedEncoder,
     
     paused: Arc<std::sync::atomic::AtomicBool>,
     mixer_running: Arc<std::sync::atomic::AtomicBool>,
     mic_producer: Arc<Mutex<Option<Arc<Mutex<HeapProducer<f32>>>>>>,
 
-    start_time: Arc<Mutex<Option<std::time::Instant>>>,
+    clock: Arc<Mutex<RecordingClock>>,
     start_timestamp: Arc<Mutex<Option<u64>>>, // For UI Sync (Unix Millis)
     current_path: Arc<Mutex<Option<PathBuf>>>, // Store current recording path for renaming
+    source_app: Option<String>, // Apps system audio was narrowed to, for the draft
+    sys_active: Arc<std::sync::atomic::AtomicBool>, // Cleared when system capture fails in best-effort mode
+    mic_active: Arc<std::sync::atomic::AtomicBool>, // Cleared when the mic can't be opened
+    mic_only: bool,
+    mixer_config: MixerConfig,
+    gains: Arc<MixGains>, // Live per-source gains, read by the mixer on every buffer
+    agc_config: Option<AgcConfig>, // Mic AGC, None when disabled
+    output_format: OutputFormat,
+    silence_split: Option<(SilenceSplitConfig, tokio::sync::mpsc::UnboundedSender<SplitSegment>)>, // Consumed by the next start
+    app_handle: AppHandle,
 }
 
 impl AudioRecorder {
-    pub fn new() -> Self {
+    pub fn new(app_handle: AppHandle) -> Self {
         Self {
             stream: None,
             mic_stream: None,
             mixer: Arc::new(Mutex::new(None)),
+            mixer_thread: None,
+            data_ready: Arc::new(DataReady::default()),
+            overruns: Arc::new(Overruns::default()),
             writer: Arc::new(Mutex::new(None)),
             paused: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             mixer_running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             mic_producer: Arc::new(Mutex::new(None)),
 
-            start_time: Arc::new(Mutex::new(None)),
+            clock: Arc::new(Mutex::new(RecordingClock::default())),
             start_timestamp: Arc::new(Mutex::new(None)),
             current_path: Arc::new(Mutex::new(None)),
+            source_app: None,
+            sys_active: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            mic_active: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            mic_only: false,
+            mixer_config: MixerConfig::default(),
+            gains: Arc::new(MixGains::default()),
+            agc_config: None,
+            output_format: OutputFormat::default(),
+            silence_split: None,
+            app_handle,
         }
     }
 
@@ -67,45 +290,152 @@ impl AudioRecorder {
         (is_running, is_paused, timestamp)
     }
 
-    // ... (keep pause/resume/get_microphones/switch_microphone as is - I will use MultiReplace for better control)
+    pub fn status(&self) -> RecordingStatus {
+        let (is_recording, is_paused, start_time_ms) = self.get_status();
+        RecordingStatus {
+            is_recording,
+            is_paused,
+            start_time_ms,
+            elapsed_ms: self.clock.lock().unwrap().elapsed().as_millis() as u64,
+        }
+    }
+
+    /// Name (or bundle id) of the app(s) the current recording captures, `None`
+    /// when capturing the whole display.
+    pub fn source_app(&self) -> Option<String> {
+        self.source_app.clone()
+    }
+
+    /// Applies to the next recording started.
+    pub fn set_mixer_config(&mut self, config: MixerConfig) {
+        self.mixer_config = config;
+    }
+
+    /// Format of the next recording started; the path's extension should match.
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.output_format = format;
+    }
+
+    /// Splits the next recording into a new file after each long silence,
+    /// sending every finished file to `segments`.
+    pub fn set_silence_split(&mut self, split: Option<(SilenceSplitConfig, tokio::sync::mpsc::UnboundedSender<SplitSegment>)>) {
+        self.silence_split = split;
+    }
+
+    /// Gains applied before mixing; changes take effect mid-recording.
+    pub fn gains(&self) -> Arc<MixGains> {
+        self.gains.clone()
+    }
+
+    /// Sets both mix levels at once; takes effect mid-recording.
+    pub fn set_mix_levels(&self, mic_gain: f32, sys_gain: f32) {
+        self.gains.set_mic(mic_gain);
+        self.gains.set_sys(sys_gain);
+    }
 
+    /// Enables (or with `None` disables) mic AGC for the next mic stream built.
+    pub fn set_agc_config(&mut self, config: Option<AgcConfig>) {
+        self.agc_config = config;
+    }
+
+    /// True when system capture failed and the recording fell back to mic only.
+    pub fn is_mic_only(&self) -> bool {
+        self.mic_only
+    }
+
+    fn emit_state(&self, transition: RecorderTransition, error: Option<String>) {
+        let payload = RecordingStateChanged {
+            transition,
+            status: self.status(),
+            error,
+        };
+        let _ = self.app_handle.emit("recording-state-changed", payload);
+    }
 
     pub fn pause_recording(&self) {
+        if !self.mixer_running.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
         self.paused.store(true, std::sync::atomic::Ordering::Relaxed);
+        // With gaps preserved the pause is part of the file, so it counts
+        if !self.mixer_config.preserve_pause_gaps {
+            self.clock.lock().unwrap().pause();
+        }
+        self.emit_state(RecorderTransition::Paused, None);
     }
 
     pub fn resume_recording(&self) {
+        if !self.mixer_running.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
         self.paused.store(false, std::sync::atomic::Ordering::Relaxed);
+        self.clock.lock().unwrap().resume();
+        self.emit_state(RecorderTransition::Resumed, None);
     }
 
+    /// `(id, display name)` for each input. IDs are CoreAudio device UIDs,
+    /// stable across reboots and unambiguous when two devices share a name.
     pub fn get_microphones() -> Vec<(String, String)> {
-        let host = cpal::default_host();
-        host.input_devices().map(|devices| {
-            devices.filter_map(|d| {
-                let name = d.name().unwrap_or("Unknown Device".to_string());
-                // Use name as ID for now, or index if possible? 
-                // cpal doesn't expose stable IDs easily across platforms.
-                // Using name is risky if duplicates exist.
-                // But for macOS, names are usually unique enough for UI.
-                Some((name.clone(), name))
-            }).collect()
-        }).unwrap_or_default()
+        devices::input_devices().into_iter()
+            .map(|d| (d.id, d.display_name))
+            .collect()
     }
 
+    pub fn get_device_capabilities(device_name: &str) -> Result<DeviceCapabilities, String> {
+        let device = devices::find_input_device(device_name).ok_or("Device not found")?;
+
+        let (configs, error) = match device.supported_input_configs() {
+            Ok(configs) => (
+                configs.map(|c| InputConfigRange {
+                    channels: c.channels(),
+                    min_sample_rate: c.min_sample_rate().0,
+                    max_sample_rate: c.max_sample_rate().0,
+                    sample_format: c.sample_format().to_string(),
+                }).collect(),
+                None,
+            ),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        };
+
+        Ok(DeviceCapabilities { device_name: device_name.to_string(), configs, error })
+    }
+
+    /// Opens `device` in its native format and converts to the mixer's
+    /// rate/channels in the callback, rather than forcing a config the
+    /// device may not support.
+    fn open_mic_stream(&self, device: &cpal::Device, producer: Arc<Mutex<HeapProducer<f32>>>) -> Result<cpal::Stream, String> {
+        let supported = device.default_input_config()
+            .map_err(|e| format!("Failed to read mic format: {:?}", e))?;
+        let sample_format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+
+        let target_rate = self.mixer_config.sample_rate;
+        let target_channels = self.mixer_config.channels;
+        let converter = StreamConverter::new(config.sample_rate.0, config.channels, target_rate, target_channels)?;
+        let agc = self.agc_config.map(|c| AutoGain::new(c, target_rate, target_channels));
+        let paused = self.paused.clone();
+        let feed = MicFeed { producer, data_ready: self.data_ready.clone(), overruns: self.overruns.clone() };
+        println!("Mic opened at {} Hz x{} ({:?}), converting to {} Hz x{}", config.sample_rate.0, config.channels, sample_format, target_rate, target_channels);
+
+        let stream = match sample_format {
+            cpal::SampleFormat::F32 => build_mic_stream::<f32>(device, &config, converter, agc, paused, feed),
+            cpal::SampleFormat::I16 => build_mic_stream::<i16>(device, &config, converter, agc, paused, feed),
+            cpal::SampleFormat::U16 => build_mic_stream::<u16>(device, &config, converter, agc, paused, feed),
+            cpal::SampleFormat::I32 => build_mic_stream::<i32>(device, &config, converter, agc, paused, feed),
+            other => return Err(format!("Unsupported mic sample format: {:?}", other)),
+        }.map_err(|e| format!("Failed to build mic stream: {:?}", e))?;
+
+        stream.play().map_err(|e| format!("Failed to play mic stream: {:?}", e))?;
+        Ok(stream)
+    }
+
+    /// `device_name` may be a device ID from `get_microphones` or, for
+    /// older callers, a plain device name.
     pub fn switch_microphone(&mut self, device_name: String) -> Result<(), String> {
         // Stop current mic stream
         self.mic_stream = None;
 
-        let host = cpal::default_host();
-        let device = host.input_devices().map_err(|e| e.to_string())?
-            .find(|d| d.name().unwrap_or_default() == device_name)
-            .ok_or("Device not found")?;
-
-        let config = cpal::StreamConfig {
-            channels: 2,
-            sample_rate: cpal::SampleRate(48000),
-            buffer_size: cpal::BufferSize::Default,
-        };
+        let device = devices::find_input_device(&device_name).ok_or("Device not found")?;
 
         // Get the producer
         let producer_arc = {
@@ -113,54 +443,103 @@ impl AudioRecorder {
             guard.clone().ok_or("Mixer not initialized")?
         };
 
-        let mic_paused = self.paused.clone();
-        
-        let mic_stream = device.build_input_stream(
-            &config,
-            move |data: &[f32], _: &_| {
-                if !mic_paused.load(std::sync::atomic::Ordering::Relaxed) {
-                    if let Ok(mut prod) = producer_arc.lock() {
-                        for &sample in data {
-                            let _ = prod.push(sample);
-                        }
-                    }
-                }
-            },
-            move |err| {
-                eprintln!("Mic error: {:?}", err);
-            },
-            None
-        ).map_err(|e| format!("Failed to build mic stream: {:?}", e))?;
-
-        mic_stream.play().map_err(|e| format!("Failed to play mic stream: {:?}", e))?;
+        let mic_stream = match self.open_mic_stream(&device, producer_arc) {
+            Ok(stream) => stream,
+            Err(e) => {
+                // Keep system audio flowing rather than waiting on a dead mic
+                self.mic_active.store(false, std::sync::atomic::Ordering::Relaxed);
+                return Err(e);
+            }
+        };
+        self.mic_active.store(true, std::sync::atomic::Ordering::Relaxed);
         self.mic_stream = Some(SendStream(mic_stream));
         
         Ok(())
     }
 
-    pub async fn start_recording(&mut self, output_path: PathBuf, mic_device_name: Option<String>, _capture_system_audio: bool, app_handle: tauri::AppHandle) -> Result<(), String> {
+    /// Starts a recording, capturing system audio from `target`.
+    ///
+    /// With `best_effort_system_audio`, a failure to start system capture falls
+    /// back to a mic-only recording (see `is_mic_only`) instead of failing.
+    pub async fn start_recording(&mut self, output_path: PathBuf, mic_device_name: Option<String>, capture_system_audio: bool, target: CaptureTarget, best_effort_system_audio: bool) -> Result<(), String> {
         let _ = self.stop_recording(None); // Ensure stopped (ignoring result)
+
+        match self.start_streams(output_path, mic_device_name, capture_system_audio, target, best_effort_system_audio).await {
+            Ok(()) => {
+                self.emit_state(RecorderTransition::Started, None);
+                Ok(())
+            }
+            Err(e) => {
+                // Tear down whatever did start so we don't leave a half-open file
+                let _ = self.stop_streams(None);
+                self.emit_state(RecorderTransition::Error, Some(e.clone()));
+                Err(e)
+            }
+        }
+    }
+
+    /// Closes the current file and continues in `{stem}_{n}` next to it.
+    /// The new encoder is opened first, so a failure keeps the current file.
+    fn split_handler(&self, output_path: &Path, segments: tokio::sync::mpsc::UnboundedSender<SplitSegment>) -> SplitHandler {
+        let writer = self.writer.clone();
+        let current_path = self.current_path.clone();
+        let clock = self.clock.clone();
+        let format = self.output_format;
+        let (sample_rate, channels) = (self.mixer_config.sample_rate, self.mixer_config.output_channels());
+        let folder = output_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let stem = output_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let mut part = 1;
+
+        Box::new(move || {
+            part += 1;
+            let next_path = paths::unique_path(&folder, &format!("{}_{}", stem, part), format.extension());
+            let next = match encoder::create_encoder(format, &next_path, sample_rate, channels) {
+                Ok(next) => next,
+                Err(e) => {
+                    eprintln!("Silence split failed, continuing in the current file: {}", e);
+                    return;
+                }
+            };
+
+            let finished = writer.lock().unwrap().replace(next);
+            if let Some(finished) = finished {
+                if let Err(e) = finished.finalize() {
+                    eprintln!("Failed to finalize split recording: {}", e);
+                }
+            }
+            let duration_sec = clock.lock().unwrap().split().as_secs_f64();
+            let Some(path) = current_path.lock().unwrap().replace(next_path.clone()) else {
+                return;
+            };
+            let _ = segments.send(SplitSegment { path, duration_sec, next_path });
+        })
+    }
+
+    async fn start_streams(&mut self, output_path: PathBuf, mic_device_name: Option<String>, _capture_system_audio: bool, target: CaptureTarget, best_effort_system_audio: bool) -> Result<(), String> {
         self.paused.store(false, std::sync::atomic::Ordering::Relaxed);
+        self.source_app = None;
+        self.mic_only = false;
 
-        // 1. Setup WAV Writer
-        let spec = WavSpec {
-            channels: 2,
-            sample_rate: 48000,
-            bits_per_sample: 32,
-            sample_format: hound::SampleFormat::Float,
+        // 1. Setup Encoder
+        const SAMPLE_RATE: u32 = 48000;
+        const CHANNELS: u16 = 2;
+
+        let mixer_config = MixerConfig {
+            sample_rate: SAMPLE_RATE,
+            channels: CHANNELS,
+            ..self.mixer_config
         };
+        let writer = encoder::create_encoder(self.output_format, &output_path, SAMPLE_RATE, mixer_config.output_channels())
+            .map_err(|e| e.to_string())?;
 
-        let writer = WavWriter::create(&output_path, spec)
-            .map_err(|e| format!("Failed to create WAV writer: {:?}", e))?;
-        
-        let writer_arc = Arc::new(Mutex::new(Some(writer)));
+        let writer_arc: SharedEncoder = Arc::new(Mutex::new(Some(writer)));
         self.writer = writer_arc.clone();
         
         // Store path for later use
         *self.current_path.lock().unwrap() = Some(output_path.clone());
         
-        // precise time tracking for file duration
-        *self.start_time.lock().unwrap() = Some(std::time::Instant::now());
+        // precise time tracking for file duration, paused time excluded
+        self.clock.lock().unwrap().start();
         
         // Wall clock time for UI sync
         let now = SystemTime::now();
@@ -175,19 +554,35 @@ impl AudioRecorder {
         // If None (Option) or "Default", we interpret as enabled (System Default)
         let mic_enabled = mic_device_name.as_deref().map_or(true, |n| n != "None");
 
-        let (mixer, sys_prod, mic_prod, running) = AudioMixer::new(writer_arc.clone(), sys_enabled, mic_enabled, app_handle);
+        self.sys_active = Arc::new(std::sync::atomic::AtomicBool::new(sys_enabled));
+        // The mic callback converts to whatever the mixer runs at
+        self.mixer_config = mixer_config;
+        self.mic_active = Arc::new(std::sync::atomic::AtomicBool::new(mic_enabled));
+        let (mut mixer, sys_prod, mic_prod, running) = AudioMixer::new(writer_arc.clone(), self.sys_active.clone(), self.mic_active.clone(), self.paused.clone(), mixer_config, self.gains.clone(), self.app_handle.clone());
+        if let Some((config, segments)) = self.silence_split.take() {
+            mixer.set_silence_split(config, self.split_handler(&output_path, segments));
+        }
+        self.data_ready = mixer.data_ready();
+        self.overruns = mixer.overruns();
         *self.mixer.lock().unwrap() = Some(mixer);
         self.mixer_running = running;
         
-        // Start Mixer Thread
+        // Start Mixer Thread, raised above normal priority so it keeps up under load
         let mixer_arc = self.mixer.clone();
-        std::thread::spawn(move || {
-            if let Ok(mut guard) = mixer_arc.lock() {
-                if let Some(mixer) = &mut *guard {
-                    mixer.process();
+        let mixer_thread = std::thread::Builder::new()
+            .name("audio-mixer".to_string())
+            .spawn(move || {
+                if let Err(e) = thread_priority::set_current_thread_priority(thread_priority::ThreadPriority::Max) {
+                    eprintln!("Mixer running at normal priority: {:?}", e);
                 }
-            }
-        });
+                if let Ok(mut guard) = mixer_arc.lock() {
+                    if let Some(mixer) = &mut *guard {
+                        mixer.process();
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start mixer thread: {}", e))?;
+        self.mixer_thread = Some(mixer_thread);
 
         // 3. Setup Microphone (if requested)
         if mic_enabled {
@@ -202,136 +597,111 @@ impl AudioRecorder {
              let device = if device_name == "Default" {
                  host.default_input_device()
                 } else {
-                    host.input_devices().map_err(|e| e.to_string())?
-                        .find(|d| d.name().unwrap_or_default() == device_name)
+                    devices::find_input_device(&device_name)
                 };
 
-                if let Some(device) = device {
-                     let supported_configs = device.supported_input_configs().map_err(|e| e.to_string())?;
-                     // Find F32 config if possible, else use first.
-                     // We prefer F32 > I16 > U16.
-                     // But we must respect the device's constraints.
-                     let best_config = supported_configs
-                         .max_by(|a, b| {
-                             // Naive preference logic
-                             use cpal::SampleFormat::*;
-                             match (a.sample_format(), b.sample_format()) {
-                                 (F32, _) => std::cmp::Ordering::Greater,
-                                 (_, F32) => std::cmp::Ordering::Less,
-                                 (I16, U16) => std::cmp::Ordering::Greater,
-                                 (U16, I16) => std::cmp::Ordering::Less,
-                                 _ => std::cmp::Ordering::Equal,
-                             }
-                         })
-                         .ok_or("No supported configs")?
-                         .with_max_sample_rate(); // Use max rate to minimize aliasing if we were resampling (we aren't yet really)
-                         
-                     let err_fn = move |err| {
-                         eprintln!("Mic stream error: {:?}", err);
-                     };
-
-                     let mic_paused = self.paused.clone();
-                     let sample_format = best_config.sample_format();
-                     let config: cpal::StreamConfig = best_config.into();
-                     
-                     let channels = config.channels;
-                     
-                     let stream = match sample_format {
-                         cpal::SampleFormat::F32 => device.build_input_stream(&config, move |data: &[f32], _: &_| {
-                             if !mic_paused.load(std::sync::atomic::Ordering::Relaxed) {
-                                  if let Ok(mut prod) = mic_prod_mutex.lock() {
-                                     if channels == 1 {
-                                         for &sample in data {
-                                             let _ = prod.push(sample);
-                                             let _ = prod.push(sample);
-                                         }
-                                     } else {
-                                         for &sample in data { // First 2 channels
-                                             let _ = prod.push(sample);
-                                         }
-                                     }
-                                  }
-                             }
-                         }, err_fn, None),
-                         cpal::SampleFormat::I16 => device.build_input_stream(&config, move |data: &[i16], _: &_| {
-                            if !mic_paused.load(std::sync::atomic::Ordering::Relaxed) {
-                                if let Ok(mut prod) = mic_prod_mutex.lock() {
-                                   if channels == 1 {
-                                       for &sample in data {
-                                           let s: f32 = sample.to_sample();
-                                           let _ = prod.push(s);
-                                           let _ = prod.push(s);
-                                       }
-                                   } else {
-                                       for &sample in data {
-                                           let s: f32 = sample.to_sample();
-                                           let _ = prod.push(s);
-                                       }
-                                   }
-                                }
-                            }
-                        }, err_fn, None),
-                         cpal::SampleFormat::U16 => device.build_input_stream(&config, move |data: &[u16], _: &_| {
-                            if !mic_paused.load(std::sync::atomic::Ordering::Relaxed) {
-                                if let Ok(mut prod) = mic_prod_mutex.lock() {
-                                   if channels == 1 {
-                                       for &sample in data {
-                                           let s: f32 = sample.to_sample();
-                                           let _ = prod.push(s);
-                                           let _ = prod.push(s);
-                                       }
-                                   } else {
-                                       for &sample in data {
-                                           let s: f32 = sample.to_sample();
-                                           let _ = prod.push(s);
-                                       }
-                                   }
-                                }
-                            }
-                        }, err_fn, None),
-                        _ => return Err("Unsupported sample format".to_string()),
-                     };
-                     
-                     let stream = stream.map_err(|e| format!("Failed to build mic stream: {:?}", e))?;
-                     stream.play().map_err(|e| format!("Failed to play mic stream: {:?}", e))?;
-                     self.mic_stream = Some(SendStream(stream));
-
-                } else {
-                    eprintln!("Requested mic device not found: {}", device_name);
+                match device {
+                    Some(device) => match self.open_mic_stream(&device, mic_prod_mutex) {
+                        Ok(stream) => self.mic_stream = Some(SendStream(stream)),
+                        Err(e) => {
+                            eprintln!("Mic unavailable, recording without it: {}", e);
+                            self.mic_active.store(false, std::sync::atomic::Ordering::Relaxed);
+                        }
+                    },
+                    None => {
+                        eprintln!("Requested mic device not found: {}", device_name);
+                        self.mic_active.store(false, std::sync::atomic::Ordering::Relaxed);
+                    }
                 }
 
         }
 
-        // 4. Setup System Audio (SCK) - Capture Main Display
+        // 4. Setup System Audio (SCK) - Main Display, optionally narrowed to target apps
         if sys_enabled {
-            let content = SCShareableContent::current();
-            // Use the first available display (usually main)
-            let display = content.displays.first().ok_or("No display found")?.clone();
-
-            let filter = SCContentFilter::new(InitParams::Display(display));
-            let mut sc_config = SCStreamConfiguration::from_size(100, 100, false);
-            sc_config.captures_audio = true;
-            sc_config.excludes_current_process_audio = true; // Avoid feedback loop if we play sounds
-            
-            let sys_prod_mutex = Arc::new(Mutex::new(sys_prod));
-            
-            let mut stream = SCStream::new(filter, sc_config, ErrorHandler);
-            
-            let output_wrapper = OutputWrapper {
-                producer: sys_prod_mutex,
-                paused: self.paused.clone(),
-            };
-            
-            stream.add_output(output_wrapper, SCStreamOutputType::Audio);
-
-            stream.start_capture().map_err(|e| format!("Failed to start capture: {:?}", e))?;
-            self.stream = Some(stream);
+            if let Err(e) = self.start_system_capture(sys_prod, &target) {
+                // A permission hiccup shouldn't cost the whole recording when the mic works
+                if !(best_effort_system_audio && self.mic_stream.is_some()) {
+                    return Err(e);
+                }
+                eprintln!("System audio unavailable, recording mic only: {}", e);
+                self.sys_active.store(false, std::sync::atomic::Ordering::Relaxed);
+                self.source_app = None;
+                self.mic_only = true;
+                let _ = self.app_handle.emit("system-audio-unavailable", SystemAudioUnavailable { error: e });
+            }
         }
 
         Ok(())
     }
 
+    fn start_system_capture(&mut self, sys_prod: HeapProducer<f32>, target: &CaptureTarget) -> Result<(), String> {
+        let content = SCShareableContent::current();
+        // Use the first available display (usually main)
+        let display = content.displays.first().ok_or("No display found")?.clone();
+
+        // One filter for all targets, so a single stream feeds the mixer.
+        // SCK only delivers audio for apps that own at least one shareable
+        // window, and the apps must be running when capture starts - apps
+        // launched afterwards are not picked up.
+        let apps: Vec<SCRunningApplication> = match target {
+            CaptureTarget::Display => Vec::new(),
+            CaptureTarget::Apps(pids) => {
+                let apps: Vec<SCRunningApplication> = content.applications
+                    .into_iter()
+                    .filter(|app| pids.contains(&app.process_id))
+                    .collect();
+                if apps.is_empty() {
+                    // Better to catch too much than to record silence
+                    let reason = format!("None of the target apps are running (pids: {:?}), capturing the whole display", pids);
+                    eprintln!("{}", reason);
+                    let _ = self.app_handle.emit("capture-target-fallback", CaptureTargetFallback { pids: pids.clone(), reason });
+                }
+                apps
+            }
+        };
+
+        let filter = if apps.is_empty() {
+            SCContentFilter::new(InitParams::Display(display))
+        } else {
+            let names: Vec<String> = apps.iter()
+                .filter_map(|app| app.application_name.clone().or_else(|| app.bundle_identifier.clone()))
+                .collect();
+            self.source_app = if names.is_empty() { None } else { Some(names.join(", ")) };
+            SCContentFilter::new(InitParams::DisplayIncludingApplicationsExceptingWindows(display, apps, Vec::new()))
+        };
+        let mut sc_config = SCStreamConfiguration::from_size(100, 100, false);
+        sc_config.captures_audio = true;
+        sc_config.excludes_current_process_audio = true; // Avoid feedback loop if we play sounds
+        
+        let sys_prod_mutex = Arc::new(Mutex::new(sys_prod));
+        
+        let mut stream = SCStream::new(filter, sc_config, ErrorHandler);
+        
+        let output_wrapper = OutputWrapper {
+            producer: sys_prod_mutex,
+            paused: self.paused.clone(),
+            data_ready: self.data_ready.clone(),
+            overruns: self.overruns.clone(),
+        };
+        
+        stream.add_output(output_wrapper, SCStreamOutputType::Audio);
+
+        stream.start_capture().map_err(|e| format!("Failed to start capture: {:?}", e))?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
     pub fn stop_recording(&mut self, new_filename: Option<String>) -> Result<(f64, PathBuf), String> {
+        let result = self.stop_streams(new_filename);
+        match &result {
+            Ok(_) => self.emit_state(RecorderTransition::Stopped, None),
+            Err(e) if e != NO_RECORDING_PATH => self.emit_state(RecorderTransition::Error, Some(e.clone())),
+            Err(_) => {}
+        }
+        result
+    }
+
+    fn stop_streams(&mut self, new_filename: Option<String>) -> Result<(f64, PathBuf), String> {
         // Stop Mic
         self.mic_stream = None; 
         
@@ -347,17 +717,20 @@ impl AudioRecorder {
         }
         self.stream = None;
 
-        // Stop Mixer
+        // Stop Mixer, waking it in case it's waiting for samples
         self.mixer_running.store(false, std::sync::atomic::Ordering::Relaxed);
-        
-        // Wait briefly for mixer to finish processing?
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        self.data_ready.notify();
+        if let Some(mixer_thread) = self.mixer_thread.take() {
+            if mixer_thread.join().is_err() {
+                eprintln!("Mixer thread panicked, finalizing what was written");
+            }
+        }
         
         // Finalize Writer
         {
             let mut guard = self.writer.lock().unwrap();
             if let Some(writer) = guard.take() {
-                writer.finalize().map_err(|e| format!("Failed to finalize WAV: {:?}", e))?;
+                writer.finalize().map_err(|e| format!("Failed to finalize recording: {}", e))?;
             }
         }
         
@@ -365,11 +738,7 @@ impl AudioRecorder {
         *self.mixer.lock().unwrap() = None;
 
         // Calculate duration
-        let duration = if let Some(start) = self.start_time.lock().unwrap().take() {
-            start.elapsed().as_secs_f64()
-        } else {
-            0.0
-        };
+        let duration = self.clock.lock().unwrap().stop().as_secs_f64();
 
         // Clear timestamps
         *self.start_timestamp.lock().unwrap() = None;
@@ -377,12 +746,12 @@ impl AudioRecorder {
         // Handle Renaming
         let final_path = if let Some(current) = self.current_path.lock().unwrap().take() {
             if let Some(name) = new_filename {
-                // Clean input name (basic cleanup)
-                let safe_name = name.trim().replace("/", "_").replace("\\", "_");
-                 if !safe_name.is_empty() {
-                    let mut new_path = current.clone();
-                    new_path.set_file_name(format!("{}.wav", safe_name));
-                    
+                let safe_name = paths::sanitize_filename(&name);
+                let folder = current.parent().map(Path::to_path_buf).unwrap_or_default();
+                let extension = current.extension().and_then(|e| e.to_str()).unwrap_or("wav").to_string();
+                if current.file_name() != Some(std::ffi::OsStr::new(&format!("{}.{}", safe_name, extension))) {
+                    let new_path = paths::unique_path(&folder, &safe_name, &extension);
+
                     if let Err(e) = std::fs::rename(&current, &new_path) {
                         eprintln!("Failed to rename recording: {:?}", e);
                         // Fallback to original path if rename fails
@@ -399,7 +768,7 @@ impl AudioRecorder {
         } else {
             // Should verify why current_path is None if we just stopped?
             // Unlikely if start_recording sets it.
-             return Err("No recording path found internally".to_string());
+             return Err(NO_RECORDING_PATH.to_string());
         };
 
         Ok((duration, final_path))
@@ -416,6 +785,8 @@ impl StreamErrorHandler for ErrorHandler {
 struct OutputWrapper {
     producer: Arc<Mutex<HeapProducer<f32>>>,
     paused: Arc<std::sync::atomic::AtomicBool>,
+    data_ready: Arc<DataReady>,
+    overruns: Arc<Overruns>,
 }
 
 impl StreamOutput for OutputWrapper {
@@ -490,6 +861,12 @@ impl StreamOutput for OutputWrapper {
 
                 if !self.paused.load(std::sync::atomic::Ordering::Relaxed) {
                     if let Ok(mut prod) = self.producer.lock() {
+                        let mut dropped = 0;
+                        let mut push = |sample: f32| {
+                            if prod.push(sample).is_err() {
+                                dropped += 1;
+                            }
+                        };
                         if is_planar && channels == 2 {
                             // Planar Stereo: [LLLL...][RRRR...]
                             // We need to interleave: L, R, L, R...
@@ -498,23 +875,25 @@ impl StreamOutput for OutputWrapper {
                             let right = &samples[num_frames..2*num_frames];
                             
                             for i in 0..num_frames {
-                                let _ = prod.push(left[i]);
-                                let _ = prod.push(right[i]);
+                                push(left[i]);
+                                push(right[i]);
                             }
                         } else if channels == 1 {
                             // Mono: [M, M, M...]
                             // Duplicate for Stereo: M, M, M, M...
                             for &sample in samples {
-                                let _ = prod.push(sample);
-                                let _ = prod.push(sample);
+                                push(sample);
+                                push(sample);
                             }
                         } else {
                             // Interleaved Stereo (or unknown), push as is
                             for &sample in samples {
-                                let _ = prod.push(sample);
+                                push(sample);
                             }
                         }
+                        self.overruns.add_sys(dropped);
                     }
+                    self.data_ready.notify();
                 }
             }
         }
@@ -0,0 +1,375 @@
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::error::AppError;
+
+/// Extension of complete cache entries.
+const ENTRY_EXTENSION: &str = "audio";
+/// Extension of entries holding only some byte ranges of the audio.
+const PARTIAL_EXTENSION: &str = "partial";
+/// Extension of the `Coverage` saved next to each partial entry.
+const COVERAGE_EXTENSION: &str = "ranges";
+
+/// Remote audio fetched through the proxy, kept on disk so replaying or
+/// seeking doesn't download it again. Ranges are cached as they're played
+/// and the entry becomes complete once every byte has been seen; the least
+/// recently used entries are evicted once the cache grows past its cap.
+pub struct AudioCache {
+    dir: RwLock<PathBuf>,
+    /// Serializes updates to coverage files.
+    coverage_lock: Mutex<()>,
+}
+
+/// Byte ranges of a partial entry that are on disk, as sorted, merged,
+/// half-open `[start, end)` intervals of a `total`-byte file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Coverage {
+    pub total: u64,
+    ranges: Vec<(u64, u64)>,
+}
+
+impl Coverage {
+    pub fn new(total: u64) -> Self {
+        Self { total, ranges: Vec::new() }
+    }
+
+    pub fn insert(&mut self, start: u64, end: u64) {
+        let end = end.min(self.total);
+        if start >= end {
+            return;
+        }
+        self.ranges.push((start, end));
+        self.ranges.sort_unstable();
+        let mut merged: Vec<(u64, u64)> = Vec::with_capacity(self.ranges.len());
+        for &(start, end) in &self.ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        self.ranges = merged;
+    }
+
+    /// Whether every byte of the inclusive range `first..=last` is on disk.
+    pub fn contains(&self, first: u64, last: u64) -> bool {
+        self.ranges.iter().any(|&(start, end)| start <= first && last < end)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.total > 0 && self.contains(0, self.total - 1)
+    }
+}
+
+impl AudioCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir: RwLock::new(dir), coverage_lock: Mutex::new(()) }
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.dir.read().unwrap().clone()
+    }
+
+    /// Moves the cache to `dir`. Entries in the old directory are left for
+    /// the caller to `clear` first.
+    pub fn set_dir(&self, dir: PathBuf) {
+        *self.dir.write().unwrap() = dir;
+    }
+
+    /// Entries are keyed by server as well as job, so pointing the app at a
+    /// different server never serves the old one's audio.
+    fn key_path(&self, base_url: &str, job_id: &str, extension: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(base_url.trim_end_matches('/').as_bytes());
+        hasher.update(b"\n");
+        hasher.update(job_id.as_bytes());
+        let key: String = hasher.finalize().iter().take(16).map(|b| format!("{:02x}", b)).collect();
+        self.dir().join(format!("{}.{}", key, extension))
+    }
+
+    fn entry_path(&self, base_url: &str, job_id: &str) -> PathBuf {
+        self.key_path(base_url, job_id, ENTRY_EXTENSION)
+    }
+
+    /// The cached file and its size, marking it as recently used.
+    pub fn get(&self, base_url: &str, job_id: &str) -> Option<(PathBuf, u64)> {
+        let path = self.entry_path(base_url, job_id);
+        let file = std::fs::File::options().append(true).open(&path).ok()?;
+        let len = file.metadata().ok()?.len();
+        // Modification time doubles as last access for eviction
+        let _ = file.set_modified(SystemTime::now());
+        Some((path, len))
+    }
+
+    /// The partial entry and which of its bytes are on disk.
+    pub fn get_partial(&self, base_url: &str, job_id: &str) -> Option<(PathBuf, Coverage)> {
+        let path = self.key_path(base_url, job_id, PARTIAL_EXTENSION);
+        let coverage = std::fs::read_to_string(self.key_path(base_url, job_id, COVERAGE_EXTENSION)).ok()?;
+        let coverage = serde_json::from_str(&coverage).ok()?;
+        let _ = std::fs::File::options().append(true).open(&path).ok()?.set_modified(SystemTime::now());
+        Some((path, coverage))
+    }
+
+    /// Opens the partial entry for writing bytes of a `total`-byte file,
+    /// starting it over if it was cached at a different size.
+    pub fn open_partial(&self, base_url: &str, job_id: &str, total: u64) -> Result<std::fs::File, AppError> {
+        let _guard = self.coverage_lock.lock().unwrap();
+        std::fs::create_dir_all(self.dir())?;
+        let path = self.key_path(base_url, job_id, PARTIAL_EXTENSION);
+        let coverage_path = self.key_path(base_url, job_id, COVERAGE_EXTENSION);
+
+        let current = std::fs::read_to_string(&coverage_path).ok()
+            .and_then(|c| serde_json::from_str::<Coverage>(&c).ok());
+        if current.map(|c| c.total) != Some(total) || !path.exists() {
+            std::fs::write(&coverage_path, serde_json::to_string(&Coverage::new(total))?)?;
+            std::fs::File::create(&path)?;
+        }
+        Ok(std::fs::File::options().write(true).open(&path)?)
+    }
+
+    /// Writes `bytes` at `offset` of an entry opened with `open_partial`.
+    pub fn write_partial(file: &mut std::fs::File, offset: u64, bytes: &[u8]) -> Result<(), AppError> {
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(bytes)?;
+        Ok(())
+    }
+
+    /// Marks `[start, end)` as written; an entry with every byte written
+    /// becomes complete. The cache is then evicted down to `max_bytes`, never
+    /// taking this entry, so seek-only playback that leaves entries partial
+    /// still stays under the cap.
+    pub fn record_range(&self, base_url: &str, job_id: &str, start: u64, end: u64, max_bytes: u64) -> Result<(), AppError> {
+        let current = {
+            let _guard = self.coverage_lock.lock().unwrap();
+            let path = self.key_path(base_url, job_id, PARTIAL_EXTENSION);
+            let coverage_path = self.key_path(base_url, job_id, COVERAGE_EXTENSION);
+            // Evicted or restarted while this range was downloading
+            let Some(mut coverage) = std::fs::read_to_string(&coverage_path).ok()
+                .and_then(|c| serde_json::from_str::<Coverage>(&c).ok())
+                .filter(|_| path.exists())
+            else {
+                return Ok(());
+            };
+
+            coverage.insert(start, end);
+            if coverage.is_complete() {
+                let entry = self.entry_path(base_url, job_id);
+                std::fs::rename(&path, &entry)?;
+                let _ = std::fs::remove_file(&coverage_path);
+                entry
+            } else {
+                std::fs::write(&coverage_path, serde_json::to_string(&coverage)?)?;
+                path
+            }
+        };
+        self.evict(max_bytes, Some(&current))
+    }
+
+    /// Total size of complete and partial entries.
+    pub fn size(&self) -> u64 {
+        self.entries().iter().map(|(_, len, _)| len).sum()
+    }
+
+    /// Removes least recently used entries other than `keep` until the total
+    /// is at most `max_bytes`.
+    pub fn evict(&self, max_bytes: u64, keep: Option<&Path>) -> Result<(), AppError> {
+        let _guard = self.coverage_lock.lock().unwrap();
+        let mut entries = self.entries();
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        entries.sort_by_key(|(_, _, used)| *used);
+
+        for (path, len, _) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            if Some(path.as_path()) == keep {
+                continue;
+            }
+            std::fs::remove_file(&path)?;
+            let _ = std::fs::remove_file(path.with_extension(COVERAGE_EXTENSION));
+            total -= len;
+        }
+        Ok(())
+    }
+
+    /// Deletes every entry; returns the bytes freed.
+    pub fn clear(&self) -> Result<u64, AppError> {
+        let _guard = self.coverage_lock.lock().unwrap();
+        let Ok(dir) = std::fs::read_dir(self.dir()) else {
+            return Ok(0);
+        };
+        let mut freed = 0;
+        for entry in dir.flatten() {
+            let len = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if entry.path().is_file() {
+                std::fs::remove_file(entry.path())?;
+                freed += len;
+            }
+        }
+        Ok(freed)
+    }
+
+    /// `(path, size, last used)` of each complete or partial entry.
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Ok(dir) = std::fs::read_dir(self.dir()) else {
+            return Vec::new();
+        };
+        dir.flatten()
+            .filter(|e| matches!(e.path().extension().and_then(|x| x.to_str()), Some(ENTRY_EXTENSION | PARTIAL_EXTENSION)))
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                Some((e.path(), meta.len(), meta.modified().ok()?))
+            })
+            .collect()
+    }
+}
+
+/// Resolves a single-range `Range` header (`bytes=a-b`, `bytes=a-`,
+/// `bytes=-n`) against a body of `len` bytes to inclusive offsets. `None`
+/// when it's malformed, unsatisfiable or asks for several ranges.
+pub fn parse_range(header: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || len == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let (start, end) = if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 {
+            return None;
+        }
+        (len.saturating_sub(suffix), len - 1)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() { len - 1 } else { end.parse::<u64>().ok()?.min(len - 1) };
+        (start, end)
+    };
+
+    (start <= end && start < len).then_some((start, end))
+}
+
+/// Reads a `Content-Range: bytes a-b/total` header into `(a, b, total)`.
+pub fn parse_content_range(header: &str) -> Option<(u64, u64, u64)> {
+    let (range, total) = header.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let (start, end, total) = (start.trim().parse().ok()?, end.trim().parse().ok()?, total.trim().parse().ok()?);
+    (start <= end && end < total).then_some((start, end, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range("bytes=500-", 1000), Some((500, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=900-5000", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=5-1", 1000), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+    }
+
+    #[test]
+    fn parses_content_range() {
+        assert_eq!(parse_content_range("bytes 0-99/1000"), Some((0, 99, 1000)));
+        assert_eq!(parse_content_range("bytes 0-99/*"), None);
+        assert_eq!(parse_content_range("bytes 5-1000/1000"), None);
+    }
+
+    #[test]
+    fn coverage_merges_ranges() {
+        let mut coverage = Coverage::new(100);
+        coverage.insert(0, 10);
+        coverage.insert(50, 60);
+        assert!(coverage.contains(0, 9));
+        assert!(!coverage.contains(5, 55));
+        coverage.insert(10, 50);
+        assert!(coverage.contains(5, 55));
+        assert!(!coverage.is_complete());
+        coverage.insert(60, 500);
+        assert!(coverage.is_complete());
+    }
+
+    #[test]
+    fn partial_entry_completes_once_every_byte_is_written() {
+        let dir = std::env::temp_dir().join(format!("scriberr-cache-{}", uuid::Uuid::new_v4()));
+        let cache = AudioCache::new(dir.clone());
+        let base = "http://scriberr";
+
+        let mut file = cache.open_partial(base, "job", 10).unwrap();
+        AudioCache::write_partial(&mut file, 6, b"6789").unwrap();
+        cache.record_range(base, "job", 6, 10, u64::MAX).unwrap();
+        let (_, coverage) = cache.get_partial(base, "job").unwrap();
+        assert!(coverage.contains(6, 9));
+        assert!(cache.get(base, "job").is_none());
+
+        let mut file = cache.open_partial(base, "job", 10).unwrap();
+        AudioCache::write_partial(&mut file, 0, b"012345").unwrap();
+        cache.record_range(base, "job", 0, 6, u64::MAX).unwrap();
+        let (path, len) = cache.get(base, "job").unwrap();
+        assert_eq!(len, 10);
+        assert_eq!(std::fs::read(path).unwrap(), b"0123456789");
+        assert!(cache.get_partial(base, "job").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn evicts_least_recently_used_first() {
+        let dir = std::env::temp_dir().join(format!("scriberr-cache-{}", uuid::Uuid::new_v4()));
+        let cache = AudioCache::new(dir.clone());
+        let base = "http://scriberr";
+
+        for (i, job) in ["a", "b", "c"].iter().enumerate() {
+            let mut file = cache.open_partial(base, job, 100).unwrap();
+            AudioCache::write_partial(&mut file, 0, &[0u8; 100]).unwrap();
+            cache.record_range(base, job, 0, 100, u64::MAX).unwrap();
+            let file = std::fs::File::options().append(true).open(cache.entry_path(base, job)).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000 + i as u64)).unwrap();
+        }
+        // Reading "a" makes "b" the oldest
+        assert!(cache.get(base, "a").is_some());
+
+        cache.evict(200, None).unwrap();
+        assert!(cache.get(base, "b").is_none());
+        assert!(cache.get(base, "a").is_some());
+        assert!(cache.get(base, "c").is_some());
+        assert!(cache.get("http://other", "a").is_none());
+
+        assert_eq!(cache.clear().unwrap(), 200);
+        assert_eq!(cache.size(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn partial_entries_are_evicted_except_the_one_being_written() {
+        let dir = std::env::temp_dir().join(format!("scriberr-cache-{}", uuid::Uuid::new_v4()));
+        let cache = AudioCache::new(dir.clone());
+        let base = "http://scriberr";
+
+        for (i, job) in ["a", "b"].iter().enumerate() {
+            let mut file = cache.open_partial(base, job, 1000).unwrap();
+            AudioCache::write_partial(&mut file, 0, &[0u8; 100]).unwrap();
+            cache.record_range(base, job, 0, 100, u64::MAX).unwrap();
+            let file = std::fs::File::options().append(true).open(cache.key_path(base, job, PARTIAL_EXTENSION)).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000 + i as u64)).unwrap();
+        }
+
+        // "a" is the oldest, but it's the one still being played
+        let mut file = cache.open_partial(base, "a", 1000).unwrap();
+        AudioCache::write_partial(&mut file, 100, &[0u8; 50]).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(500)).unwrap();
+        cache.record_range(base, "a", 100, 150, 200).unwrap();
+
+        assert!(cache.get_partial(base, "a").is_some());
+        assert!(cache.get_partial(base, "b").is_none());
+        assert_eq!(cache.size(), 150);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,341 @@
+use serde::Serialize;
+use serde_json::Value;
+use specta::Type;
+use reqwest::StatusCode;
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, Serialize, Type, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointPresence {
+    Present,
+    Missing,
+    /// Couldn't be probed, e.g. `{id}/audio` on a server with no jobs yet.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct EndpointCheck {
+    pub path: String,
+    /// What stops working without it, for "your server is too old for X".
+    pub feature: String,
+    pub presence: EndpointPresence,
+    pub status: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ApiCompatibility {
+    pub api_version: Option<String>,
+    pub endpoints: Vec<EndpointCheck>,
+    pub compatible: bool,
+    /// Human-readable problems, empty when everything is present.
+    pub issues: Vec<String>,
+}
+
+/// Why a server couldn't be used, as far as a single request can tell.
+#[derive(Debug, Clone, Copy, Serialize, Type, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionProblem {
+    /// The host name doesn't resolve.
+    Dns,
+    /// Resolved, but nothing accepted the connection.
+    Unreachable,
+    Timeout,
+    /// The server answered but rejected the API key.
+    Unauthorized,
+    /// Something answered that isn't Scriberr, e.g. a login page or proxy error.
+    NotScriberr,
+    ServerError,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ConnectionDiagnosis {
+    pub reachable: bool,
+    pub problem: Option<ConnectionProblem>,
+    /// Status of the models request, when one came back.
+    pub status: Option<u16>,
+    pub server_version: Option<String>,
+    /// Models the server offers; `None` when the list couldn't be read.
+    pub model_count: Option<usize>,
+    /// Round trip of the models request.
+    pub latency_ms: Option<u64>,
+    pub message: Option<String>,
+}
+
+/// Outcome of the settings screen's "test connection", flattened from a
+/// `ConnectionDiagnosis`.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ConnectionCheck {
+    pub reachable: bool,
+    pub status_code: Option<u16>,
+    /// True only when the server accepted the key; a 401/403 makes it false.
+    pub api_key_valid: bool,
+    pub server_version: Option<String>,
+    pub latency_ms: Option<u64>,
+    /// What went wrong, worded for the user; `None` on success.
+    pub error: Option<String>,
+}
+
+impl From<ConnectionDiagnosis> for ConnectionCheck {
+    fn from(diagnosis: ConnectionDiagnosis) -> Self {
+        let error = diagnosis.problem.map(|problem| {
+            let summary = match problem {
+                ConnectionProblem::Dns => "The server's host name could not be resolved",
+                ConnectionProblem::Unreachable => "Could not connect to the server",
+                ConnectionProblem::Timeout => "The server did not answer in time",
+                ConnectionProblem::Unauthorized => "The API key was rejected",
+                ConnectionProblem::NotScriberr => "The URL doesn't point at a Scriberr server",
+                ConnectionProblem::ServerError => "The server returned an error",
+            };
+            match &diagnosis.message {
+                Some(detail) => format!("{} ({})", summary, detail),
+                None => summary.to_string(),
+            }
+        });
+        Self {
+            reachable: diagnosis.reachable,
+            status_code: diagnosis.status,
+            api_key_valid: diagnosis.reachable && diagnosis.problem.is_none(),
+            server_version: diagnosis.server_version,
+            latency_ms: diagnosis.latency_ms,
+            error,
+        }
+    }
+}
+
+/// A transcription model offered by the server.
+#[derive(Debug, Clone, Serialize, Type, PartialEq)]
+pub struct TranscriptionModel {
+    /// Sent as the `model` field of an upload.
+    pub id: String,
+    /// Display name, when the server gives one.
+    pub name: Option<String>,
+}
+
+/// How long `diagnose` waits on each request.
+const DIAGNOSE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+pub struct CompatService;
+
+impl CompatService {
+    /// Probes every endpoint the client depends on. Anything but a 404/405
+    /// means the route exists (401/400 still prove the server knows it).
+    pub async fn check(client: &reqwest::Client, base_url: &str, api_key: &str) -> Result<ApiCompatibility, AppError> {
+        let base = base_url.trim_end_matches('/');
+        let api = format!("{}/api/v1/transcription", base);
+
+        let models = Self::probe(client.get(format!("{}/models", api)), api_key).await?;
+
+        let list_resp = client.get(format!("{}/list?page=1&limit=1", api))
+            .header("X-API-Key", api_key)
+            .send()
+            .await?;
+        let list_status = list_resp.status();
+        let first_job_id = if list_status.is_success() {
+            list_resp.json::<Value>().await.ok().and_then(|body| first_job_id(&body))
+        } else {
+            None
+        };
+
+        // An empty multipart POST is rejected with 400 by servers that have the route
+        let upload = Self::probe(client.post(format!("{}/upload", api)), api_key).await?;
+
+        let audio = match &first_job_id {
+            // Not every router answers HEAD, so ask for a single byte instead
+            Some(id) => {
+                let request = client.get(format!("{}/{}/audio", api, id)).header(reqwest::header::RANGE, "bytes=0-0");
+                Some(Self::probe(request, api_key).await?)
+            }
+            None => None,
+        };
+
+        let endpoints = vec![
+            check("/api/v1/transcription/models", "Connection checks and model selection", Some(models)),
+            check("/api/v1/transcription/upload", "Uploading recordings", Some(upload)),
+            check("/api/v1/transcription/list", "Library sync", Some(list_status)),
+            check("/api/v1/transcription/{id}/audio", "Downloads and playback", audio),
+        ];
+
+        let issues: Vec<String> = endpoints.iter()
+            .filter(|e| e.presence == EndpointPresence::Missing)
+            .map(|e| format!("Your server is too old for {} ({} is missing)", e.feature, e.path))
+            .collect();
+
+        Ok(ApiCompatibility {
+            api_version: Self::detect_version(client, base, api_key).await,
+            compatible: issues.is_empty(),
+            endpoints,
+            issues,
+        })
+    }
+
+    /// Requests the models list with `api_key` and explains what went wrong,
+    /// without touching saved settings. Every request times out after 5s.
+    pub async fn diagnose(base_url: &str, api_key: &str) -> Result<ConnectionDiagnosis, AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(DIAGNOSE_TIMEOUT)
+            .connect_timeout(DIAGNOSE_TIMEOUT)
+            .build()?;
+        let base = base_url.trim().trim_end_matches('/');
+
+        let started = std::time::Instant::now();
+        let result = client.get(format!("{}/api/v1/transcription/models", base))
+            .header("X-API-Key", api_key)
+            .send()
+            .await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let resp = match result {
+            Ok(resp) => resp,
+            Err(e) => {
+                let problem = if e.is_timeout() {
+                    ConnectionProblem::Timeout
+                } else if is_dns_error(&e) {
+                    ConnectionProblem::Dns
+                } else {
+                    ConnectionProblem::Unreachable
+                };
+                return Ok(ConnectionDiagnosis {
+                    reachable: false,
+                    problem: Some(problem),
+                    status: None,
+                    server_version: None,
+                    model_count: None,
+                    latency_ms: None,
+                    message: Some(e.to_string()),
+                });
+            }
+        };
+
+        let status = resp.status();
+        let header_version = resp.headers().get("x-scriberr-version")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let problem = if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            Some(ConnectionProblem::Unauthorized)
+        } else if crate::services::http::is_html(&resp) || status == StatusCode::NOT_FOUND {
+            Some(ConnectionProblem::NotScriberr)
+        } else if !status.is_success() {
+            Some(ConnectionProblem::ServerError)
+        } else {
+            None
+        };
+        let model_count = if problem.is_none() {
+            resp.json::<Value>().await.ok().as_ref().and_then(count_models)
+        } else {
+            None
+        };
+
+        let server_version = match header_version {
+            Some(version) => Some(version),
+            None => Self::detect_version(&client, base, api_key).await,
+        };
+
+        Ok(ConnectionDiagnosis {
+            reachable: true,
+            problem,
+            status: Some(status.as_u16()),
+            server_version,
+            model_count,
+            latency_ms: Some(latency_ms),
+            message: problem.map(|_| format!("Models request returned HTTP {}", status.as_u16())),
+        })
+    }
+
+    /// Models the server can transcribe with, in the order it lists them.
+    pub async fn list_models(client: &reqwest::Client, base_url: &str, api_key: &str) -> Result<Vec<TranscriptionModel>, AppError> {
+        let base = base_url.trim_end_matches('/');
+        let resp = client.get(format!("{}/api/v1/transcription/models", base))
+            .header("X-API-Key", api_key)
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            if crate::services::http::is_html(&resp) {
+                return Err(crate::services::http::unexpected_body(resp).await);
+            }
+            return Err(AppError::http(status, "Listing models failed"));
+        }
+        let body: Value = crate::services::http::read_json(resp).await?;
+        parse_models(&body).ok_or_else(|| AppError::Server("Models response has no model list".to_string()))
+    }
+
+    async fn probe(request: reqwest::RequestBuilder, api_key: &str) -> Result<StatusCode, AppError> {
+        Ok(request.header("X-API-Key", api_key).send().await?.status())
+    }
+
+    /// Reads the version from `/api/v1/version`, `/api/v1/health` or `/health`,
+    /// whichever answers.
+    async fn detect_version(client: &reqwest::Client, base: &str, api_key: &str) -> Option<String> {
+        for path in ["/api/v1/version", "/api/v1/health", "/health"] {
+            let Ok(resp) = client.get(format!("{}{}", base, path)).header("X-API-Key", api_key).send().await else {
+                continue;
+            };
+            if !resp.status().is_success() {
+                continue;
+            }
+            if let Some(version) = resp.json::<Value>().await.ok()
+                .and_then(|body| body.get("version").and_then(|v| v.as_str()).map(str::to_string))
+            {
+                return Some(version);
+            }
+        }
+        None
+    }
+}
+
+fn check(path: &str, feature: &str, status: Option<StatusCode>) -> EndpointCheck {
+    let presence = match status {
+        None => EndpointPresence::Unknown,
+        Some(StatusCode::NOT_FOUND) | Some(StatusCode::METHOD_NOT_ALLOWED) => EndpointPresence::Missing,
+        Some(_) => EndpointPresence::Present,
+    };
+    EndpointCheck {
+        path: path.to_string(),
+        feature: feature.to_string(),
+        presence,
+        status: status.map(|s| s.as_u16()),
+    }
+}
+
+fn first_job_id(body: &Value) -> Option<String> {
+    let jobs = body.as_array()
+        .or_else(|| body.get("jobs").or(body.get("data")).and_then(|j| j.as_array()))?;
+    jobs.first()?.get("id")?.as_str().map(str::to_string)
+}
+
+/// The models endpoint returns a bare array or wraps it in `models`/`data`.
+fn model_entries(body: &Value) -> Option<&Vec<Value>> {
+    body.as_array()
+        .or_else(|| body.get("models").or(body.get("data")).and_then(|m| m.as_array()))
+}
+
+fn count_models(body: &Value) -> Option<usize> {
+    model_entries(body).map(Vec::len)
+}
+
+/// Entries are either plain ids or objects with an `id` (or `model`) and an
+/// optional `name`; anything else is skipped.
+fn parse_models(body: &Value) -> Option<Vec<TranscriptionModel>> {
+    let models = model_entries(body)?.iter().filter_map(|entry| match entry {
+        Value::String(id) => Some(TranscriptionModel { id: id.clone(), name: None }),
+        Value::Object(fields) => {
+            let id = fields.get("id").or(fields.get("model")).and_then(|v| v.as_str())?;
+            let name = fields.get("name").and_then(|v| v.as_str()).map(str::to_string);
+            Some(TranscriptionModel { id: id.to_string(), name })
+        }
+        _ => None,
+    });
+    Some(models.collect())
+}
+
+/// Whether a connection failed at name resolution rather than connecting.
+fn is_dns_error(error: &reqwest::Error) -> bool {
+    let mut source: Option<&dyn std::error::Error> = Some(error);
+    while let Some(e) = source {
+        if e.to_string().contains("dns error") {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
@@ -2,6 +2,7 @@ use sqlx::{sqlite::{SqlitePoolOptions, SqliteConnectOptions}, Pool, Sqlite};
 use std::path::PathBuf;
 use std::str::FromStr;
 use crate::error::AppError;
+use crate::services::transcript::parse_transcript;
 use serde::{Serialize, Deserialize};
 use specta::Type;
 use tokio::fs;
@@ -15,6 +16,7 @@ pub enum SyncStatus {
     ProcessingRemote, // Actively processing
     CompletedSynced,
     Failed,
+    LocalTranscribed, // Not uploaded, transcribed on this machine
 }
 
 impl From<String> for SyncStatus {
@@ -26,6 +28,7 @@ impl From<String> for SyncStatus {
             "PROCESSING_REMOTE" => SyncStatus::ProcessingRemote,
             "COMPLETED_SYNCED" => SyncStatus::CompletedSynced,
             "FAILED" => SyncStatus::Failed,
+            "LOCAL_TRANSCRIBED" => SyncStatus::LocalTranscribed,
             // Swagger / Remote statuses
             "uploaded" | "pending" => SyncStatus::RemotePending,
             "processing" => SyncStatus::ProcessingRemote,
@@ -45,6 +48,7 @@ impl ToString for SyncStatus {
             SyncStatus::ProcessingRemote => "PROCESSING_REMOTE".to_string(),
             SyncStatus::CompletedSynced => "COMPLETED_SYNCED".to_string(),
             SyncStatus::Failed => "FAILED".to_string(),
+            SyncStatus::LocalTranscribed => "LOCAL_TRANSCRIBED".to_string(),
         }
     }
 }
@@ -65,6 +69,15 @@ pub struct CachedRecording {
     pub transcript_text: Option<String>,
     pub summary_text: Option<String>,
     pub individual_transcripts_json: Option<String>,
+    pub content_signature: Option<String>,
+    pub source_app: Option<String>,
+    pub mic_only: bool,
+    /// When the recording was moved to the trash.
+    pub deleted_at: Option<String>,
+    /// Renamed locally but not yet on the server.
+    pub title_dirty: bool,
+    /// Title derived from the transcript; local only, the server keeps its own.
+    pub title_auto: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Type, sqlx::FromRow)]
@@ -73,6 +86,72 @@ pub struct CachedSpeakerMap {
     pub local_recording_id: String,
     pub original_speaker_label: String,
     pub display_name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    CreatedAt,
+    Title,
+    Duration,
+}
+
+impl SortField {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortField::CreatedAt => "created_at",
+            SortField::Title => "title",
+            SortField::Duration => "duration",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+/// Most results `search_recordings` returns.
+const SEARCH_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SearchResult {
+    pub recording: CachedRecording,
+    /// Matched context, with the matched terms wrapped in `<mark>`.
+    pub snippet: Option<String>,
+}
+
+/// Turns user input into an FTS5 query: each word is quoted, so punctuation
+/// can't be read as query syntax, and prefix-matched so results show up
+/// while typing. `None` when there's nothing to search for.
+fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input.split_whitespace()
+        .map(|word| word.replace('"', "\"\""))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// A local-only label; tags belong to `local_id`s and never go to the server.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, sqlx::FromRow)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
 }
 
 pub struct DatabaseService {
@@ -106,6 +185,26 @@ impl DatabaseService {
         Ok(Self { pool })
     }
 
+    /// Private in-memory database with migrations applied. A single connection
+    /// keeps every query on the same memory database.
+    pub async fn new_in_memory() -> Result<Self, AppError> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .map_err(|e| AppError::Database(format!("Migration failed: {}", e)))?;
+
+        Ok(Self { pool })
+    }
+
     pub fn get_pool(&self) -> &Pool<Sqlite> {
         &self.pool
     }
@@ -117,6 +216,7 @@ impl DatabaseService {
         title: String,
         duration_sec: f64,
         local_file_path: String,
+        source_app: Option<String>,
     ) -> Result<CachedRecording, AppError> {
         let local_id = Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
@@ -125,9 +225,9 @@ impl DatabaseService {
         sqlx::query!(
             r#"
             INSERT INTO cached_recordings (
-                local_id, title, duration_sec, created_at, sync_status, local_file_path, keep_offline
+                local_id, title, duration_sec, created_at, sync_status, local_file_path, keep_offline, source_app
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             local_id,
             title,
@@ -135,7 +235,8 @@ impl DatabaseService {
             now,
             status,
             local_file_path,
-            false
+            false,
+            source_app
         )
         .execute(&self.pool)
         .await
@@ -160,7 +261,7 @@ impl DatabaseService {
     pub async fn get_all_recordings(&self) -> Result<Vec<CachedRecording>, AppError> {
         let recs = sqlx::query_as!(
             CachedRecording,
-            "SELECT * FROM cached_recordings ORDER BY created_at DESC"
+            "SELECT * FROM cached_recordings WHERE deleted_at IS NULL ORDER BY created_at DESC"
         )
         .fetch_all(&self.pool)
         .await
@@ -169,6 +270,118 @@ impl DatabaseService {
         Ok(recs)
     }
 
+    /// Recordings matching `query` in their title, summary or transcript, best
+    /// match first. Each comes with a snippet of the matched text, with the
+    /// matched terms wrapped in `<mark>`.
+    pub async fn search_recordings(&self, query: &str) -> Result<Vec<SearchResult>, AppError> {
+        let Some(fts_query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        // Title matches weigh most, then the summary, then the transcript
+        let rows = sqlx::query!(
+            r#"
+            SELECT f.local_id AS "local_id!: String",
+                   snippet(recordings_fts, -1, '<mark>', '</mark>', '…', 12) AS "snippet?: String"
+            FROM recordings_fts f
+            JOIN cached_recordings r ON r.local_id = f.local_id
+            WHERE recordings_fts MATCH ? AND r.deleted_at IS NULL
+            ORDER BY bm25(recordings_fts, 0.0, 10.0, 3.0, 1.0)
+            LIMIT ?
+            "#,
+            fts_query,
+            SEARCH_LIMIT
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let mut results = Vec::with_capacity(rows.len());
+        for row in rows {
+            results.push(SearchResult { recording: self.get_recording(&row.local_id).await?, snippet: row.snippet });
+        }
+        Ok(results)
+    }
+
+    /// One page of the library (trash excluded), sorted by `sort` with newest
+    /// first breaking ties.
+    pub async fn get_recordings_paginated(&self, offset: i64, limit: i64, sort: SortField, order: SortOrder) -> Result<Vec<CachedRecording>, AppError> {
+        let sort = sort.as_str();
+        let order = order.as_str();
+        let recs = sqlx::query_as!(
+            CachedRecording,
+            r#"
+            SELECT * FROM cached_recordings
+            WHERE deleted_at IS NULL
+            ORDER BY
+                CASE WHEN ?1 = 'title' AND ?2 = 'asc' THEN title END COLLATE NOCASE ASC,
+                CASE WHEN ?1 = 'title' AND ?2 = 'desc' THEN title END COLLATE NOCASE DESC,
+                CASE WHEN ?1 = 'duration' AND ?2 = 'asc' THEN duration_sec END ASC,
+                CASE WHEN ?1 = 'duration' AND ?2 = 'desc' THEN duration_sec END DESC,
+                CASE WHEN ?1 = 'created_at' AND ?2 = 'asc' THEN created_at END ASC,
+                created_at DESC,
+                local_id
+            LIMIT ?3 OFFSET ?4
+            "#,
+            sort,
+            order,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(recs)
+    }
+
+    /// Recordings outside the trash, for paging.
+    pub async fn count_recordings(&self) -> Result<i64, AppError> {
+        let count = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!: i64" FROM cached_recordings WHERE deleted_at IS NULL"#)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(count)
+    }
+
+    /// Recordings in the trash, most recently deleted first.
+    pub async fn get_trashed_recordings(&self) -> Result<Vec<CachedRecording>, AppError> {
+        let recs = sqlx::query_as!(
+            CachedRecording,
+            "SELECT * FROM cached_recordings WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(recs)
+    }
+
+    /// Moves a recording to the trash; its files stay until it's purged.
+    pub async fn soft_delete_recording(&self, local_id: &str) -> Result<(), AppError> {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query!(
+            "UPDATE cached_recordings SET deleted_at = ? WHERE local_id = ?",
+            now,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn restore_recording(&self, local_id: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET deleted_at = NULL WHERE local_id = ?",
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
     pub async fn update_sync_status(&self, local_id: &str, status: SyncStatus) -> Result<(), AppError> {
         let status_str = status.to_string();
         sqlx::query!(
@@ -228,6 +441,80 @@ impl DatabaseService {
         Ok(())
     }
 
+    pub async fn update_title(&self, local_id: &str, title: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET title = ?, title_auto = 0 WHERE local_id = ?",
+            title,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Sets a title derived from the content. Unlike `update_title` it's marked
+    /// `title_auto`, so syncs keep it without it ever being pushed.
+    pub async fn set_auto_title(&self, local_id: &str, title: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET title = ?, title_auto = 1 WHERE local_id = ?",
+            title,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn set_title_dirty(&self, local_id: &str, dirty: bool) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET title_dirty = ? WHERE local_id = ?",
+            dirty,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Uploaded recordings whose rename hasn't reached the server yet.
+    pub async fn get_title_dirty_recordings(&self) -> Result<Vec<CachedRecording>, AppError> {
+        let recs = sqlx::query_as!(
+            CachedRecording,
+            "SELECT * FROM cached_recordings WHERE title_dirty = 1 AND remote_job_id IS NOT NULL"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(recs)
+    }
+
+    pub async fn set_individual_transcripts(&self, local_id: &str, json: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET individual_transcripts_json = ? WHERE local_id = ?",
+            json,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn clear_local_file_path(&self, local_id: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET local_file_path = NULL WHERE local_id = ?",
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
     pub async fn set_local_audio_path(&self, local_id: &str, path: Option<String>) -> Result<(), AppError> {
         let keep_offline = path.is_some();
         sqlx::query!(
@@ -242,7 +529,54 @@ impl DatabaseService {
         Ok(())
     }
     
+    /// Stores a transcript made on this machine. Recordings not on the server
+    /// yet become `LocalTranscribed`; the server's transcript replaces it on sync.
+    pub async fn set_local_transcript(&self, local_id: &str, text: &str) -> Result<(), AppError> {
+        let status = SyncStatus::LocalTranscribed.to_string();
+        sqlx::query!(
+            r#"
+            UPDATE cached_recordings
+            SET transcript_text = ?,
+                sync_status = CASE WHEN remote_job_id IS NULL THEN ? ELSE sync_status END
+            WHERE local_id = ?
+            "#,
+            text,
+            status,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Pins or unpins the recording's local audio without touching its paths.
+    /// Pinned audio is never pruned after upload or by the storage quota.
+    pub async fn set_keep_offline(&self, local_id: &str, keep: bool) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            "UPDATE cached_recordings SET keep_offline = ? WHERE local_id = ?",
+            keep,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Recording not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Deletes the row along with its tag links; tags left unused go too.
     pub async fn delete_recording(&self, local_id: &str) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM recording_tags WHERE local_recording_id = ?", local_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        sqlx::query!("DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM recording_tags)")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
         sqlx::query!("DELETE FROM cached_recordings WHERE local_id = ?", local_id)
             .execute(&self.pool)
             .await
@@ -250,6 +584,8 @@ impl DatabaseService {
         Ok(())
     }
 
+    /// Inserts or refreshes the rows linked to a server job. Also returns the
+    /// previous `sync_status` when an existing row's status changed.
     pub async fn upsert_remote_recording(
         &self,
         remote_id: &str,
@@ -260,10 +596,10 @@ impl DatabaseService {
         summary: Option<&str>,
         individual_json: Option<&str>,
         remote_audio_url: Option<&str>
-    ) -> Result<CachedRecording, AppError> {
+    ) -> Result<(CachedRecording, Option<String>), AppError> {
         // Check if exists
         let existing = sqlx::query!(
-            "SELECT local_id FROM cached_recordings WHERE remote_job_id = ?",
+            "SELECT local_id, sync_status FROM cached_recordings WHERE remote_job_id = ?",
             remote_id
         )
         .fetch_optional(&self.pool)
@@ -272,39 +608,25 @@ impl DatabaseService {
 
         let sync_status = SyncStatus::from(status_str.to_string()).to_string();
 
-        // Parse transcript logic
-        let mut db_transcript = transcript.map(|s| s.to_string());
-        let mut db_individual_json = individual_json.map(|s| s.to_string());
-        
-        // If transcript looks like JSON, try to extract text and segments
-        if let Some(t_str) = transcript {
-            // Simple heuristic to avoid parsing plain text if possible, though safe to parse
-            if t_str.trim().starts_with('{') {
-               if let Ok(val) = serde_json::from_str::<serde_json::Value>(t_str) {
-                    if let Some(txt) = val.get("text").and_then(|v| v.as_str()) {
-                        db_transcript = Some(txt.to_string());
-                    }
-                    // If we have segments in the main transcript, use them if individual_json is empty
-                    // or better, preferring the segments aligned with the text
-                    if let Some(segs) = val.get("segments") {
-                        db_individual_json = Some(segs.to_string());
-                    }
-               }
-            }
-        }
+        // Segments in the transcript itself win over the per-track ones
+        let (db_transcript, transcript_segments) = transcript.map(parse_transcript).unwrap_or_default();
+        let db_individual_json = transcript_segments
+            .or_else(|| individual_json.and_then(|raw| parse_transcript(raw).1));
 
         if let Some(record) = existing {
-            // Update
+            // Update every local copy linked to the job (see find_uploaded_by_hash).
+            // A pending rename or a derived title wins over the server's, and
+            // a local transcript is kept until the server has one of its own.
             sqlx::query!(
                 r#"
                 UPDATE cached_recordings 
-                SET title = ?, 
+                SET title = CASE WHEN title_dirty OR title_auto THEN title ELSE ? END, 
                     sync_status = ?, 
-                    transcript_text = ?, 
+                    transcript_text = COALESCE(?, transcript_text), 
                     summary_text = ?, 
                     individual_transcripts_json = ?,
                     remote_audio_url = ?
-                WHERE local_id = ?
+                WHERE remote_job_id = ?
                 "#,
                 title,
                 sync_status,
@@ -312,13 +634,15 @@ impl DatabaseService {
                 summary,
                 db_individual_json,
                 remote_audio_url,
-                record.local_id
+                remote_id
             )
             .execute(&self.pool)
             .await
             .map_err(|e| AppError::Database(e.to_string()))?;
 
-            self.get_recording(&record.local_id).await
+            let recording = self.get_recording(&record.local_id).await?;
+            let old_status = Some(record.sync_status).filter(|old| *old != recording.sync_status);
+            Ok((recording, old_status))
         } else {
             // Insert
             let local_id = Uuid::new_v4().to_string();
@@ -349,7 +673,7 @@ impl DatabaseService {
             .await
             .map_err(|e| AppError::Database(e.to_string()))?;
 
-            self.get_recording(&local_id).await
+            Ok((self.get_recording(&local_id).await?, None))
         }
     }
 
@@ -360,4 +684,437 @@ impl DatabaseService {
             .map_err(|e| AppError::Database(e.to_string()))?;
         Ok(())
     }
+
+    pub async fn set_content_signature(&self, local_id: &str, signature: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET content_signature = ? WHERE local_id = ?",
+            signature,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// First `max_chars` characters of the transcript (the `text` field for JSON
+    /// transcripts), cut in SQL so the full text never leaves the database.
+    pub async fn get_transcript_excerpt(&self, local_id: &str, max_chars: i64) -> Result<Option<String>, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT CASE
+                WHEN json_valid(transcript_text) AND json_type(transcript_text) = 'object' THEN substr(json_extract(transcript_text, '$.text'), 1, ?)
+                ELSE substr(transcript_text, 1, ?)
+            END as "excerpt?: String"
+            FROM cached_recordings WHERE local_id = ?
+            "#,
+            max_chars,
+            max_chars,
+            local_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .ok_or(AppError::NotFound("Recording not found".to_string()))?;
+
+        Ok(row.excerpt)
+    }
+
+    pub async fn set_mic_only(&self, local_id: &str, mic_only: bool) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET mic_only = ? WHERE local_id = ?",
+            mic_only,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn set_file_hash(&self, local_id: &str, hash: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE cached_recordings SET file_hash = ? WHERE local_id = ?",
+            hash,
+            local_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Another recording with this audio that already has a server job.
+    pub async fn find_uploaded_by_hash(&self, hash: &str, exclude_local_id: &str) -> Result<Option<CachedRecording>, AppError> {
+        let failed = SyncStatus::Failed.to_string();
+        let rec = sqlx::query_as!(
+            CachedRecording,
+            "SELECT * FROM cached_recordings WHERE file_hash = ? AND local_id != ? AND remote_job_id IS NOT NULL AND sync_status != ? LIMIT 1",
+            hash,
+            exclude_local_id,
+            failed
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(rec)
+    }
+
+    pub async fn find_by_content_signature(&self, signature: &str) -> Result<Vec<CachedRecording>, AppError> {
+        let recs = sqlx::query_as!(
+            CachedRecording,
+            "SELECT * FROM cached_recordings WHERE content_signature = ?",
+            signature
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(recs)
+    }
+
+    // Speaker Maps
+
+    pub async fn get_speaker_maps(&self, local_id: &str) -> Result<Vec<CachedSpeakerMap>, AppError> {
+        let maps = sqlx::query_as!(
+            CachedSpeakerMap,
+            r#"SELECT id as "id!", local_recording_id, original_speaker_label, display_name, color
+            FROM cached_speaker_maps WHERE local_recording_id = ? ORDER BY id"#,
+            local_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Ok(maps)
+    }
+
+    /// Makes sure every label has a map entry with a color. New speakers get
+    /// the next palette color, so colors depend only on order of appearance.
+    pub async fn ensure_speaker_maps(&self, local_id: &str, labels: &[String]) -> Result<Vec<CachedSpeakerMap>, AppError> {
+        let existing = self.get_speaker_maps(local_id).await?;
+        let mut next_index = existing.len();
+
+        for (index, map) in existing.iter().enumerate() {
+            if map.color.is_none() {
+                let color = crate::services::transcript::palette_color(index);
+                sqlx::query!("UPDATE cached_speaker_maps SET color = ? WHERE id = ?", color, map.id)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| AppError::Database(e.to_string()))?;
+            }
+        }
+
+        for label in labels {
+            if existing.iter().any(|m| &m.original_speaker_label == label) {
+                continue;
+            }
+            let color = crate::services::transcript::palette_color(next_index);
+            next_index += 1;
+            sqlx::query!(
+                "INSERT INTO cached_speaker_maps (local_recording_id, original_speaker_label, display_name, color) VALUES (?, ?, ?, ?)",
+                local_id,
+                label,
+                label,
+                color
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        self.get_speaker_maps(local_id).await
+    }
+
+    pub async fn update_speaker_map(
+        &self,
+        local_id: &str,
+        label: &str,
+        display_name: Option<&str>,
+        color: Option<&str>,
+    ) -> Result<CachedSpeakerMap, AppError> {
+        let result = sqlx::query!(
+            "UPDATE cached_speaker_maps SET display_name = COALESCE(?, display_name), color = COALESCE(?, color) WHERE local_recording_id = ? AND original_speaker_label = ?",
+            display_name,
+            color,
+            local_id,
+            label
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!("Speaker {} not found", label)));
+        }
+
+        self.get_speaker_maps(local_id).await?
+            .into_iter()
+            .find(|m| m.original_speaker_label == label)
+            .ok_or(AppError::NotFound(format!("Speaker {} not found", label)))
+    }
+
+    /// Names a speaker of the recording, creating its map entry if the
+    /// transcript hasn't been opened yet. A blank name restores the label.
+    pub async fn set_speaker_name(&self, local_id: &str, label: &str, display_name: &str) -> Result<CachedSpeakerMap, AppError> {
+        let name = match display_name.trim() {
+            "" => label,
+            name => name,
+        };
+        self.ensure_speaker_maps(local_id, &[label.to_string()]).await?;
+        self.update_speaker_map(local_id, label, Some(name), None).await
+    }
+
+    /// Forgets every speaker name and color of the recording; the labels get
+    /// fresh entries the next time its segments are read.
+    pub async fn delete_speaker_map(&self, local_id: &str) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM cached_speaker_maps WHERE local_recording_id = ?", local_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Tags a recording, creating the tag if it's new. Names are trimmed and
+    /// matched case-insensitively.
+    pub async fn add_tag(&self, local_id: &str, name: &str) -> Result<Tag, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation("Tag name cannot be empty".to_string()));
+        }
+
+        sqlx::query!("INSERT OR IGNORE INTO tags (name) VALUES (?)", name)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let tag = sqlx::query_as!(Tag, "SELECT id, name FROM tags WHERE name = ?", name)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query!(
+            "INSERT OR IGNORE INTO recording_tags (local_recording_id, tag_id) VALUES (?, ?)",
+            local_id,
+            tag.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(tag)
+    }
+
+    /// Untags a recording. A tag no recording uses any more is dropped.
+    pub async fn remove_tag(&self, local_id: &str, name: &str) -> Result<(), AppError> {
+        let name = name.trim();
+        sqlx::query!(
+            "DELETE FROM recording_tags WHERE local_recording_id = ? AND tag_id IN (SELECT id FROM tags WHERE name = ?)",
+            local_id,
+            name
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        sqlx::query!("DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM recording_tags)")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn get_tags_for_recording(&self, local_id: &str) -> Result<Vec<Tag>, AppError> {
+        sqlx::query_as!(
+            Tag,
+            r#"
+            SELECT t.id AS "id!", t.name
+            FROM tags t
+            JOIN recording_tags rt ON rt.tag_id = t.id
+            WHERE rt.local_recording_id = ?
+            ORDER BY t.name COLLATE NOCASE
+            "#,
+            local_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    pub async fn list_all_tags(&self) -> Result<Vec<Tag>, AppError> {
+        sqlx::query_as!(Tag, "SELECT id, name FROM tags ORDER BY name COLLATE NOCASE")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// Local ids of recordings carrying the tag.
+    pub async fn recordings_with_tag(&self, name: &str) -> Result<Vec<String>, AppError> {
+        let rows = sqlx::query!(
+            "SELECT rt.local_recording_id FROM recording_tags rt JOIN tags t ON t.id = rt.tag_id WHERE t.name = ?",
+            name.trim()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(rows.into_iter().map(|r| r.local_recording_id).collect())
+    }
+
+    /// Recordings carrying the tag, newest first. Trashed ones are left out.
+    pub async fn get_recordings_by_tag(&self, name: &str) -> Result<Vec<CachedRecording>, AppError> {
+        let name = name.trim();
+        sqlx::query_as!(
+            CachedRecording,
+            r#"
+            SELECT * FROM cached_recordings
+            WHERE deleted_at IS NULL
+              AND local_id IN (
+                  SELECT rt.local_recording_id FROM recording_tags rt
+                  JOIN tags t ON t.id = rt.tag_id
+                  WHERE t.name = ?
+              )
+            ORDER BY created_at DESC
+            "#,
+            name
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn search_ranks_title_matches_and_tracks_updates() {
+        let db = DatabaseService::new_in_memory().await.unwrap();
+        let standup = db.create_draft("Weekly standup".to_string(), 1.0, "/tmp/standup.wav".to_string(), None).await.unwrap();
+        let review = db.create_draft("Budget review".to_string(), 1.0, "/tmp/review.wav".to_string(), None).await.unwrap();
+        db.mark_as_synced(&standup.local_id, "We went over the budget forecast", None, None, "").await.unwrap();
+
+        let results = db.search_recordings("budg").await.unwrap();
+        let ids: Vec<&str> = results.iter().map(|r| r.recording.local_id.as_str()).collect();
+        assert_eq!(ids, [review.local_id.as_str(), standup.local_id.as_str()]);
+        assert_eq!(results[1].snippet.as_deref(), Some("We went over the <mark>budget</mark> forecast"));
+
+        db.update_title(&review.local_id, "Planning").await.unwrap();
+        assert_eq!(db.search_recordings("budget").await.unwrap().len(), 1);
+
+        db.soft_delete_recording(&standup.local_id).await.unwrap();
+        assert!(db.search_recordings("budget").await.unwrap().is_empty());
+        assert!(db.search_recordings("\"(*)").await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn fts_query_quotes_each_word() {
+        assert_eq!(fts_query("q3 budget-review").as_deref(), Some("\"q3\"* \"budget-review\"*"));
+        assert_eq!(fts_query("say \"hi\"").as_deref(), Some("\"say\"* \"\"\"hi\"\"\"*"));
+        assert_eq!(fts_query("  * - "), None);
+    }
+
+    #[tokio::test]
+    async fn tags_are_shared_case_insensitively_and_dropped_when_unused() {
+        let db = DatabaseService::new_in_memory().await.unwrap();
+        let a = db.create_draft("A".to_string(), 1.0, "/tmp/a.wav".to_string(), None).await.unwrap();
+        let b = db.create_draft("B".to_string(), 1.0, "/tmp/b.wav".to_string(), None).await.unwrap();
+
+        let tag = db.add_tag(&a.local_id, " Project X ").await.unwrap();
+        assert_eq!(tag.name, "Project X");
+        assert_eq!(db.add_tag(&b.local_id, "project x").await.unwrap().id, tag.id);
+        db.add_tag(&b.local_id, "Interviews").await.unwrap();
+        assert!(matches!(db.add_tag(&b.local_id, "  ").await, Err(AppError::Validation(_))));
+
+        let names: Vec<String> = db.get_tags_for_recording(&b.local_id).await.unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["Interviews", "Project X"]);
+        assert_eq!(db.recordings_with_tag("PROJECT X").await.unwrap().len(), 2);
+
+        db.remove_tag(&b.local_id, "Interviews").await.unwrap();
+        let all: Vec<String> = db.list_all_tags().await.unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(all, ["Project X"]);
+    }
+
+    #[tokio::test]
+    async fn speaker_names_persist_until_the_recording_is_deleted() {
+        let db = DatabaseService::new_in_memory().await.unwrap();
+        let rec = db.create_draft("Call".to_string(), 1.0, "/tmp/call.wav".to_string(), None).await.unwrap();
+
+        let map = db.set_speaker_name(&rec.local_id, "Speaker 1", " Alice ").await.unwrap();
+        assert_eq!(map.display_name, "Alice");
+        assert!(map.color.is_some());
+        db.set_speaker_name(&rec.local_id, "Speaker 2", "Bob").await.unwrap();
+        db.set_speaker_name(&rec.local_id, "Speaker 2", "").await.unwrap();
+
+        let names: Vec<String> = db.get_speaker_maps(&rec.local_id).await.unwrap().into_iter().map(|m| m.display_name).collect();
+        assert_eq!(names, ["Alice", "Speaker 2"]);
+
+        db.delete_speaker_map(&rec.local_id).await.unwrap();
+        assert!(db.get_speaker_maps(&rec.local_id).await.unwrap().is_empty());
+        db.set_speaker_name(&rec.local_id, "Speaker 1", "Alice").await.unwrap();
+
+        db.delete_recording(&rec.local_id).await.unwrap();
+        assert!(db.get_speaker_maps(&rec.local_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn keep_offline_is_set_without_a_download() {
+        let db = DatabaseService::new_in_memory().await.unwrap();
+        let rec = db.create_draft("Local".to_string(), 1.0, "/tmp/local.wav".to_string(), None).await.unwrap();
+
+        db.set_keep_offline(&rec.local_id, true).await.unwrap();
+        let pinned = db.get_recording(&rec.local_id).await.unwrap();
+        assert!(pinned.keep_offline);
+        assert!(pinned.local_audio_path.is_none());
+        assert_eq!(pinned.local_file_path.as_deref(), Some("/tmp/local.wav"));
+
+        assert!(matches!(db.set_keep_offline("missing", true).await, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn upsert_reports_status_transitions() {
+        let db = DatabaseService::new_in_memory().await.unwrap();
+        for status in ["processing", "processing"] {
+            let (_, old) = db.upsert_remote_recording("job", "Job", status, "2024-12-01T10:00:00Z", None, None, None, None).await.unwrap();
+            assert!(old.is_none());
+        }
+        let (rec, old) = db.upsert_remote_recording("job", "Job", "completed", "2024-12-01T10:00:00Z", None, None, None, None).await.unwrap();
+        assert_eq!(old, Some(SyncStatus::ProcessingRemote.to_string()));
+        assert_eq!(rec.sync_status, SyncStatus::CompletedSynced.to_string());
+    }
+
+    #[tokio::test]
+    async fn deleting_a_recording_drops_its_tags() {
+        let db = DatabaseService::new_in_memory().await.unwrap();
+        let a = db.create_draft("A".to_string(), 1.0, "/tmp/a.wav".to_string(), None).await.unwrap();
+        let b = db.create_draft("B".to_string(), 1.0, "/tmp/b.wav".to_string(), None).await.unwrap();
+        db.add_tag(&a.local_id, "standup").await.unwrap();
+        db.add_tag(&b.local_id, "standup").await.unwrap();
+        db.add_tag(&a.local_id, "client-x").await.unwrap();
+
+        db.delete_recording(&a.local_id).await.unwrap();
+
+        let tagged = db.get_recordings_by_tag("standup").await.unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].local_id, b.local_id);
+        let all: Vec<String> = db.list_all_tags().await.unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(all, ["standup"]);
+    }
+
+    #[tokio::test]
+    async fn pages_sort_by_title_and_duration() {
+        let db = DatabaseService::new_in_memory().await.unwrap();
+        for (title, duration) in [("banana", 3.0), ("Apple", 1.0), ("cherry", 2.0)] {
+            db.create_draft(title.to_string(), duration, format!("/tmp/{}.wav", title), None).await.unwrap();
+        }
+        let titles = |recs: Vec<CachedRecording>| recs.into_iter().map(|r| r.title).collect::<Vec<_>>();
+
+        let first = db.get_recordings_paginated(0, 2, SortField::Title, SortOrder::Asc).await.unwrap();
+        assert_eq!(titles(first), ["Apple", "banana"]);
+        let second = db.get_recordings_paginated(2, 2, SortField::Title, SortOrder::Asc).await.unwrap();
+        assert_eq!(titles(second), ["cherry"]);
+
+        let longest = db.get_recordings_paginated(0, 10, SortField::Duration, SortOrder::Desc).await.unwrap();
+        assert_eq!(titles(longest), ["banana", "cherry", "Apple"]);
+        assert_eq!(db.count_recordings().await.unwrap(), 3);
+    }
 }
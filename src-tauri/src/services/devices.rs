@@ -0,0 +1,133 @@
+use std::mem;
+use std::ptr;
+use core_foundation::base::TCFType;
+use core_foundation::string::CFString;
+use coreaudio_sys::{
+    kAudioDevicePropertyDeviceNameCFString, kAudioDevicePropertyDeviceUID, kAudioDevicePropertyScopeInput,
+    kAudioDevicePropertyStreamConfiguration, kAudioHardwarePropertyDevices, kAudioObjectPropertyElementMaster,
+    kAudioObjectPropertyScopeGlobal, kAudioObjectSystemObject, AudioBufferList, AudioDeviceID,
+    AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize, AudioObjectPropertyAddress, CFStringRef,
+};
+use cpal::traits::{DeviceTrait, HostTrait};
+
+/// An input device with an ID that survives reordering and identical names.
+pub struct InputDevice {
+    /// CoreAudio device UID; see `fallback_id` when it can't be read.
+    pub id: String,
+    /// Name for display, with ` (2)`, ` (3)`... appended to duplicates.
+    pub display_name: String,
+    pub device: cpal::Device,
+}
+
+/// Input devices in cpal's order, each paired with its CoreAudio UID.
+///
+/// cpal doesn't expose the `AudioDeviceID` behind a device, but it enumerates
+/// `kAudioHardwarePropertyDevices` in order, so the n-th cpal input device
+/// named X is the n-th CoreAudio input device named X.
+pub fn input_devices() -> Vec<InputDevice> {
+    let Ok(devices) = cpal::default_host().input_devices() else {
+        return Vec::new();
+    };
+    let core_audio = core_audio_inputs();
+
+    let mut seen: Vec<String> = Vec::new();
+    devices.map(|device| {
+        let name = device.name().unwrap_or("Unknown Device".to_string());
+        let ordinal = seen.iter().filter(|n| *n == &name).count();
+        seen.push(name.clone());
+
+        let id = core_audio.iter()
+            .filter(|(_, n)| n == &name)
+            .nth(ordinal)
+            .map(|(uid, _)| uid.clone())
+            .unwrap_or_else(|| fallback_id(&name, ordinal));
+        let display_name = if ordinal == 0 { name } else { format!("{} ({})", name, ordinal + 1) };
+        InputDevice { id, display_name, device }
+    }).collect()
+}
+
+/// ID for a device whose UID can't be read: its name, with `#n` appended
+/// for the n-th device of that name so identical interfaces stay distinct.
+fn fallback_id(name: &str, ordinal: usize) -> String {
+    if ordinal == 0 {
+        name.to_string()
+    } else {
+        format!("{}#{}", name, ordinal + 1)
+    }
+}
+
+/// Resolves a device by UID, falling back to the first device with that
+/// name so IDs saved by older versions (which were names) keep working.
+pub fn find_input_device(id_or_name: &str) -> Option<cpal::Device> {
+    let devices = input_devices();
+    let index = devices.iter().position(|d| d.id == id_or_name)
+        .or_else(|| devices.iter().position(|d| d.device.name().ok().as_deref() == Some(id_or_name)))?;
+    devices.into_iter().nth(index).map(|d| d.device)
+}
+
+/// `(uid, name)` of every CoreAudio device with input channels, in system order.
+fn core_audio_inputs() -> Vec<(String, String)> {
+    let address = AudioObjectPropertyAddress {
+        mSelector: kAudioHardwarePropertyDevices,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+
+    let mut size = 0u32;
+    // SAFETY: plain CoreAudio property queries with correctly sized buffers
+    let ids: Vec<AudioDeviceID> = unsafe {
+        if AudioObjectGetPropertyDataSize(kAudioObjectSystemObject, &address, 0, ptr::null(), &mut size) != 0 {
+            return Vec::new();
+        }
+        let mut ids = vec![0 as AudioDeviceID; size as usize / mem::size_of::<AudioDeviceID>()];
+        if AudioObjectGetPropertyData(kAudioObjectSystemObject, &address, 0, ptr::null(), &mut size, ids.as_mut_ptr() as *mut _) != 0 {
+            return Vec::new();
+        }
+        ids
+    };
+
+    ids.into_iter()
+        .filter(|&id| has_input(id))
+        .filter_map(|id| Some((string_property(id, kAudioDevicePropertyDeviceUID)?, string_property(id, kAudioDevicePropertyDeviceNameCFString)?)))
+        .collect()
+}
+
+fn string_property(device: AudioDeviceID, selector: u32) -> Option<String> {
+    let address = AudioObjectPropertyAddress {
+        mSelector: selector,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+    let mut value: CFStringRef = ptr::null();
+    let mut size = mem::size_of::<CFStringRef>() as u32;
+    // SAFETY: the property is a CFStringRef we own (create rule) on success
+    unsafe {
+        let status = AudioObjectGetPropertyData(device, &address, 0, ptr::null(), &mut size, &mut value as *mut _ as *mut _);
+        if status != 0 || value.is_null() {
+            return None;
+        }
+        Some(CFString::wrap_under_create_rule(value as core_foundation::string::CFStringRef).to_string())
+    }
+}
+
+fn has_input(device: AudioDeviceID) -> bool {
+    let address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyStreamConfiguration,
+        mScope: kAudioDevicePropertyScopeInput,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+    let mut size = 0u32;
+    // SAFETY: the buffer list is read into an allocation of the reported size
+    unsafe {
+        if AudioObjectGetPropertyDataSize(device, &address, 0, ptr::null(), &mut size) != 0 || size == 0 {
+            return false;
+        }
+        let mut buffer = vec![0u8; size as usize];
+        if AudioObjectGetPropertyData(device, &address, 0, ptr::null(), &mut size, buffer.as_mut_ptr() as *mut _) != 0 {
+            return false;
+        }
+        let list = &*(buffer.as_ptr() as *const AudioBufferList);
+        let buffers = std::slice::from_raw_parts(list.mBuffers.as_ptr(), list.mNumberBuffers as usize);
+        buffers.iter().any(|b| b.mNumberChannels > 0)
+    }
+}
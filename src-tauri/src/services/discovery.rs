@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use objc::runtime::Object;
+use objc::{class, msg_send, sel, sel_impl};
 use screencapturekit::sc_shareable_content::SCShareableContent;
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -7,34 +11,138 @@ pub struct RunnableApp {
     pub id: String, // Bundle ID
     pub pid: i32,
     pub name: String,
-    pub icon: Vec<u8>, // Optional: for UI if needed later
+    pub icon: Vec<u8>, // PNG; empty if the icon couldn't be read
 }
 
-pub async fn get_running_meeting_apps() -> Result<Vec<RunnableApp>, String> {
+/// Meeting apps offered for capture out of the box; settings can add more.
+pub const DEFAULT_MEETING_BUNDLE_IDS: [&str; 4] = [
+    "us.zoom.xos",
+    "com.tinyspeck.slackmacgap",
+    "com.microsoft.teams",
+    "com.microsoft.teams2",
+];
+
+/// The built-in bundle ids followed by `extra`, blanks and duplicates dropped.
+pub fn meeting_bundle_ids(extra: &[String]) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    let candidates = DEFAULT_MEETING_BUNDLE_IDS.iter().map(|id| id.to_string())
+        .chain(extra.iter().map(|id| id.trim().to_string()));
+    for id in candidates {
+        if !id.is_empty() && !ids.iter().any(|known| known.eq_ignore_ascii_case(&id)) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// Running apps whose bundle id is a known meeting app or in `extra_bundle_ids`.
+pub async fn get_running_meeting_apps(extra_bundle_ids: &[String]) -> Result<Vec<RunnableApp>, String> {
+    let target_bundle_ids = meeting_bundle_ids(extra_bundle_ids);
+    let apps = get_all_running_apps().await?;
+    Ok(apps.into_iter()
+        .filter(|app| target_bundle_ids.iter().any(|id| id.eq_ignore_ascii_case(&app.id)))
+        .map(|mut app| {
+            app.icon = app_icon(&app.id, app.pid).unwrap_or_default();
+            app
+        })
+        .collect())
+}
+
+/// Rendered icons by bundle id; apps are polled, icons don't change.
+fn icon_cache() -> &'static Mutex<HashMap<String, Vec<u8>>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Vec<u8>>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// The app's icon as PNG, cached by bundle id. Failures aren't cached, so
+/// they're retried on the next poll.
+fn app_icon(bundle_id: &str, pid: i32) -> Option<Vec<u8>> {
+    if let Some(icon) = icon_cache().lock().unwrap().get(bundle_id) {
+        return Some(icon.clone());
+    }
+    let icon = render_app_icon(pid)?;
+    icon_cache().lock().unwrap().insert(bundle_id.to_string(), icon.clone());
+    Some(icon)
+}
+
+/// `NSRunningApplication.icon` for `pid`, encoded as PNG via `NSBitmapImageRep`.
+fn render_app_icon(pid: i32) -> Option<Vec<u8>> {
+    /// `NSBitmapImageFileTypePNG`
+    const PNG_FILE_TYPE: usize = 4;
+
+    objc::rc::autoreleasepool(|| {
+        // SAFETY: AppKit messages on objects checked for nil; everything
+        // returned is autoreleased and copied out before the pool drains
+        unsafe {
+            let app: *mut Object = msg_send![class!(NSRunningApplication), runningApplicationWithProcessIdentifier: pid];
+            if app.is_null() {
+                return None;
+            }
+            let image: *mut Object = msg_send![app, icon];
+            if image.is_null() {
+                return None;
+            }
+            let tiff: *mut Object = msg_send![image, TIFFRepresentation];
+            if tiff.is_null() {
+                return None;
+            }
+            let rep: *mut Object = msg_send![class!(NSBitmapImageRep), imageRepWithData: tiff];
+            if rep.is_null() {
+                return None;
+            }
+            let properties: *mut Object = msg_send![class!(NSDictionary), dictionary];
+            let png: *mut Object = msg_send![rep, representationUsingType: PNG_FILE_TYPE properties: properties];
+            if png.is_null() {
+                return None;
+            }
+            let length: usize = msg_send![png, length];
+            let bytes: *const u8 = msg_send![png, bytes];
+            if bytes.is_null() || length == 0 {
+                return None;
+            }
+            Some(std::slice::from_raw_parts(bytes, length).to_vec())
+        }
+    })
+}
+
+/// Every application ScreenCaptureKit can capture, for picking one that
+/// isn't a known meeting app.
+pub async fn get_all_running_apps() -> Result<Vec<RunnableApp>, String> {
     let content = SCShareableContent::current();
         //.map_err(|e| format!("Failed to get shareable content: {:?}", e))?; // v0.2.8 might not return Result or different error
 
-    let target_bundle_ids = vec![
-        "us.zoom.xos",
-        "com.tinyspeck.slackmacgap",
-        "com.microsoft.teams",
-        "com.microsoft.teams2",
-    ];
-
     let mut apps = Vec::new();
 
     for app in content.applications {
         if let Some(bundle_id) = &app.bundle_identifier {
-            if target_bundle_ids.contains(&bundle_id.as_str()) {
-                apps.push(RunnableApp {
-                    id: bundle_id.clone(),
-                    pid: app.process_id,
-                    name: app.application_name.clone().unwrap_or_default(),
-                    icon: Vec::new(), // Placeholder, SCShareableContent doesn't give icon bytes directly usually
-                });
-            }
+            apps.push(RunnableApp {
+                id: bundle_id.clone(),
+                pid: app.process_id,
+                name: app.application_name.clone().unwrap_or_default(),
+                icon: Vec::new(), // Filled in for meeting apps; SCShareableContent has no icons
+            });
         }
     }
 
     Ok(apps)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_bundle_ids_are_merged_with_defaults() {
+        let extra = vec![
+            "com.hnc.Discord".to_string(),
+            "US.ZOOM.XOS".to_string(),
+            "  ".to_string(),
+            "com.hnc.Discord".to_string(),
+        ];
+        let ids = meeting_bundle_ids(&extra);
+
+        assert_eq!(ids.len(), DEFAULT_MEETING_BUNDLE_IDS.len() + 1);
+        assert_eq!(ids.last().map(String::as_str), Some("com.hnc.Discord"));
+        assert_eq!(ids.iter().filter(|id| id.eq_ignore_ascii_case("us.zoom.xos")).count(), 1);
+    }
+}
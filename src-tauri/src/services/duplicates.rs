@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use specta::Type;
+use crate::services::db::CachedRecording;
+
+#[derive(Debug, Clone, Copy, Serialize, Type, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    /// Identical audio, by `file_hash`.
+    SameAudio,
+    /// Same title and duration; likely, but not certainly, the same recording.
+    SameTitleAndDuration,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct DuplicateGroup {
+    pub reason: DuplicateReason,
+    /// Oldest first, which is the usual one to keep.
+    pub recordings: Vec<CachedRecording>,
+}
+
+/// Groups recordings that look like the same audio. Hash matches are certain
+/// and checked first; whatever isn't in a hash group is then matched on
+/// title and whole-second duration, which catches legacy rows without a hash.
+pub fn find_groups(recordings: Vec<CachedRecording>) -> Vec<DuplicateGroup> {
+    let mut by_hash: HashMap<String, Vec<CachedRecording>> = HashMap::new();
+    let mut rest = Vec::new();
+    for recording in recordings {
+        match recording.file_hash.clone() {
+            Some(hash) => by_hash.entry(hash).or_default().push(recording),
+            None => rest.push(recording),
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (_, members) in by_hash {
+        if members.len() > 1 {
+            groups.push(group(DuplicateReason::SameAudio, members));
+        } else {
+            rest.extend(members);
+        }
+    }
+
+    let mut by_title: HashMap<(String, i64), Vec<CachedRecording>> = HashMap::new();
+    for recording in rest {
+        // Zero-length rows are server jobs we haven't measured, not evidence of a match
+        if recording.duration_sec < 1.0 {
+            continue;
+        }
+        let key = (recording.title.trim().to_lowercase(), recording.duration_sec.round() as i64);
+        by_title.entry(key).or_default().push(recording);
+    }
+    groups.extend(by_title.into_values()
+        .filter(|members| members.len() > 1)
+        .map(|members| group(DuplicateReason::SameTitleAndDuration, members)));
+
+    groups.sort_by(|a, b| a.recordings[0].created_at.cmp(&b.recordings[0].created_at));
+    groups
+}
+
+fn group(reason: DuplicateReason, mut recordings: Vec<CachedRecording>) -> DuplicateGroup {
+    recordings.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    DuplicateGroup { reason, recordings }
+}
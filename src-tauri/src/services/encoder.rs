@@ -0,0 +1,319 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use flacenc::bitsink::ByteSink;
+use flacenc::component::{BitRepr, StreamInfo};
+use flacenc::error::Verify;
+use flacenc::source::{Fill, FrameBuf};
+use hound::{WavSpec, WavWriter};
+use mp3lame_encoder::{Bitrate, FlushNoGap, InterleavedPcm, Quality};
+use serde::{Serialize, Deserialize};
+use specta::Type;
+use crate::error::AppError;
+
+/// Container/codec new recordings are written in.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, Type, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// 32-bit float, uncompressed. Large, but nothing is lost and it's what
+    /// every earlier version wrote.
+    #[default]
+    Wav,
+    /// 128 kbps CBR; a tenth of the WAV size, plenty for transcription.
+    Mp3,
+    /// Lossless, roughly half the size of 16-bit WAV for speech.
+    Flac,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Wav => "wav",
+            OutputFormat::Mp3 => "mp3",
+            OutputFormat::Flac => "flac",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Wav => "audio/wav",
+            OutputFormat::Mp3 => "audio/mpeg",
+            OutputFormat::Flac => "audio/flac",
+        }
+    }
+
+    /// The format a file name's extension implies, if it's one we write.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        [OutputFormat::Wav, OutputFormat::Mp3, OutputFormat::Flac]
+            .into_iter()
+            .find(|f| f.extension().eq_ignore_ascii_case(extension))
+    }
+}
+
+/// Sink for the mixer's interleaved output. Implementations may buffer;
+/// nothing is guaranteed on disk until `finalize`.
+pub trait AudioEncoder: Send {
+    fn write_sample(&mut self, sample: f32) -> Result<(), AppError>;
+    fn finalize(self: Box<Self>) -> Result<(), AppError>;
+}
+
+/// Shared between the recorder, which finalizes it, and the mixer thread.
+pub type SharedEncoder = Arc<Mutex<Option<Box<dyn AudioEncoder>>>>;
+
+/// MIME type to upload `path` with, from its extension.
+pub fn mime_for_path(path: &Path) -> &'static str {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .and_then(OutputFormat::from_extension)
+        .map_or("application/octet-stream", |f| f.mime_type())
+}
+
+/// Opens an encoder writing `format` to `path`.
+pub fn create_encoder(format: OutputFormat, path: &Path, sample_rate: u32, channels: u16) -> Result<Box<dyn AudioEncoder>, AppError> {
+    match format {
+        OutputFormat::Wav => Ok(Box::new(WavEncoder::create(path, sample_rate, channels)?)),
+        OutputFormat::Flac => Ok(Box::new(FlacEncoder::create(path, sample_rate, channels)?)),
+        OutputFormat::Mp3 => Ok(Box::new(Mp3Encoder::create(path, sample_rate, channels)?)),
+    }
+}
+
+pub struct WavEncoder {
+    writer: WavWriter<BufWriter<File>>,
+}
+
+impl WavEncoder {
+    pub fn create(path: &Path, sample_rate: u32, channels: u16) -> Result<Self, AppError> {
+        let spec = WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let writer = WavWriter::create(path, spec)
+            .map_err(|e| AppError::Audio(format!("Failed to create WAV writer: {:?}", e)))?;
+        Ok(Self { writer })
+    }
+}
+
+impl AudioEncoder for WavEncoder {
+    fn write_sample(&mut self, sample: f32) -> Result<(), AppError> {
+        self.writer.write_sample(sample)
+            .map_err(|e| AppError::Audio(format!("Failed to write WAV sample: {:?}", e)))
+    }
+
+    fn finalize(self: Box<Self>) -> Result<(), AppError> {
+        self.writer.finalize()
+            .map_err(|e| AppError::Audio(format!("Failed to finalize WAV: {:?}", e)))
+    }
+}
+
+/// FLAC via `flacenc`. Samples are staged as 16-bit PCM next to the output
+/// while recording, keeping the mixer thread light, and encoded a block at a
+/// time in `finalize`, so memory stays flat however long the recording is.
+pub struct FlacEncoder {
+    path: PathBuf,
+    staging_path: PathBuf,
+    staging: BufWriter<File>,
+    sample_rate: u32,
+    channels: u16,
+}
+
+impl FlacEncoder {
+    const BITS_PER_SAMPLE: usize = 16;
+    /// `fLaC`, the STREAMINFO block header and its 34-byte body.
+    const HEADER_LEN: usize = 42;
+
+    pub fn create(path: &Path, sample_rate: u32, channels: u16) -> Result<Self, AppError> {
+        let staging_path = path.with_extension("flac.pcm");
+        let staging = BufWriter::new(File::create(&staging_path)?);
+        Ok(Self { path: path.to_path_buf(), staging_path, staging, sample_rate, channels })
+    }
+
+    fn header(stream_info: &StreamInfo) -> Result<Vec<u8>, AppError> {
+        let mut sink = ByteSink::new();
+        stream_info.write(&mut sink)
+            .map_err(|e| AppError::Audio(format!("Failed to serialize FLAC header: {:?}", e)))?;
+        let body = sink.as_slice();
+
+        let mut header = Vec::with_capacity(Self::HEADER_LEN);
+        header.extend_from_slice(b"fLaC");
+        // Last-metadata-block flag, block type 0 (STREAMINFO), 24-bit length
+        header.push(0x80);
+        header.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        header.extend_from_slice(body);
+        Ok(header)
+    }
+}
+
+impl AudioEncoder for FlacEncoder {
+    fn write_sample(&mut self, sample: f32) -> Result<(), AppError> {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        self.staging.write_all(&value.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn finalize(mut self: Box<Self>) -> Result<(), AppError> {
+        self.staging.flush()?;
+        let channels = self.channels as usize;
+
+        let config = flacenc::config::Encoder::default().into_verified()
+            .map_err(|(_, e)| AppError::Audio(format!("Invalid FLAC encoder config: {:?}", e)))?;
+        let block_size = config.block_size;
+        let mut stream_info = StreamInfo::new(self.sample_rate as usize, channels, Self::BITS_PER_SAMPLE)
+            .map_err(|e| AppError::Audio(format!("Invalid FLAC stream parameters: {:?}", e)))?;
+        let mut framebuf = FrameBuf::with_size(channels, block_size)
+            .map_err(|e| AppError::Audio(format!("Invalid FLAC block size: {:?}", e)))?;
+
+        let mut staging = BufReader::new(File::open(&self.staging_path)?);
+        let mut out = BufWriter::new(File::create(&self.path)?);
+        // Rewritten once the frame sizes and sample count are known
+        out.write_all(&[0; Self::HEADER_LEN])?;
+
+        let block_bytes = block_size * channels * 2;
+        let mut bytes = Vec::with_capacity(block_bytes);
+        let mut samples = Vec::with_capacity(block_size * channels);
+        let mut total_samples = 0;
+        for frame_number in 0.. {
+            bytes.clear();
+            (&mut staging).take(block_bytes as u64).read_to_end(&mut bytes)?;
+            samples.clear();
+            samples.extend(bytes.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]) as i32));
+            // A stop mid-write can leave a partial sample frame at the end
+            samples.truncate(samples.len() / channels * channels);
+            if samples.is_empty() {
+                break;
+            }
+
+            framebuf.fill_interleaved(&samples)
+                .map_err(|e| AppError::Audio(format!("Failed to buffer FLAC block: {:?}", e)))?;
+            let frame = flacenc::encode_fixed_size_frame(&config, &framebuf, frame_number, &stream_info)
+                .map_err(|e| AppError::Audio(format!("FLAC encoding failed: {:?}", e)))?;
+            stream_info.update_frame_info(&frame);
+
+            let mut sink = ByteSink::new();
+            frame.write(&mut sink)
+                .map_err(|e| AppError::Audio(format!("Failed to serialize FLAC frame: {:?}", e)))?;
+            out.write_all(sink.as_slice())?;
+            total_samples += samples.len() / channels;
+        }
+
+        stream_info.set_total_samples(total_samples);
+        out.seek(SeekFrom::Start(0))?;
+        out.write_all(&Self::header(&stream_info)?)?;
+        out.flush()?;
+
+        let _ = std::fs::remove_file(&self.staging_path);
+        Ok(())
+    }
+}
+
+/// MP3 via LAME. Samples are handed to the encoder a few frames at a time and
+/// the encoded bytes written straight out, so nothing accumulates.
+pub struct Mp3Encoder {
+    encoder: mp3lame_encoder::Encoder,
+    file: BufWriter<File>,
+    pcm: Vec<i16>,
+    chunk_len: usize,
+    out: Vec<u8>,
+}
+
+impl Mp3Encoder {
+    /// Samples per channel in an MPEG-1 Layer III frame.
+    const FRAME_SAMPLES: usize = 1152;
+    /// Frames buffered before each encode call.
+    const FRAMES_PER_CHUNK: usize = 4;
+
+    pub fn create(path: &Path, sample_rate: u32, channels: u16) -> Result<Self, AppError> {
+        if channels > 2 {
+            return Err(AppError::Audio("MP3 supports at most 2 channels; use WAV or FLAC for dual-track recordings".to_string()));
+        }
+        let build_error = |e: mp3lame_encoder::BuildError| AppError::Audio(format!("Failed to configure MP3 encoder: {:?}", e));
+
+        let mut builder = mp3lame_encoder::Builder::new()
+            .ok_or(AppError::Audio("Failed to create MP3 encoder".to_string()))?;
+        builder.set_num_channels(channels as u8).map_err(build_error)?;
+        builder.set_sample_rate(sample_rate).map_err(build_error)?;
+        builder.set_brate(Bitrate::Kbps128).map_err(build_error)?;
+        builder.set_quality(Quality::Good).map_err(build_error)?;
+        let encoder = builder.build().map_err(build_error)?;
+
+        let chunk_len = Self::FRAME_SAMPLES * Self::FRAMES_PER_CHUNK * channels as usize;
+        Ok(Self {
+            encoder,
+            file: BufWriter::new(File::create(path)?),
+            pcm: Vec::with_capacity(chunk_len),
+            chunk_len,
+            out: Vec::with_capacity(mp3lame_encoder::max_required_buffer_size(chunk_len)),
+        })
+    }
+
+    fn encode_buffered(&mut self) -> Result<(), AppError> {
+        self.out.clear();
+        self.out.reserve(mp3lame_encoder::max_required_buffer_size(self.pcm.len()));
+        let written = self.encoder.encode(InterleavedPcm(&self.pcm), self.out.spare_capacity_mut())
+            .map_err(|e| AppError::Audio(format!("MP3 encoding failed: {:?}", e)))?;
+        // SAFETY: the encoder initialized the first `written` bytes of spare capacity
+        unsafe { self.out.set_len(written) };
+        self.file.write_all(&self.out)?;
+        self.pcm.clear();
+        Ok(())
+    }
+}
+
+impl AudioEncoder for Mp3Encoder {
+    fn write_sample(&mut self, sample: f32) -> Result<(), AppError> {
+        self.pcm.push((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+        if self.pcm.len() >= self.chunk_len {
+            self.encode_buffered()?;
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self: Box<Self>) -> Result<(), AppError> {
+        if !self.pcm.is_empty() {
+            self.encode_buffered()?;
+        }
+
+        self.out.clear();
+        self.out.reserve(mp3lame_encoder::max_required_buffer_size(0));
+        let written = self.encoder.flush::<FlushNoGap>(self.out.spare_capacity_mut())
+            .map_err(|e| AppError::Audio(format!("Failed to flush MP3 encoder: {:?}", e)))?;
+        // SAFETY: as in encode_buffered
+        unsafe { self.out.set_len(written) };
+        self.file.write_all(&self.out)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::integrity;
+
+    #[test]
+    fn flac_encodes_recordings_longer_than_one_block() {
+        let dir = std::env::temp_dir().join(format!("scriberr-encoder-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("take.flac");
+
+        // A few blocks plus a partial one, as interleaved stereo
+        let frames = 3 * 4096 + 1000;
+        let mut encoder = create_encoder(OutputFormat::Flac, &path, 48_000, 2).unwrap();
+        for i in 0..frames {
+            let sample = (i as f32 * 0.01).sin() * 0.5;
+            encoder.write_sample(sample).unwrap();
+            encoder.write_sample(-sample).unwrap();
+        }
+        encoder.finalize().unwrap();
+
+        let info = integrity::inspect_audio(&path).unwrap();
+        assert_eq!(info.format, "flac");
+        assert_eq!((info.sample_rate, info.channels, info.bits_per_sample), (48_000, 2, 16));
+        assert!((info.duration_sec - frames as f64 / 48_000.0).abs() < 1e-9);
+        assert!(!path.with_extension("flac.pcm").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Emitter, Runtime};
+use crate::services::db::CachedRecording;
+
+/// Name of the single event carrying every library change.
+pub const LIBRARY_EVENT: &str = "library-event";
+
+/// Everything the recording list needs to stay current, as one typed stream.
+///
+/// Serialized as `{ "kind": "added", "data": { ...recording } }`, so the UI can
+/// switch exhaustively on `kind`:
+/// - `added` / `updated`: full `CachedRecording` rows, to upsert in place
+/// - `stopped`: the draft a recording just became, sent after its `added` so
+///   every window can react to the stop itself
+/// - `deleted`: the row to drop, by `local_id` or `remote_job_id` (tombstones
+///   from the server only carry the latter)
+/// - `sync_started` / `sync_progress` / `sync_completed`: bracket a sync run;
+///   `sync_completed` follows every `sync_started`, even when the run fails,
+///   and a full refetch on it is always safe
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum LibraryEvent {
+    Added(CachedRecording),
+    Updated(CachedRecording),
+    Stopped(CachedRecording),
+    Deleted {
+        local_id: Option<String>,
+        remote_job_id: Option<String>,
+    },
+    SyncStarted,
+    SyncProgress {
+        page: u32,
+        processed: usize,
+    },
+    SyncCompleted,
+}
+
+impl LibraryEvent {
+    pub fn emit<R: Runtime>(self, app: &AppHandle<R>) {
+        // Ignore errors if the app is closing
+        let _ = app.emit(LIBRARY_EVENT, self);
+    }
+}
@@ -0,0 +1,233 @@
+use serde::Deserialize;
+use specta::Type;
+use crate::services::transcript::LabeledSegment;
+
+#[derive(Debug, Clone, Copy, Deserialize, Type, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Srt,
+    Vtt,
+    Txt,
+    Md,
+}
+
+impl ExportFormat {
+    /// Subtitles need timed segments; documents can fall back to plain text.
+    pub fn is_subtitle(&self) -> bool {
+        matches!(self, ExportFormat::Srt | ExportFormat::Vtt)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Srt => "srt",
+            ExportFormat::Vtt => "vtt",
+            ExportFormat::Txt => "txt",
+            ExportFormat::Md => "md",
+        }
+    }
+}
+
+/// A subtitle entry with its timing made safe to display.
+struct Cue {
+    start: f64,
+    end: f64,
+    speaker: Option<String>,
+    text: String,
+}
+
+/// Sorts segments by start and drops empty ones. A cue running into the next
+/// one is cut where the next starts, and none ends before it starts.
+fn cues(segments: &[LabeledSegment]) -> Vec<Cue> {
+    let mut cues: Vec<Cue> = segments.iter()
+        .filter(|s| !s.segment.text.trim().is_empty())
+        .map(|s| {
+            let start = s.segment.start.max(0.0);
+            Cue {
+                start,
+                end: s.segment.end.max(start),
+                // The name the user gave the speaker, else the raw label
+                speaker: s.display_name.clone().or_else(|| s.segment.speaker.clone()),
+                text: s.segment.text.trim().to_string(),
+            }
+        })
+        .collect();
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    for i in 1..cues.len() {
+        let next_start = cues[i].start;
+        let previous = &mut cues[i - 1];
+        // Cues starting together are left to overlap rather than vanish
+        if previous.end > next_start && next_start > previous.start {
+            previous.end = next_start;
+        }
+    }
+    cues
+}
+
+/// `HH:MM:SS` plus milliseconds after `separator` (`,` for SRT, `.` for VTT).
+fn timestamp(seconds: f64, separator: char) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    let (hours, rest) = (total_ms / 3_600_000, total_ms % 3_600_000);
+    let (minutes, rest) = (rest / 60_000, rest % 60_000);
+    let (secs, ms) = (rest / 1000, rest % 1000);
+    format!("{:02}:{:02}:{:02}{}{:03}", hours, minutes, secs, separator, ms)
+}
+
+pub fn to_srt(segments: &[LabeledSegment]) -> String {
+    let mut out = String::new();
+    for (index, cue) in cues(segments).iter().enumerate() {
+        let text = match &cue.speaker {
+            Some(speaker) => format!("{}: {}", speaker, cue.text),
+            None => cue.text.clone(),
+        };
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            timestamp(cue.start, ','),
+            timestamp(cue.end, ','),
+            text
+        ));
+    }
+    out
+}
+
+pub fn to_vtt(segments: &[LabeledSegment]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues(segments) {
+        // Voice spans let players style or label speakers themselves
+        let text = match &cue.speaker {
+            Some(speaker) => format!("<v {}>{}", speaker.replace('>', ""), cue.text),
+            None => cue.text.clone(),
+        };
+        out.push_str(&format!("{} --> {}\n{}\n\n", timestamp(cue.start, '.'), timestamp(cue.end, '.'), text));
+    }
+    out
+}
+
+/// Runs of consecutive segments by the same speaker, as (speaker, text).
+fn speaker_blocks(segments: &[LabeledSegment]) -> Vec<(Option<String>, String)> {
+    let mut blocks: Vec<(Option<String>, String)> = Vec::new();
+    for cue in cues(segments) {
+        match blocks.last_mut() {
+            Some((speaker, text)) if *speaker == cue.speaker => {
+                text.push(' ');
+                text.push_str(&cue.text);
+            }
+            _ => blocks.push((cue.speaker, cue.text)),
+        }
+    }
+    blocks
+}
+
+/// Paragraph per speaker turn. Without segments, `fallback` is written as is.
+pub fn to_txt(segments: &[LabeledSegment], fallback: Option<&str>) -> String {
+    if segments.is_empty() {
+        return fallback.map(|text| format!("{}\n", text.trim())).unwrap_or_default();
+    }
+    speaker_blocks(segments).into_iter()
+        .map(|(speaker, text)| match speaker {
+            Some(speaker) => format!("{}: {}\n", speaker, text),
+            None => format!("{}\n", text),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Like `to_txt`, under the title as an H1 and the summary as a blockquote.
+pub fn to_markdown(title: &str, summary: Option<&str>, segments: &[LabeledSegment], fallback: Option<&str>) -> String {
+    let mut out = format!("# {}\n\n", title.trim());
+    if let Some(summary) = summary.map(str::trim).filter(|s| !s.is_empty()) {
+        for line in summary.lines() {
+            match line.trim_end() {
+                "" => out.push_str(">\n"),
+                line => out.push_str(&format!("> {}\n", line)),
+            }
+        }
+        out.push('\n');
+    }
+
+    if segments.is_empty() {
+        if let Some(text) = fallback {
+            out.push_str(&format!("{}\n", text.trim()));
+        }
+        return out;
+    }
+    let blocks: Vec<String> = speaker_blocks(segments).into_iter()
+        .map(|(speaker, text)| match speaker {
+            Some(speaker) => format!("**{}:** {}\n", speaker, text),
+            None => format!("{}\n", text),
+        })
+        .collect();
+    out.push_str(&blocks.join("\n"));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::transcript::TranscriptSegment;
+
+    fn segment(start: f64, end: f64, text: &str, speaker: Option<&str>, name: Option<&str>) -> LabeledSegment {
+        LabeledSegment {
+            segment: TranscriptSegment { start, end, text: text.to_string(), speaker: speaker.map(str::to_string) },
+            display_name: name.map(str::to_string),
+            color: None,
+        }
+    }
+
+    #[test]
+    fn txt_groups_consecutive_segments_by_speaker() {
+        let segments = [
+            segment(0.0, 1.0, "Hello.", Some("SPEAKER_00"), Some("Alice")),
+            segment(1.0, 2.0, "Still me.", Some("SPEAKER_00"), Some("Alice")),
+            segment(2.0, 3.0, "Hi.", Some("SPEAKER_01"), None),
+            segment(3.0, 4.0, "Narration", None, None),
+        ];
+        assert_eq!(to_txt(&segments, None), "Alice: Hello. Still me.\n\nSPEAKER_01: Hi.\n\nNarration\n");
+        assert_eq!(to_txt(&[], Some(" Just text ")), "Just text\n");
+    }
+
+    #[test]
+    fn markdown_has_title_summary_and_speaker_blocks() {
+        let segments = [segment(0.0, 1.0, "Hello.", Some("SPEAKER_00"), Some("Alice"))];
+        assert_eq!(
+            to_markdown("Standup", Some("Short.\n\nNothing else."), &segments, None),
+            "# Standup\n\n> Short.\n>\n> Nothing else.\n\n**Alice:** Hello.\n"
+        );
+        assert_eq!(to_markdown("Notes", None, &[], Some("Plain")), "# Notes\n\nPlain\n");
+    }
+
+    #[test]
+    fn timestamps_use_the_format_separator() {
+        assert_eq!(timestamp(3723.4567, ','), "01:02:03,457");
+        assert_eq!(timestamp(0.0, '.'), "00:00:00.000");
+    }
+
+    #[test]
+    fn srt_numbers_cues_and_names_speakers() {
+        let segments = [
+            segment(0.0, 1.5, " Hello", Some("SPEAKER_00"), Some("Alice")),
+            segment(1.5, 3.0, "Hi.", Some("SPEAKER_01"), None),
+            segment(3.0, 4.0, "No speaker", None, None),
+        ];
+        assert_eq!(
+            to_srt(&segments),
+            "1\n00:00:00,000 --> 00:00:01,500\nAlice: Hello\n\n\
+             2\n00:00:01,500 --> 00:00:03,000\nSPEAKER_01: Hi.\n\n\
+             3\n00:00:03,000 --> 00:00:04,000\nNo speaker\n\n"
+        );
+    }
+
+    #[test]
+    fn vtt_trims_overlaps_and_skips_empty_segments() {
+        let segments = [
+            segment(2.0, 5.0, "Second", None, Some("Bob")),
+            segment(0.0, 3.0, "First", None, None),
+            segment(4.0, 4.0, "  ", None, None),
+        ];
+        assert_eq!(
+            to_vtt(&segments),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:02.000\nFirst\n\n00:00:02.000 --> 00:00:05.000\n<v Bob>Second\n\n"
+        );
+    }
+}
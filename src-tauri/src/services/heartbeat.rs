@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Emitter, Runtime};
+use tokio::sync::RwLock;
+use tokio::time::Duration;
+use crate::services::http;
+use crate::services::storage::Settings;
+
+/// How long a probe may take before the server counts as unreachable.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Interval used while the heartbeat is disabled or unconfigured, to notice
+/// when that changes.
+const IDLE_POLL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ConnectivityChanged {
+    pub online: bool,
+}
+
+pub struct HeartbeatService;
+
+impl HeartbeatService {
+    /// Probes `heartbeat_path` on the configured server every
+    /// `heartbeat_interval_secs` and keeps `online` current, emitting
+    /// `connectivity-changed` on every flip. Any HTTP answer counts as online,
+    /// even an error status: the point is reachability, not health. While the
+    /// heartbeat is disabled or unconfigured the server is assumed online, so
+    /// a failed last probe doesn't keep uploads and sync paused.
+    pub fn start<R: Runtime>(app: AppHandle<R>, settings: Arc<RwLock<Settings>>, online: Arc<AtomicBool>) {
+        tauri::async_runtime::spawn(async move {
+            let client = match http::build_client() {
+                Ok(client) => client,
+                Err(e) => {
+                    eprintln!("Heartbeat disabled, failed to build client: {:?}", e);
+                    return;
+                }
+            };
+
+            loop {
+                let (url, interval) = {
+                    let s = settings.read().await;
+                    let url = (!s.scriberr_url.is_empty())
+                        .then(|| format!("{}/{}", s.scriberr_url.trim_end_matches('/'), s.heartbeat_path.trim_start_matches('/')));
+                    (url, s.heartbeat_interval_secs)
+                };
+
+                let (Some(url), true) = (url, interval > 0) else {
+                    set_online(&app, &online, true);
+                    tokio::time::sleep(IDLE_POLL).await;
+                    continue;
+                };
+
+                let reachable = client.head(&url).timeout(PROBE_TIMEOUT).send().await.is_ok();
+                set_online(&app, &online, reachable);
+
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+            }
+        });
+    }
+}
+
+fn set_online<R: Runtime>(app: &AppHandle<R>, online: &AtomicBool, reachable: bool) {
+    if online.swap(reachable, Ordering::SeqCst) != reachable {
+        println!("Server is now {}", if reachable { "online" } else { "offline" });
+        let _ = app.emit("connectivity-changed", ConnectivityChanged { online: reachable });
+    }
+}
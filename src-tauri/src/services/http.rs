@@ -0,0 +1,100 @@
+use std::time::Duration;
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use crate::error::AppError;
+
+const SNIPPET_LEN: usize = 200;
+
+/// Time allowed to open a connection to the server.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Default limit for a whole request; enough for any API call.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Uploads and downloads move whole recordings, so they set this instead.
+pub const TRANSFER_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Client for talking to the server. Requests give up after `REQUEST_TIMEOUT`
+/// unless they set their own, as transfers do with `TRANSFER_TIMEOUT`.
+pub fn build_client() -> Result<reqwest::Client, AppError> {
+    Ok(reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()?)
+}
+
+/// Client for open-ended streams, like audio piped to the player: no overall
+/// limit, but a stalled read fails after `REQUEST_TIMEOUT`.
+pub fn build_streaming_client() -> Result<reqwest::Client, AppError> {
+    Ok(reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(REQUEST_TIMEOUT)
+        .build()?)
+}
+
+/// Returns true if the response advertises a JSON content type.
+pub fn is_json(resp: &Response) -> bool {
+    content_type(resp).is_some_and(|ct| ct.contains("json"))
+}
+
+/// Returns true if the response advertises an HTML content type.
+pub fn is_html(resp: &Response) -> bool {
+    content_type(resp).is_some_and(|ct| ct.contains("text/html"))
+}
+
+fn content_type(resp: &Response) -> Option<String> {
+    resp.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_ascii_lowercase())
+}
+
+/// Parses a response body as JSON.
+///
+/// Reverse proxies (nginx, Caddy, Cloudflare...) answer with HTML error pages,
+/// which would otherwise surface as a cryptic serde error. Those are turned into
+/// `AppError::Server` with the status and a short snippet of the body.
+pub async fn read_json<T: DeserializeOwned>(resp: Response) -> Result<T, AppError> {
+    let status = resp.status();
+    let json_declared = is_json(&resp);
+    let html_declared = is_html(&resp);
+    let text = resp.text().await?;
+
+    if html_declared || (!json_declared && looks_like_html(&text)) {
+        return Err(AppError::Server(format!("Server returned HTML ({}): {}", status, snippet(&text))));
+    }
+
+    serde_json::from_str(&text).map_err(|e| {
+        AppError::Server(format!("Server returned an unexpected response ({}): {} [{}]", status, snippet(&text), e))
+    })
+}
+
+/// Builds an `AppError::Server` for a response that is known to be unusable
+/// (e.g. an HTML page where audio was expected), consuming the body for context.
+pub async fn unexpected_body(resp: Response) -> AppError {
+    let status = resp.status();
+    let kind = if is_html(&resp) { "HTML" } else { "an unexpected response" };
+    let text = resp.text().await.unwrap_or_default();
+    AppError::Server(format!("Server returned {} ({}): {}", kind, status, snippet(&text)))
+}
+
+fn looks_like_html(text: &str) -> bool {
+    let head = text.trim_start();
+    head.starts_with('<')
+}
+
+/// Collapses whitespace, prefers the `<title>` of HTML pages and truncates to a
+/// readable length.
+fn snippet(text: &str) -> String {
+    let lower = text.to_ascii_lowercase();
+    let source = match (lower.find("<title>"), lower.find("</title>")) {
+        (Some(start), Some(end)) if end > start + 7 => &text[start + 7..end],
+        _ => text,
+    };
+
+    let collapsed = source.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() > SNIPPET_LEN {
+        let truncated: String = collapsed.chars().take(SNIPPET_LEN).collect();
+        format!("{}...", truncated)
+    } else {
+        collapsed
+    }
+}
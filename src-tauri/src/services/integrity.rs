@@ -0,0 +1,250 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use crate::error::AppError;
+
+/// What the audio header on disk actually says, regardless of the DB.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct WavInfo {
+    pub path: String,
+    pub format: String, // "wav" or "flac"
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub sample_format: String, // "float" or "int"
+    pub duration_sec: f64,
+    pub file_size_bytes: u64,
+}
+
+/// Streams the file through SHA-256 and returns the lowercase hex digest.
+pub fn hash_file(path: &Path) -> Result<String, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+const SIGNATURE_EDGE_BYTES: u64 = 64 * 1024;
+const SIGNATURE_SAMPLE_BYTES: u64 = 16 * 1024;
+const SIGNATURE_SAMPLES: u64 = 8;
+
+/// Fast fingerprint for dedup: hashes the file size, the first and last
+/// 64 KiB and a few evenly spaced 16 KiB chunks. Equal signatures only mean
+/// "probably equal"; confirm with `hash_file`.
+pub fn content_signature(path: &Path) -> Result<String, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut hasher = Sha256::new();
+    hasher.update(len.to_le_bytes());
+
+    let mut ranges = vec![(0, SIGNATURE_EDGE_BYTES)];
+    for i in 1..=SIGNATURE_SAMPLES {
+        ranges.push((len * i / (SIGNATURE_SAMPLES + 1), SIGNATURE_SAMPLE_BYTES));
+    }
+    ranges.push((len.saturating_sub(SIGNATURE_EDGE_BYTES), SIGNATURE_EDGE_BYTES));
+
+    let mut buf = Vec::new();
+    for (offset, size) in ranges {
+        let size = size.min(len.saturating_sub(offset));
+        buf.resize(size as usize, 0);
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut buf)?;
+        hasher.update(&buf);
+    }
+
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Checks that the file starts with a recognizable audio header. WAV files are
+/// additionally parsed so an unfinalized/truncated header is rejected.
+pub fn has_valid_audio_header(path: &Path) -> Result<bool, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let mut header = [0u8; 12];
+    let mut filled = 0;
+    while filled < header.len() {
+        let read = file.read(&mut header[filled..])?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+
+    if filled < header.len() {
+        return Ok(false);
+    }
+
+    if &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE" {
+        let riff_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        let file_len = std::fs::metadata(path)?.len();
+        if riff_len + 8 > file_len {
+            return Ok(false);
+        }
+        return Ok(hound::WavReader::open(path).is_ok());
+    }
+
+    let known = &header[0..4] == b"fLaC"
+        || &header[0..4] == b"OggS"
+        || &header[0..3] == b"ID3"
+        || &header[4..8] == b"ftyp"
+        || (header[0] == 0xFF && header[1] & 0xE0 == 0xE0); // MPEG frame sync
+
+    Ok(known)
+}
+
+/// Verifies a local audio copy: the hash must match when one is known,
+/// otherwise the header must look sane.
+pub fn verify_audio_file(path: &Path, expected_hash: Option<&str>) -> Result<bool, AppError> {
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    if let Some(expected) = expected_hash {
+        return Ok(hash_file(path)?.eq_ignore_ascii_case(expected));
+    }
+
+    has_valid_audio_header(path)
+}
+
+/// Reads the header of a WAV or FLAC file, picked by its magic bytes. Other
+/// formats (e.g. MP3, which has no sample count up front) are rejected.
+pub fn inspect_audio(path: &Path) -> Result<WavInfo, AppError> {
+    let head = read_head(path, 12)?;
+    match audio_extension(None, &head) {
+        "flac" => inspect_flac(path),
+        "wav" if head.starts_with(b"RIFF") => inspect_wav(path),
+        _ => {
+            let extension = path.extension()
+                .and_then(|e| e.to_str())
+                .map(str::to_ascii_uppercase)
+                .unwrap_or_else(|| "this".to_string());
+            Err(AppError::Validation(format!("Inspecting {} files is not supported", extension)))
+        }
+    }
+}
+
+/// Reads the FLAC STREAMINFO block; duration comes from its sample count.
+fn inspect_flac(path: &Path) -> Result<WavInfo, AppError> {
+    let head = read_head(path, 42)?;
+    // fLaC, then a metadata block header whose type (low 7 bits) must be STREAMINFO
+    if head.len() < 42 || &head[0..4] != b"fLaC" || head[4] & 0x7F != 0 {
+        return Err(AppError::Audio("Not a readable FLAC file".to_string()));
+    }
+
+    // Sample rate (20 bits), channels - 1 (3), bits per sample - 1 (5), total samples (36)
+    let packed = u64::from_be_bytes(head[18..26].try_into().unwrap());
+    let sample_rate = (packed >> 44) as u32;
+    let total_samples = packed & 0xF_FFFF_FFFF;
+    let duration_sec = if sample_rate > 0 {
+        total_samples as f64 / sample_rate as f64
+    } else {
+        0.0
+    };
+
+    Ok(WavInfo {
+        path: path.to_string_lossy().to_string(),
+        format: "flac".to_string(),
+        sample_rate,
+        channels: ((packed >> 41) & 0x7) as u16 + 1,
+        bits_per_sample: ((packed >> 36) & 0x1F) as u16 + 1,
+        sample_format: "int".to_string(),
+        duration_sec,
+        file_size_bytes: std::fs::metadata(path)?.len(),
+    })
+}
+
+/// Reads the WAV header with hound; duration comes from the frame count.
+fn inspect_wav(path: &Path) -> Result<WavInfo, AppError> {
+    let reader = hound::WavReader::open(path)
+        .map_err(|e| AppError::Audio(format!("Not a readable WAV file: {}", e)))?;
+    let spec = reader.spec();
+    let duration_sec = if spec.sample_rate > 0 {
+        reader.duration() as f64 / spec.sample_rate as f64
+    } else {
+        0.0
+    };
+
+    Ok(WavInfo {
+        path: path.to_string_lossy().to_string(),
+        format: "wav".to_string(),
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        bits_per_sample: spec.bits_per_sample,
+        sample_format: match spec.sample_format {
+            hound::SampleFormat::Float => "float".to_string(),
+            hound::SampleFormat::Int => "int".to_string(),
+        },
+        duration_sec,
+        file_size_bytes: std::fs::metadata(path)?.len(),
+    })
+}
+
+/// Picks a file extension for downloaded audio. The magic bytes win because
+/// servers often send `application/octet-stream`; the content type is the
+/// fallback, then `wav`.
+pub fn audio_extension(content_type: Option<&str>, head: &[u8]) -> &'static str {
+    if head.len() >= 12 && &head[0..4] == b"RIFF" && &head[8..12] == b"WAVE" {
+        return "wav";
+    }
+    if head.starts_with(b"fLaC") {
+        return "flac";
+    }
+    if head.starts_with(b"OggS") {
+        // Opus streams carry an OpusHead packet right after the first page header
+        let is_opus = head.windows(8).any(|w| w == b"OpusHead");
+        return if is_opus { "opus" } else { "ogg" };
+    }
+    if head.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return "webm";
+    }
+    if head.len() >= 8 && &head[4..8] == b"ftyp" {
+        return "m4a";
+    }
+    if head.starts_with(b"ID3") || (head.len() >= 2 && head[0] == 0xFF && head[1] & 0xE0 == 0xE0) {
+        return "mp3";
+    }
+
+    let mime = content_type
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| ct.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    match mime.as_str() {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/ogg" => "ogg",
+        "audio/opus" => "opus",
+        "audio/flac" | "audio/x-flac" => "flac",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" | "audio/aac" => "m4a",
+        "audio/webm" => "webm",
+        _ => "wav",
+    }
+}
+
+/// MIME type for an extension returned by `audio_extension`.
+pub fn audio_mime(extension: &str) -> &'static str {
+    match extension {
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "ogg" | "opus" => "audio/ogg",
+        "webm" => "audio/webm",
+        "m4a" => "audio/mp4",
+        _ => "audio/wav",
+    }
+}
+
+/// First bytes of a file, for sniffing.
+pub fn read_head(path: &Path, len: usize) -> Result<Vec<u8>, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let mut head = Vec::with_capacity(len);
+    (&mut file).take(len as u64).read_to_end(&mut head)?;
+    Ok(head)
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use crate::error::AppError;
+
+/// Keychain service the API keys are filed under.
+const SERVICE: &str = "scriberr-companion";
+
+/// In-process store used instead of the OS keychain once `use_memory_store`
+/// has been called.
+static MEMORY_STORE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+/// Keeps secrets in memory for the rest of the process, for tests and
+/// environments without a keychain.
+pub fn use_memory_store() {
+    MEMORY_STORE.get_or_init(|| Mutex::new(HashMap::new()));
+}
+
+/// Account name for the API key of a server, so each server keeps its own key.
+pub fn api_key_account(scriberr_url: &str) -> String {
+    let url = scriberr_url.trim().trim_end_matches('/');
+    if url.is_empty() {
+        "default".to_string()
+    } else {
+        format!("api-key:{}", url)
+    }
+}
+
+pub fn get(account: &str) -> Result<Option<String>, AppError> {
+    if let Some(store) = MEMORY_STORE.get() {
+        return Ok(store.lock().unwrap().get(account).cloned());
+    }
+    match entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(access_error(e)),
+    }
+}
+
+pub fn set(account: &str, secret: &str) -> Result<(), AppError> {
+    if let Some(store) = MEMORY_STORE.get() {
+        store.lock().unwrap().insert(account.to_string(), secret.to_string());
+        return Ok(());
+    }
+    entry(account)?.set_password(secret).map_err(access_error)
+}
+
+pub fn delete(account: &str) -> Result<(), AppError> {
+    if let Some(store) = MEMORY_STORE.get() {
+        store.lock().unwrap().remove(account);
+        return Ok(());
+    }
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(access_error(e)),
+    }
+}
+
+fn entry(account: &str) -> Result<keyring::Entry, AppError> {
+    keyring::Entry::new(SERVICE, account).map_err(access_error)
+}
+
+fn access_error(error: keyring::Error) -> AppError {
+    AppError::Unexpected(format!("Keychain access failed (allow Scriberr Companion in Keychain Access): {}", error))
+}
@@ -1,27 +1,517 @@
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use ringbuf::{HeapProducer, HeapConsumer, HeapRb};
-use hound::WavWriter;
-use tauri::{AppHandle, Emitter};
+use crate::error::AppError;
+use crate::services::encoder::SharedEncoder;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Emitter, Runtime, Wry};
 
-pub struct AudioMixer {
+/// How long system audio may go without buffers before we warn.
+const SYSTEM_AUDIO_STALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest the mixer waits for samples before checking for a stop, pause or
+/// stall on its own.
+const DATA_WAIT_TIMEOUT: Duration = Duration::from_millis(20);
+
+/// Pole of the DC blocker; closer to 1.0 means a lower cutoff (~4 Hz at 48 kHz).
+const DC_BLOCKER_POLE: f32 = 0.9995;
+
+/// Per-recording mixer tuning, taken from settings when recording starts.
+#[derive(Debug, Clone, Copy)]
+pub struct MixerConfig {
+    /// Remove DC offset from each source before summing.
+    pub dc_blocker: bool,
+    /// Mixed samples are soft-limited to ±clip_ceiling.
+    pub clip_ceiling: f32,
+    /// Latency budget of each source's ring buffer.
+    pub buffer_seconds: f32,
+    /// Format of the samples flowing through the mixer (the WAV's format).
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Write silence while paused so the file's timeline matches wall-clock time.
+    pub preserve_pause_gaps: bool,
+    /// Write mic and system audio as separate channel groups (mic L/R, sys
+    /// L/R) instead of summing them, for remixing later.
+    pub dual_track: bool,
+}
+
+impl Default for MixerConfig {
+    fn default() -> Self {
+        Self {
+            dc_blocker: true,
+            clip_ceiling: 1.0,
+            buffer_seconds: 8.0,
+            sample_rate: 48000,
+            channels: 2,
+            preserve_pause_gaps: false,
+            dual_track: false,
+        }
+    }
+}
+
+impl MixerConfig {
+    /// Channels in the written file: `channels`, or twice that with `dual_track`.
+    pub fn output_channels(&self) -> u16 {
+        if self.dual_track { self.channels * 2 } else { self.channels }
+    }
+
+    /// Samples per ring buffer: `sample_rate * channels * buffer_seconds`.
+    pub fn ring_buffer_capacity(&self) -> usize {
+        let samples = self.sample_rate as f32 * self.channels as f32 * self.buffer_seconds;
+        (samples.ceil() as usize).max(self.channels as usize * 1024)
+    }
+}
+
+/// Fraction of the ceiling where the limiter starts bending the signal.
+const LIMITER_KNEE: f32 = 0.8;
+
+/// Soft-knee limiter: linear up to `LIMITER_KNEE * ceiling`, then a tanh curve
+/// that approaches but never reaches the ceiling, so loud peaks round off
+/// instead of squaring off into audible distortion.
+pub fn soft_limit(sample: f32, ceiling: f32) -> f32 {
+    let knee = ceiling * LIMITER_KNEE;
+    let magnitude = sample.abs();
+    if magnitude <= knee {
+        return sample;
+    }
+    let headroom = ceiling - knee;
+    let limited = knee + headroom * ((magnitude - knee) / headroom).tanh();
+    limited.copysign(sample)
+}
+
+/// Upper bound for a source's gain multiplier.
+pub const MAX_GAIN: f32 = 4.0;
+
+/// Per-source gain multipliers, shared with the mixer thread so changes apply
+/// mid-recording. Stored as `f32` bits.
+#[derive(Debug)]
+pub struct MixGains {
+    mic: AtomicU32,
+    sys: AtomicU32,
+}
+
+impl Default for MixGains {
+    fn default() -> Self {
+        Self {
+            mic: AtomicU32::new(1.0f32.to_bits()),
+            sys: AtomicU32::new(1.0f32.to_bits()),
+        }
+    }
+}
+
+impl MixGains {
+    pub fn mic(&self) -> f32 {
+        f32::from_bits(self.mic.load(Ordering::Relaxed))
+    }
+
+    pub fn sys(&self) -> f32 {
+        f32::from_bits(self.sys.load(Ordering::Relaxed))
+    }
+
+    pub fn set_mic(&self, gain: f32) {
+        self.mic.store(gain.clamp(0.0, MAX_GAIN).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn set_sys(&self, gain: f32) {
+        self.sys.store(gain.clamp(0.0, MAX_GAIN).to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// First-order high-pass (`y = x - x[n-1] + R * y[n-1]`) over an interleaved
+/// stream, keeping separate state per channel.
+pub struct DcBlocker {
+    prev_input: Vec<f32>,
+    prev_output: Vec<f32>,
+    channel: usize,
+}
+
+impl DcBlocker {
+    pub fn new(channels: usize) -> Self {
+        Self {
+            prev_input: vec![0.0; channels],
+            prev_output: vec![0.0; channels],
+            channel: 0,
+        }
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let ch = self.channel;
+        let out = sample - self.prev_input[ch] + DC_BLOCKER_POLE * self.prev_output[ch];
+        self.prev_input[ch] = sample;
+        self.prev_output[ch] = out;
+        self.channel = (ch + 1) % self.prev_input.len();
+        out
+    }
+}
+
+/// Mic automatic gain control, off unless enabled in settings.
+#[derive(Debug, Clone, Copy)]
+pub struct AgcConfig {
+    /// Level the AGC steers toward (0.1 ≈ -20 dBFS).
+    pub target_rms: f32,
+    /// Time constant for turning gain down when the input gets louder.
+    pub attack_ms: f32,
+    /// Time constant for turning gain up when the input gets quieter.
+    pub release_ms: f32,
+    pub max_gain: f32,
+}
+
+impl Default for AgcConfig {
+    fn default() -> Self {
+        Self {
+            target_rms: 0.1,
+            attack_ms: 50.0,
+            release_ms: 2000.0,
+            max_gain: 10.0,
+        }
+    }
+}
+
+/// Window of the level detector feeding the AGC.
+const AGC_DETECTOR_MS: f32 = 100.0;
+/// Below this level the input is treated as silence and the gain is held.
+const AGC_SILENCE_RMS: f32 = 1e-4;
+
+/// Slow-acting AGC applied in the mic callback, before samples reach the mixer.
+pub struct AutoGain {
+    config: AgcConfig,
+    gain: f32,
+    mean_square: f32,
+    detector_coef: f32,
+    attack_coef: f32,
+    release_coef: f32,
+}
+
+impl AutoGain {
+    /// `sample_rate * channels` is the rate samples are fed to `process`.
+    pub fn new(config: AgcConfig, sample_rate: u32, channels: u16) -> Self {
+        let rate = sample_rate as f32 * channels.max(1) as f32;
+        let coef = |ms: f32| 1.0 - (-1000.0 / (ms.max(1.0) * rate)).exp();
+        Self {
+            config,
+            gain: 1.0,
+            mean_square: 0.0,
+            detector_coef: coef(AGC_DETECTOR_MS),
+            attack_coef: coef(config.attack_ms),
+            release_coef: coef(config.release_ms),
+        }
+    }
+
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.mean_square += (sample * sample - self.mean_square) * self.detector_coef;
+        let rms = self.mean_square.sqrt();
+
+        if rms > AGC_SILENCE_RMS {
+            let desired = (self.config.target_rms / rms).min(self.config.max_gain);
+            let coef = if desired < self.gain { self.attack_coef } else { self.release_coef };
+            self.gain += (desired - self.gain) * coef;
+        }
+
+        (sample * self.gain).clamp(-1.0, 1.0)
+    }
+}
+
+/// Window the level meter aggregates over before emitting `audio-level`.
+const LEVEL_WINDOW_MS: u32 = 50;
+
+/// Payload of the `audio-level` event, one per `LEVEL_WINDOW_MS` of audio.
+/// A disabled source reports 0.0.
+#[derive(Debug, Clone, Copy, Default, Serialize, Type, PartialEq)]
+pub struct AudioLevel {
+    pub mic_peak: f32,
+    pub sys_peak: f32,
+    pub mic_rms: f32,
+    /// RMS of what is written to the file, for the visualizer.
+    pub mixed_rms: f32,
+}
+
+/// Accumulates per-source peak/RMS over fixed windows of samples.
+pub struct LevelMeter {
+    window: usize,
+    count: usize,
+    mic_peak: f32,
+    sys_peak: f32,
+    mic_squares: f32,
+    mixed_squares: f32,
+}
+
+impl LevelMeter {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        let window = (sample_rate as usize * channels.max(1) as usize * LEVEL_WINDOW_MS as usize / 1000).max(1);
+        Self { window, count: 0, mic_peak: 0.0, sys_peak: 0.0, mic_squares: 0.0, mixed_squares: 0.0 }
+    }
+
+    /// Feeds one sample of each source and the mix; returns the levels when a
+    /// window completes.
+    pub fn push(&mut self, mic: f32, sys: f32, mixed: f32) -> Option<AudioLevel> {
+        self.mic_peak = self.mic_peak.max(mic.abs());
+        self.sys_peak = self.sys_peak.max(sys.abs());
+        self.mic_squares += mic * mic;
+        self.mixed_squares += mixed * mixed;
+        self.count += 1;
+
+        if self.count < self.window {
+            return None;
+        }
+
+        let level = AudioLevel {
+            mic_peak: self.mic_peak,
+            sys_peak: self.sys_peak,
+            mic_rms: (self.mic_squares / self.count as f32).sqrt(),
+            mixed_rms: (self.mixed_squares / self.count as f32).sqrt(),
+        };
+        self.count = 0;
+        self.mic_peak = 0.0;
+        self.sys_peak = 0.0;
+        self.mic_squares = 0.0;
+        self.mixed_squares = 0.0;
+        Some(level)
+    }
+}
+
+/// Collects one interleaved frame of each source into a single frame laid
+/// out as all mic channels followed by all system channels.
+pub struct DualTrackFrame {
+    channels: usize,
+    filled: usize,
+    samples: Vec<f32>,
+}
+
+impl DualTrackFrame {
+    pub fn new(channels: usize) -> Self {
+        Self { channels, filled: 0, samples: vec![0.0; channels * 2] }
+    }
+
+    /// Adds the next channel of each source, each limited on its own since
+    /// they're no longer summed.
+    pub fn push(&mut self, mic: f32, sys: f32, ceiling: f32) {
+        if self.filled == self.channels {
+            self.filled = 0;
+        }
+        self.samples[self.filled] = soft_limit(mic, ceiling);
+        self.samples[self.channels + self.filled] = soft_limit(sys, ceiling);
+        self.filled += 1;
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.filled == self.channels
+    }
+
+    /// The completed frame; the next `push` starts a new one.
+    pub fn take(&mut self) -> &[f32] {
+        &self.samples
+    }
+}
+
+/// Window the silence detector measures RMS over.
+const SILENCE_WINDOW_MS: u32 = 100;
+
+/// Starts a new file after a long enough stretch of silence.
+#[derive(Debug, Clone, Copy)]
+pub struct SilenceSplitConfig {
+    /// Mixed RMS below this counts as silence.
+    pub threshold_rms: f32,
+    /// How long the silence must last before splitting.
+    pub min_silence_sec: f64,
+}
+
+/// Tracks how long the mix has stayed below the silence threshold.
+pub struct SilenceDetector {
+    threshold_rms: f32,
+    window: usize,
+    windows_needed: usize,
+    count: usize,
+    squares: f32,
+    silent_windows: usize,
+    /// Only set once sound has been heard, so a silent stretch splits once
+    /// and a recording that starts silent doesn't produce an empty file.
+    armed: bool,
+}
+
+impl SilenceDetector {
+    pub fn new(config: SilenceSplitConfig, sample_rate: u32, channels: u16) -> Self {
+        let window = (sample_rate as usize * channels.max(1) as usize * SILENCE_WINDOW_MS as usize / 1000).max(1);
+        let windows_needed = ((config.min_silence_sec * 1000.0) / SILENCE_WINDOW_MS as f64).ceil().max(1.0) as usize;
+        Self {
+            threshold_rms: config.threshold_rms,
+            window,
+            windows_needed,
+            count: 0,
+            squares: 0.0,
+            silent_windows: 0,
+            armed: false,
+        }
+    }
+
+    /// Feeds one mixed sample; true when the current silence has just
+    /// lasted long enough to split.
+    pub fn push(&mut self, sample: f32) -> bool {
+        self.squares += sample * sample;
+        self.count += 1;
+        if self.count < self.window {
+            return false;
+        }
+
+        let rms = (self.squares / self.count as f32).sqrt();
+        self.count = 0;
+        self.squares = 0.0;
+
+        if rms >= self.threshold_rms {
+            self.silent_windows = 0;
+            self.armed = true;
+            return false;
+        }
+
+        self.silent_windows += 1;
+        if self.armed && self.silent_windows >= self.windows_needed {
+            self.armed = false;
+            return true;
+        }
+        false
+    }
+}
+
+/// Called on the mixer thread when a silence split is due. The writer isn't
+/// locked at that point, so the handler may swap in a new encoder.
+pub type SplitHandler = Box<dyn FnMut() + Send>;
+
+/// Payload of the `system-audio-stalled` event.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SystemAudioStalled {
+    pub stalled_for_ms: u64,
+}
+
+/// Wakes the mixer as soon as a capture callback has pushed samples, instead
+/// of it polling the ring buffers.
+#[derive(Default)]
+pub struct DataReady {
+    ready: Mutex<bool>,
+    signal: Condvar,
+}
+
+impl DataReady {
+    pub fn notify(&self) {
+        *self.ready.lock().unwrap() = true;
+        self.signal.notify_one();
+    }
+
+    /// Returns once notified (including before the call) or after `timeout`.
+    pub fn wait(&self, timeout: Duration) {
+        let ready = self.ready.lock().unwrap();
+        let (mut ready, _) = self.signal.wait_timeout_while(ready, timeout, |ready| !*ready).unwrap();
+        *ready = false;
+    }
+}
+
+/// How often `buffer-overrun` may be sent while samples keep being dropped.
+const OVERRUN_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Samples the capture callbacks dropped because a ring buffer was full,
+/// i.e. the mixer (usually the disk behind it) fell behind.
+#[derive(Debug, Default)]
+pub struct Overruns {
+    mic: AtomicU64,
+    sys: AtomicU64,
+}
+
+impl Overruns {
+    pub fn add_mic(&self, dropped: u64) {
+        if dropped > 0 {
+            self.mic.fetch_add(dropped, Ordering::Relaxed);
+        }
+    }
+
+    pub fn add_sys(&self, dropped: u64) {
+        if dropped > 0 {
+            self.sys.fetch_add(dropped, Ordering::Relaxed);
+        }
+    }
+
+    pub fn totals(&self) -> BufferOverrun {
+        BufferOverrun {
+            mic_samples_dropped: self.mic.load(Ordering::Relaxed),
+            sys_samples_dropped: self.sys.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Payload of the `buffer-overrun` event: samples dropped so far in the
+/// current recording, per source.
+#[derive(Debug, Clone, Copy, Default, Serialize, Type, PartialEq)]
+pub struct BufferOverrun {
+    pub mic_samples_dropped: u64,
+    pub sys_samples_dropped: u64,
+}
+
+/// Failed writes in a row after which the recording is given up on. A full
+/// disk fails every write; a one-off hiccup doesn't get this far.
+const MAX_CONSECUTIVE_WRITE_ERRORS: u32 = 32;
+
+/// Watches the encoder's write results for a run of failures.
+#[derive(Default)]
+pub struct WriteHealth {
+    consecutive_errors: u32,
+    failure: Option<AppError>,
+}
+
+impl WriteHealth {
+    pub fn record(&mut self, result: Result<(), AppError>) {
+        match result {
+            Ok(()) => self.consecutive_errors = 0,
+            Err(e) => {
+                self.consecutive_errors += 1;
+                if self.consecutive_errors >= MAX_CONSECUTIVE_WRITE_ERRORS && self.failure.is_none() {
+                    self.failure = Some(AppError::Io(format!("Recording stopped, writing audio failed repeatedly: {}", e)));
+                }
+            }
+        }
+    }
+
+    /// The error to stop the recording with, once writes keep failing.
+    pub fn take_failure(&mut self) -> Option<AppError> {
+        self.failure.take()
+    }
+}
+
+/// Mixes the mic and system ring buffers into the encoder, paced by the mic:
+/// whenever mic samples arrive they're written, with system audio or silence
+/// alongside, so a stalled system stream can't hold the recording up. Without
+/// a mic, system audio is written as it comes.
+///
+/// Generic over the runtime so tests can drive it with tauri's mock runtime.
+pub struct AudioMixer<R: Runtime = Wry> {
     sys_consumer: HeapConsumer<f32>,
     mic_consumer: HeapConsumer<f32>,
-    writer: Arc<Mutex<Option<WavWriter<std::io::BufWriter<std::fs::File>>>>>,
+    writer: SharedEncoder,
     running: Arc<std::sync::atomic::AtomicBool>,
-    sys_enabled: bool,
-    mic_enabled: bool,
-    app_handle: AppHandle,
+    sys_enabled: Arc<std::sync::atomic::AtomicBool>,
+    mic_enabled: Arc<std::sync::atomic::AtomicBool>,
+    paused: Arc<std::sync::atomic::AtomicBool>,
+    config: MixerConfig,
+    gains: Arc<MixGains>,
+    mic_dc: DcBlocker,
+    sys_dc: DcBlocker,
+    silence_split: Option<(SilenceSplitConfig, SplitHandler)>,
+    data_ready: Arc<DataReady>,
+    overruns: Arc<Overruns>,
+    app_handle: AppHandle<R>,
 }
 
-impl AudioMixer {
+impl<R: Runtime> AudioMixer<R> {
     pub fn new(
-        writer: Arc<Mutex<Option<WavWriter<std::io::BufWriter<std::fs::File>>>>>,
-        sys_enabled: bool,
-        mic_enabled: bool,
-        app_handle: AppHandle
+        writer: SharedEncoder,
+        sys_enabled: Arc<std::sync::atomic::AtomicBool>,
+        mic_enabled: Arc<std::sync::atomic::AtomicBool>,
+        paused: Arc<std::sync::atomic::AtomicBool>,
+        config: MixerConfig,
+        gains: Arc<MixGains>,
+        app_handle: AppHandle<R>
     ) -> (Self, HeapProducer<f32>, HeapProducer<f32>, Arc<std::sync::atomic::AtomicBool>) {
-        let sys_rb = HeapRb::<f32>::new(192000); // 2 seconds buffer
-        let mic_rb = HeapRb::<f32>::new(192000);
+        let capacity = config.ring_buffer_capacity();
+        let sys_rb = HeapRb::<f32>::new(capacity);
+        let mic_rb = HeapRb::<f32>::new(capacity);
         
         let (sys_prod, sys_cons) = sys_rb.split();
         let (mic_prod, mic_cons) = mic_rb.split();
@@ -36,6 +526,14 @@ impl AudioMixer {
                 running: running.clone(),
                 sys_enabled,
                 mic_enabled,
+                paused,
+                config,
+                gains,
+                mic_dc: DcBlocker::new(config.channels as usize),
+                sys_dc: DcBlocker::new(config.channels as usize),
+                silence_split: None,
+                data_ready: Arc::new(DataReady::default()),
+                overruns: Arc::new(Overruns::default()),
                 app_handle,
             },
             sys_prod,
@@ -44,70 +542,458 @@ impl AudioMixer {
         )
     }
 
+    /// For the capture callbacks to signal new samples, and the recorder to
+    /// wake the mixer when stopping it.
+    pub fn data_ready(&self) -> Arc<DataReady> {
+        self.data_ready.clone()
+    }
+
+    /// For the capture callbacks to count samples they had to drop.
+    pub fn overruns(&self) -> Arc<Overruns> {
+        self.overruns.clone()
+    }
+
+    /// Calls `on_split` whenever the mix has been silent for long enough.
+    pub fn set_silence_split(&mut self, config: SilenceSplitConfig, on_split: SplitHandler) {
+        self.silence_split = Some((config, on_split));
+    }
+
     pub fn process(&mut self) {
-        let mut sample_count = 0;
-        let mut sum_squares = 0.0;
-        let emit_interval = 2048; // Approx 23Hz at 48kHz, good balance for visualizer
+        let mut meter = LevelMeter::new(self.config.sample_rate, self.config.channels);
+        let mut silence = self.silence_split.as_ref()
+            .map(|(config, _)| SilenceDetector::new(*config, self.config.sample_rate, self.config.channels));
+        let mut split_due = false;
+        let mut dual_frame = self.config.dual_track.then(|| DualTrackFrame::new(self.config.channels as usize));
+        let ceiling = self.config.clip_ceiling;
+        let dc_blocker = self.config.dc_blocker;
+
+        // SCK stops delivering buffers (without erroring) when e.g. the screen
+        // recording permission is revoked mid-session
+        let mut last_sys_buffer = Instant::now();
+        let mut stall_reported = false;
+
+        // Start of the current pause and the silent samples written for it
+        let mut pause_started: Option<Instant> = None;
+        let mut silence_written: u64 = 0;
+
+        let mut health = WriteHealth::default();
+
+        let mut overruns_reported = BufferOverrun::default();
+        let mut last_overrun_check = Instant::now();
 
         while self.running.load(std::sync::atomic::Ordering::Relaxed) {
-             let mut process_mixed_sample = |sample: f32| {
-                 // RMS Calculation
-                 sum_squares += sample * sample;
-                 sample_count += 1;
+            // Stop writing and let the recorder finalize what made it to disk
+            if let Some(error) = health.take_failure() {
+                eprintln!("{}", error);
+                self.running.store(false, std::sync::atomic::Ordering::Relaxed);
+                let _ = self.app_handle.emit("recording-error", error);
+                break;
+            }
+
+            if last_overrun_check.elapsed() >= OVERRUN_REPORT_INTERVAL {
+                last_overrun_check = Instant::now();
+                let overruns = self.overruns.totals();
+                if overruns != overruns_reported {
+                    overruns_reported = overruns;
+                    eprintln!("Ring buffer overrun, audio dropped: {:?}", overruns);
+                    let _ = self.app_handle.emit("buffer-overrun", overruns);
+                }
+            }
 
-                 if sample_count >= emit_interval {
-                     let rms = (sum_squares / sample_count as f32).sqrt();
+            // Can be switched off mid-recording when system capture fails to start
+            let sys_enabled = self.sys_enabled.load(std::sync::atomic::Ordering::Relaxed);
+            if sys_enabled {
+                if !self.sys_consumer.is_empty() || self.paused.load(std::sync::atomic::Ordering::Relaxed) {
+                    last_sys_buffer = Instant::now();
+                    stall_reported = false;
+                } else if !stall_reported && last_sys_buffer.elapsed() >= SYSTEM_AUDIO_STALL_TIMEOUT {
+                    stall_reported = true;
+                    let payload = SystemAudioStalled { stalled_for_ms: last_sys_buffer.elapsed().as_millis() as u64 };
+                    let _ = self.app_handle.emit("system-audio-stalled", payload);
+                }
+            }
+
+            // The capture callbacks push nothing while paused; pace zeros off
+            // the clock instead so the pause shows up as a gap in the file
+            if self.config.preserve_pause_gaps && self.paused.load(std::sync::atomic::Ordering::Relaxed) {
+                let started = *pause_started.get_or_insert_with(Instant::now);
+                let frames_due = (started.elapsed().as_secs_f64() * self.config.sample_rate as f64) as u64;
+                let samples_due = frames_due * self.config.output_channels() as u64;
+                if let Ok(mut guard) = self.writer.lock() {
+                    if let Some(writer) = &mut *guard {
+                        while silence_written < samples_due {
+                            health.record(writer.write_sample(0.0));
+                            silence_written += 1;
+                        }
+                    }
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                continue;
+            }
+            pause_started = None;
+            silence_written = 0;
+
+             let mut process_mixed_sample = |mic: f32, sys: f32, sample: f32| {
+                 if let Some(level) = meter.push(mic, sys, sample) {
                      // Emit event - ignore errors if app is closing
-                     let _ = self.app_handle.emit("audio-level", rms);
-                     
-                     sum_squares = 0.0;
-                     sample_count = 0;
+                     let _ = self.app_handle.emit("audio-level", level);
+                 }
+                 if let Some(detector) = silence.as_mut() {
+                     split_due |= detector.push(sample);
+                 }
+
+                 if let Some(frame) = dual_frame.as_mut() {
+                     frame.push(mic, sys, ceiling);
+                     if !frame.is_complete() {
+                         return;
+                     }
                  }
                  
                  // Write to file
                  if let Ok(mut guard) = self.writer.lock() {
                      if let Some(writer) = &mut *guard {
-                         let _ = writer.write_sample(sample);
+                         match dual_frame.as_mut() {
+                             Some(frame) => {
+                                 for &s in frame.take() {
+                                     health.record(writer.write_sample(s));
+                                 }
+                             }
+                             None => {
+                                 health.record(writer.write_sample(sample));
+                             }
+                         }
                      }
                  }
              };
 
-            if self.mic_enabled {
+            // Cleared when the mic can't be opened, so system audio keeps flowing
+            if self.mic_enabled.load(std::sync::atomic::Ordering::Relaxed) {
                 // Mic Master Mode
                 if self.mic_consumer.is_empty() {
-                     std::thread::sleep(std::time::Duration::from_millis(1));
+                     self.data_ready.wait(DATA_WAIT_TIMEOUT);
                      continue;
                 }
                 
+                let (mic_gain, sys_gain) = (self.gains.mic(), self.gains.sys());
                 while !self.mic_consumer.is_empty() {
-                    let s_mic = self.mic_consumer.pop().unwrap_or(0.0);
-                    let s_sys = if self.sys_enabled {
+                    let mut s_mic = self.mic_consumer.pop().unwrap_or(0.0);
+                    let mut s_sys = if sys_enabled {
                         self.sys_consumer.pop().unwrap_or(0.0) 
                     } else {
                         0.0
                     };
+                    if dc_blocker {
+                        s_mic = self.mic_dc.process(s_mic);
+                        s_sys = self.sys_dc.process(s_sys);
+                    }
+                    s_mic *= mic_gain;
+                    s_sys *= sys_gain;
                     
-                    let mixed = (s_mic + s_sys).max(-1.0).min(1.0);
-                    process_mixed_sample(mixed);
+                    let mixed = soft_limit(s_mic + s_sys, ceiling);
+                    process_mixed_sample(s_mic, s_sys, mixed);
                 }
                 
             } else {
                 // System Master Mode
-                if self.sys_enabled {
+                if sys_enabled {
                     if self.sys_consumer.is_empty() {
-                        std::thread::sleep(std::time::Duration::from_millis(1));
+                        self.data_ready.wait(DATA_WAIT_TIMEOUT);
                         continue;
                     }
                     
+                    let sys_gain = self.gains.sys();
                     while !self.sys_consumer.is_empty() {
-                         let s_sys = self.sys_consumer.pop().unwrap_or(0.0);
-                         let mixed = s_sys.max(-1.0).min(1.0);
-                         process_mixed_sample(mixed);
+                         let mut s_sys = self.sys_consumer.pop().unwrap_or(0.0);
+                         if dc_blocker {
+                             s_sys = self.sys_dc.process(s_sys);
+                         }
+                         s_sys *= sys_gain;
+                         let mixed = soft_limit(s_sys, ceiling);
+                         process_mixed_sample(0.0, s_sys, mixed);
                     }
                 } else {
                     std::thread::sleep(std::time::Duration::from_millis(100));
                 }
             }
+
+            if std::mem::take(&mut split_due) {
+                if let Some((_, on_split)) = self.silence_split.as_mut() {
+                    on_split();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc;
+    use crate::services::encoder::AudioEncoder;
+    use tauri::test::{mock_app, MockRuntime};
+
+    /// Encoder that keeps what it's given, for checking the mixer's output.
+    struct CollectingEncoder(Arc<Mutex<Vec<f32>>>);
+
+    impl AudioEncoder for CollectingEncoder {
+        fn write_sample(&mut self, sample: f32) -> Result<(), AppError> {
+            self.0.lock().unwrap().push(sample);
+            Ok(())
         }
+
+        fn finalize(self: Box<Self>) -> Result<(), AppError> {
+            Ok(())
+        }
+    }
+
+    /// Runs a mixer with the given sources enabled, feeds it through `feed`,
+    /// waits for `expected` samples, then stops it. Fails rather than hangs
+    /// if the mixer doesn't keep up or doesn't stop.
+    fn run_mixer(mic: bool, sys: bool, expected: usize, feed: impl FnOnce(&mut HeapProducer<f32>, &mut HeapProducer<f32>)) -> Vec<f32> {
+        let app = mock_app();
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let writer: SharedEncoder = Arc::new(Mutex::new(Some(Box::new(CollectingEncoder(written.clone())) as Box<dyn AudioEncoder>)));
+        let config = MixerConfig { dc_blocker: false, ..MixerConfig::default() };
+        let (mut mixer, mut sys_prod, mut mic_prod, running) = AudioMixer::<MockRuntime>::new(
+            writer,
+            Arc::new(AtomicBool::new(sys)),
+            Arc::new(AtomicBool::new(mic)),
+            Arc::new(AtomicBool::new(false)),
+            config,
+            Arc::new(MixGains::default()),
+            app.handle().clone(),
+        );
+
+        let data_ready = mixer.data_ready();
+        let (done_tx, done_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            mixer.process();
+            let _ = done_tx.send(());
+        });
+        feed(&mut mic_prod, &mut sys_prod);
+        data_ready.notify();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while written.lock().unwrap().len() < expected && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        running.store(false, Ordering::Relaxed);
+        done_rx.recv_timeout(Duration::from_secs(5)).expect("mixer didn't stop");
+
+        let samples = written.lock().unwrap();
+        samples.clone()
+    }
+
+    #[test]
+    fn starved_system_audio_doesnt_hold_up_the_mic() {
+        let mic = vec![0.25f32; 4_800];
+        let samples = run_mixer(true, true, mic.len(), |mic_prod, _| {
+            assert_eq!(mic_prod.push_slice(&mic), mic.len());
+        });
+
+        // One output sample per mic sample, with silence standing in for system audio
+        assert_eq!(samples.len(), mic.len());
+        assert!(samples.iter().all(|&s| s == 0.25));
+    }
+
+    #[test]
+    fn system_audio_short_of_the_mic_is_padded_with_silence() {
+        let samples = run_mixer(true, true, 4_800, |mic_prod, sys_prod| {
+            sys_prod.push_slice(&[0.5; 1_000]);
+            mic_prod.push_slice(&[0.25; 4_800]);
+        });
+
+        assert_eq!(samples.len(), 4_800);
+        assert_eq!(samples.iter().filter(|&&s| s == 0.75).count(), 1_000);
+    }
+
+    #[test]
+    fn without_a_mic_system_audio_is_written_as_it_comes() {
+        let samples = run_mixer(false, true, 2_000, |_, sys_prod| {
+            sys_prod.push_slice(&[0.5; 2_000]);
+        });
+        assert_eq!(samples.len(), 2_000);
+    }
+
+    #[test]
+    fn ring_buffers_hold_the_configured_seconds() {
+        let config = MixerConfig::default();
+        assert_eq!(config.ring_buffer_capacity(), 48_000 * 2 * 8);
+        let tiny = MixerConfig { buffer_seconds: 0.001, ..config };
+        assert_eq!(tiny.ring_buffer_capacity(), 2 * 1024);
+    }
+
+    #[test]
+    fn overruns_add_up_per_source() {
+        let overruns = Overruns::default();
+        overruns.add_mic(10);
+        overruns.add_mic(0);
+        overruns.add_sys(3);
+        overruns.add_mic(5);
+        assert_eq!(overruns.totals(), BufferOverrun { mic_samples_dropped: 15, sys_samples_dropped: 3 });
+    }
+
+    #[test]
+    fn data_ready_wakes_the_waiter_and_keeps_early_notifications() {
+        let ready = Arc::new(DataReady::default());
+
+        ready.notify();
+        let started = Instant::now();
+        ready.wait(Duration::from_secs(5));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Consumed by that wait, so this one times out
+        let started = Instant::now();
+        ready.wait(Duration::from_millis(50));
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        let notifier = ready.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            notifier.notify();
+        });
+        let started = Instant::now();
+        ready.wait(Duration::from_secs(5));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn a_mixer_with_no_input_still_stops() {
+        assert!(run_mixer(true, true, 0, |_, _| {}).is_empty());
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn dc_blocker_removes_offset() {
+        let channels = 2;
+        let mut blocker = DcBlocker::new(channels);
+        let rate = 48_000.0;
+        let mut tail = Vec::new();
+
+        // Two seconds of a 440 Hz tone riding on a 0.3 offset, interleaved stereo
+        for n in 0..(2 * 48_000) {
+            let x = 0.3 + 0.2 * (2.0 * std::f32::consts::PI * 440.0 * n as f32 / rate).sin();
+            for _ in 0..channels {
+                let y = blocker.process(x);
+                if n >= 48_000 {
+                    tail.push(y);
+                }
+            }
+        }
+
+        let mean = tail.iter().sum::<f32>() / tail.len() as f32;
+        assert!(mean.abs() < 0.005, "mean after settling was {}", mean);
+        let peak = tail.iter().fold(0.0f32, |m, y| m.max(y.abs()));
+        assert!(peak > 0.15 && peak < 0.25, "tone should pass through, peak was {}", peak);
+    }
+
+    #[test]
+    fn agc_converges_toward_target() {
+        let config = AgcConfig::default();
+        let mut agc = AutoGain::new(config, 48_000, 1);
+        let rate = 48_000;
+        let mut out = Vec::new();
+
+        // Quiet speaker for 5s, then a loud one for 5s (RMS 0.02 -> 0.5)
+        for n in 0..(10 * rate) {
+            let amp = if n < 5 * rate { 0.02 } else { 0.5 };
+            let x = amp * std::f32::consts::SQRT_2 * (2.0 * std::f32::consts::PI * 220.0 * n as f32 / rate as f32).sin();
+            out.push(agc.process(x));
+        }
+
+        let quiet = rms(&out[4 * rate..5 * rate]);
+        let loud = rms(&out[9 * rate..10 * rate]);
+        assert!((quiet - config.target_rms).abs() < 0.02, "quiet phase settled at {}", quiet);
+        assert!((loud - config.target_rms).abs() < 0.02, "loud phase settled at {}", loud);
+    }
+
+    #[test]
+    fn soft_limit_is_transparent_below_the_knee_and_bounded_above() {
+        assert_eq!(soft_limit(0.5, 1.0), 0.5);
+        assert_eq!(soft_limit(-0.8, 1.0), -0.8);
+
+        let mut previous = 0.0;
+        for i in 0..400 {
+            let x = i as f32 * 0.01;
+            let y = soft_limit(x, 1.0);
+            assert!(y <= 1.0, "{} limited to {}", x, y);
+            assert!(y >= previous, "limiter must be monotonic");
+            assert_eq!(soft_limit(-x, 1.0), -y);
+            previous = y;
+        }
+    }
+
+    #[test]
+    fn level_meter_reports_each_window() {
+        // 50 ms of 48 kHz stereo
+        let mut meter = LevelMeter::new(48_000, 2);
+        let mut levels = Vec::new();
+        for n in 0..(2 * 4800) {
+            let mic = if n % 2 == 0 { 0.5 } else { -0.5 };
+            if let Some(level) = meter.push(mic, 0.0, mic) {
+                levels.push(level);
+            }
+        }
+
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].mic_peak, 0.5);
+        assert_eq!(levels[0].sys_peak, 0.0);
+        assert!((levels[0].mic_rms - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn silence_detector_fires_once_per_silence_after_sound() {
+        let config = SilenceSplitConfig { threshold_rms: 0.01, min_silence_sec: 1.0 };
+        let mut detector = SilenceDetector::new(config, 1_000, 1);
+        let mut feed = |amplitude: f32, samples: usize| {
+            (0..samples).filter(|_| detector.push(amplitude)).count()
+        };
+
+        // Leading silence doesn't split
+        assert_eq!(feed(0.0, 3_000), 0);
+        assert_eq!(feed(0.5, 500), 0);
+        // Just short of the minimum, then long enough, then still silent
+        assert_eq!(feed(0.0, 900), 0);
+        assert_eq!(feed(0.0, 100), 1);
+        assert_eq!(feed(0.0, 5_000), 0);
+        // Sound re-arms it
+        assert_eq!(feed(0.5, 500), 0);
+        assert_eq!(feed(0.0, 1_000), 1);
+    }
+
+    #[test]
+    fn write_health_fails_only_after_a_run_of_errors() {
+        let mut health = WriteHealth::default();
+        let failed = || Err(AppError::Io("No space left on device".to_string()));
+
+        for _ in 0..MAX_CONSECUTIVE_WRITE_ERRORS - 1 {
+            health.record(failed());
+        }
+        health.record(Ok(()));
+        for _ in 0..MAX_CONSECUTIVE_WRITE_ERRORS - 1 {
+            health.record(failed());
+        }
+        assert!(health.take_failure().is_none());
+
+        health.record(failed());
+        assert!(matches!(health.take_failure(), Some(AppError::Io(msg)) if msg.contains("No space left")));
+        assert!(health.take_failure().is_none());
+    }
+
+    #[test]
+    fn dual_track_frames_put_mic_channels_before_system_channels() {
+        let mut frame = DualTrackFrame::new(2);
+        frame.push(0.1, 0.3, 1.0);
+        assert!(!frame.is_complete());
+        frame.push(0.2, 0.4, 1.0);
+        assert!(frame.is_complete());
+        assert_eq!(frame.take(), &[0.1, 0.2, 0.3, 0.4]);
+
+        frame.push(0.5, 0.7, 1.0);
+        assert!(!frame.is_complete());
     }
 }
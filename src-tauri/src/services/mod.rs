@@ -5,3 +5,20 @@ pub mod discovery;
 pub mod db;
 pub mod sync;
 pub mod proxy;
+pub mod http;
+pub mod scheduler;
+pub mod integrity;
+pub mod events;
+pub mod transcript;
+pub mod compat;
+pub mod quota;
+pub mod paths;
+pub mod duplicates;
+pub mod heartbeat;
+pub mod encoder;
+pub mod resample;
+pub mod devices;
+pub mod audio_cache;
+pub mod keychain;
+pub mod transcribe;
+pub mod export;
\ No newline at end of file
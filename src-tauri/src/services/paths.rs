@@ -0,0 +1,126 @@
+use std::path::{Path, PathBuf};
+
+/// Longest file stem we produce, in bytes; leaves room for an extension,
+/// a ` (n)` suffix and `.part` under the common 255-byte name limit.
+const MAX_STEM_BYTES: usize = 200;
+
+/// Device names Windows refuses as file stems, whatever the extension.
+const WINDOWS_RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Fallback stem when nothing usable is left of a title.
+const DEFAULT_STEM: &str = "recording";
+
+/// Turns a title into something usable as a file stem on macOS, Linux and
+/// Windows:
+/// - path separators, reserved and control characters (including NUL) become `_`
+/// - whitespace is collapsed, leading dots and trailing dots/spaces are dropped
+///   so the file is neither hidden nor mangled by Windows
+/// - Windows device names (`CON`, `com1.notes`, ...) get a `_` after the name
+/// - the result is truncated to `MAX_STEM_BYTES` on a char boundary
+pub fn sanitize_filename(title: &str) -> String {
+    let replaced: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    let collapsed = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+    let trimmed = collapsed.trim_start_matches(['.', ' ']);
+
+    let mut stem = String::new();
+    for c in trimmed.chars() {
+        if stem.len() + c.len_utf8() > MAX_STEM_BYTES {
+            break;
+        }
+        stem.push(c);
+    }
+    let stem = stem.trim_end_matches(['.', ' ']);
+
+    if stem.is_empty() {
+        return DEFAULT_STEM.to_string();
+    }
+
+    // `CON.txt` is just as reserved as `CON`, so compare the part before the first dot
+    let base = stem.split('.').next().unwrap_or(stem);
+    if WINDOWS_RESERVED.iter().any(|r| r.eq_ignore_ascii_case(base)) {
+        return format!("{}_{}", base, &stem[base.len()..]);
+    }
+
+    stem.to_string()
+}
+
+/// `folder/{stem}.{extension}`, or the first free `folder/{stem} (n).{extension}`
+/// when that already exists, so two recordings with the same title never
+/// overwrite each other.
+pub fn unique_path(folder: &Path, stem: &str, extension: &str) -> PathBuf {
+    let candidate = folder.join(format!("{}.{}", stem, extension));
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    (2..)
+        .map(|n| folder.join(format!("{} ({}).{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .expect("unbounded counter always finds a free name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_reserved_characters() {
+        assert_eq!(sanitize_filename("a/b\\c:d*e?f\"g<h>i|j"), "a_b_c_d_e_f_g_h_i_j");
+        assert_eq!(sanitize_filename("null\0byte\ttab"), "null_byte_tab");
+    }
+
+    #[test]
+    fn strips_leading_dots_and_trailing_dots_and_spaces() {
+        assert_eq!(sanitize_filename("..hidden"), "hidden");
+        assert_eq!(sanitize_filename("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(sanitize_filename("  Standup notes.  "), "Standup notes");
+    }
+
+    #[test]
+    fn falls_back_when_nothing_is_left() {
+        assert_eq!(sanitize_filename(""), DEFAULT_STEM);
+        assert_eq!(sanitize_filename(" . . "), DEFAULT_STEM);
+        assert_eq!(sanitize_filename("..."), DEFAULT_STEM);
+    }
+
+    #[test]
+    fn suffixes_windows_reserved_names() {
+        assert_eq!(sanitize_filename("CON"), "CON_");
+        assert_eq!(sanitize_filename("prn"), "prn_");
+        assert_eq!(sanitize_filename("com1.backup"), "com1_.backup");
+        assert_eq!(sanitize_filename("Conference"), "Conference");
+    }
+
+    #[test]
+    fn truncates_long_titles_on_char_boundaries() {
+        let long = "é".repeat(300);
+        let stem = sanitize_filename(&long);
+        assert!(stem.len() <= MAX_STEM_BYTES);
+        assert!(stem.chars().all(|c| c == 'é'));
+    }
+
+    #[test]
+    fn unique_path_skips_existing_files() {
+        let dir = std::env::temp_dir().join(format!("scriberr-paths-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(unique_path(&dir, "Meeting", "wav"), dir.join("Meeting.wav"));
+        std::fs::write(dir.join("Meeting.wav"), b"").unwrap();
+        std::fs::write(dir.join("Meeting (2).wav"), b"").unwrap();
+        assert_eq!(unique_path(&dir, "Meeting", "wav"), dir.join("Meeting (3).wav"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,33 +1,45 @@
 use axum::{
     extract::{Path, State},
-    http::{HeaderMap, StatusCode},
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::Response,
     routing::get,
     Router,
     body::Body,
 };
+use std::io::SeekFrom;
 use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tokio::sync::RwLock;
 use tokio::net::TcpListener;
 use tokio::sync::oneshot;
+use tokio_util::io::ReaderStream;
+use crate::services::audio_cache::{self, AudioCache};
+use crate::services::integrity;
+use crate::services::http;
 use crate::services::storage::Settings;
 
 #[derive(Clone)]
 pub struct ProxyState {
     pub settings: Arc<RwLock<Settings>>,
     pub client: reqwest::Client,
+    pub cache: Arc<AudioCache>,
 }
 
 pub struct ProxyService;
 
 impl ProxyService {
-    pub async fn start(settings: Arc<RwLock<Settings>>, shutdown_rx: oneshot::Receiver<()>) -> Result<u16, Box<dyn std::error::Error>> {
-        let client = reqwest::Client::builder()
-            .build()?;
+    /// URL the proxy serves a remote job's audio at.
+    pub fn stream_url(port: u16, job_id: &str) -> String {
+        format!("http://127.0.0.1:{}/stream/{}", port, job_id)
+    }
+
+    pub async fn start(settings: Arc<RwLock<Settings>>, cache: Arc<AudioCache>, shutdown_rx: oneshot::Receiver<()>) -> Result<u16, Box<dyn std::error::Error>> {
+        let client = http::build_streaming_client()?;
 
         let state = ProxyState {
             settings,
             client,
+            cache,
         };
 
         // Define the app
@@ -63,15 +75,30 @@ async fn proxy_handler(
     println!("[PROXY] Received request for job_id: {}", job_id);
 
     // 1. Get Settings
-    let (base_url, api_key) = {
+    let (base_url, api_key, cache_max_bytes) = {
         let s = state.settings.read().await;
         if s.scriberr_url.is_empty() {
              eprintln!("[PROXY] Error: Scriberr URL is empty in settings");
              return Err(StatusCode::SERVICE_UNAVAILABLE); // Configuration missing
         }
-        (s.scriberr_url.clone(), s.api_key.clone())
+        (s.scriberr_url.clone(), s.api_key.clone(), s.audio_cache_max_bytes)
     };
 
+    // Seeking back through something already played is served locally
+    if cache_max_bytes > 0 {
+        if let Some((path, len)) = state.cache.get(&base_url, &job_id) {
+            println!("[PROXY] Serving {} from cache", job_id);
+            return serve_cached(&path, len, headers.get("range")).await;
+        }
+        let range = headers.get("range").and_then(|r| r.to_str().ok());
+        if let (Some(range), Some((path, coverage))) = (range, state.cache.get_partial(&base_url, &job_id)) {
+            if audio_cache::parse_range(range, coverage.total).is_some_and(|(start, end)| coverage.contains(start, end)) {
+                println!("[PROXY] Serving {} {} from cache", job_id, range);
+                return serve_cached(&path, coverage.total, headers.get("range")).await;
+            }
+        }
+    }
+
     // 2. Construct Upstream URL
     let url = format!("{}/api/v1/transcription/{}/audio", base_url.trim_end_matches('/'), job_id);
     println!("[PROXY] Connecting to Upstream: {}", url);
@@ -131,10 +158,131 @@ async fn proxy_handler(
     // CORS for Web Audio API
     response_builder = response_builder.header("access-control-allow-origin", "*");
     
-    // 7. Stream Body
-    let body = Body::from_stream(upstream_resp.bytes_stream());
+    // 7. Stream Body, caching the bytes it covers for next time
+    let cache_at = if cache_max_bytes > 0 { cacheable_range(status, upstream_resp.headers()) } else { None };
+    let body = match cache_at {
+        Some((offset, total)) => tee_to_cache(&state.cache, upstream_resp, base_url, job_id, offset, total, cache_max_bytes),
+        None => Body::from_stream(upstream_resp.bytes_stream()),
+    };
 
     response_builder
         .body(body)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
 }
+
+/// Bytes written between coverage updates while teeing a response.
+const RECORD_INTERVAL_BYTES: u64 = 1024 * 1024;
+
+/// Where an upstream response's body sits in the file: `(offset, total)`.
+/// `None` when the total size isn't known, since then it can't be cached.
+fn cacheable_range(status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap) -> Option<(u64, u64)> {
+    match status {
+        reqwest::StatusCode::OK => {
+            let len = headers.get("content-length")?.to_str().ok()?.parse().ok()?;
+            (len > 0).then_some((0, len))
+        }
+        reqwest::StatusCode::PARTIAL_CONTENT => {
+            let (start, _, total) = audio_cache::parse_content_range(headers.get("content-range")?.to_str().ok()?)?;
+            Some((start, total))
+        }
+        _ => None,
+    }
+}
+
+/// Streams `upstream` to the client while writing its bytes into the job's
+/// cache entry at `offset`. What arrived is recorded even when the client
+/// goes away early (say, to seek elsewhere).
+fn tee_to_cache(cache: &Arc<AudioCache>, upstream: reqwest::Response, base_url: String, job_id: String, offset: u64, total: u64, max_bytes: u64) -> Body {
+    let mut file = match cache.open_partial(&base_url, &job_id, total) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("[PROXY] Audio cache unavailable: {:?}", e);
+            return Body::from_stream(upstream.bytes_stream());
+        }
+    };
+
+    let (mut client, body) = tokio::io::duplex(64 * 1024);
+    let cache = cache.clone();
+    tokio::spawn(async move {
+        let mut upstream = upstream;
+        let (mut written, mut recorded) = (offset, offset);
+        let mut caching = true;
+
+        loop {
+            let chunk = match upstream.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) => {
+                    println!("[PROXY] Upstream body for {} ended early: {}", job_id, e);
+                    break;
+                }
+            };
+
+            if caching {
+                match AudioCache::write_partial(&mut file, written, &chunk) {
+                    Ok(()) => written += chunk.len() as u64,
+                    Err(e) => {
+                        println!("[PROXY] Not caching {}: {:?}", job_id, e);
+                        caching = false;
+                    }
+                }
+                if caching && written - recorded >= RECORD_INTERVAL_BYTES {
+                    if let Err(e) = cache.record_range(&base_url, &job_id, recorded, written, max_bytes) {
+                        println!("[PROXY] Not caching {}: {:?}", job_id, e);
+                        caching = false;
+                    }
+                    recorded = written;
+                }
+            }
+
+            if client.write_all(&chunk).await.is_err() {
+                break;
+            }
+        }
+
+        if caching && written > recorded {
+            if let Err(e) = cache.record_range(&base_url, &job_id, recorded, written, max_bytes) {
+                println!("[PROXY] Not caching {}: {:?}", job_id, e);
+            }
+        }
+    });
+
+    Body::from_stream(ReaderStream::new(body))
+}
+
+/// Serves a cached file of `len` bytes, honouring a single `Range`. A partial
+/// entry is only served for ranges it has.
+async fn serve_cached(path: &std::path::Path, len: u64, range: Option<&HeaderValue>) -> Result<Response, StatusCode> {
+    let content_type = integrity::read_head(path, 64)
+        .map(|head| integrity::audio_mime(integrity::audio_extension(None, &head)))
+        .unwrap_or("application/octet-stream");
+    let builder = Response::builder()
+        .header("content-type", content_type)
+        .header("accept-ranges", "bytes")
+        .header("access-control-allow-origin", "*");
+
+    let (status, start, count) = match range.and_then(|r| r.to_str().ok()) {
+        None => (StatusCode::OK, 0, len),
+        Some(range) => match audio_cache::parse_range(range, len) {
+            Some((start, end)) => (StatusCode::PARTIAL_CONTENT, start, end - start + 1),
+            None => {
+                return builder
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header("content-range", format!("bytes */{}", len))
+                    .body(Body::empty())
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        },
+    };
+
+    let mut file = tokio::fs::File::open(path).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    file.seek(SeekFrom::Start(start)).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut builder = builder.status(status).header("content-length", count);
+    if status == StatusCode::PARTIAL_CONTENT {
+        builder = builder.header("content-range", format!("bytes {}-{}/{}", start, start + count - 1, len));
+    }
+    builder
+        .body(Body::from_stream(ReaderStream::new(file.take(count))))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
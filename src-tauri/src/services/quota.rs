@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, Emitter, Runtime};
+use crate::error::AppError;
+use crate::services::db::{CachedRecording, DatabaseService, SyncStatus};
+use crate::services::events::LibraryEvent;
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct StorageUsage {
+    pub used_bytes: u64,
+    pub file_count: usize,
+    pub quota_bytes: Option<u64>,
+}
+
+/// A local file removed to get back under quota.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct PrunedFile {
+    pub local_id: String,
+    pub title: String,
+    pub path: String,
+    pub bytes: u64,
+}
+
+pub struct QuotaService;
+
+impl QuotaService {
+    /// Sums the size of every local audio file the library references.
+    pub async fn usage(db: &DatabaseService, quota_bytes: Option<u64>) -> Result<StorageUsage, AppError> {
+        let recordings = db.get_all_recordings().await?;
+        let mut used_bytes = 0;
+        let mut file_count = 0;
+
+        for recording in &recordings {
+            for (_, bytes) in local_files(recording) {
+                used_bytes += bytes;
+                file_count += 1;
+            }
+        }
+
+        Ok(StorageUsage { used_bytes, file_count, quota_bytes })
+    }
+
+    /// Deletes local copies of the oldest synced recordings until usage is
+    /// under `quota_bytes`. Drafts and `keep_offline` recordings are never
+    /// touched; the server still has everything that gets pruned.
+    ///
+    /// Emits `storage-pruned` with the removed files when anything was deleted.
+    pub async fn enforce<R: Runtime>(db: &DatabaseService, app: &AppHandle<R>, quota_bytes: u64) -> Result<Vec<PrunedFile>, AppError> {
+        let mut used = Self::usage(db, Some(quota_bytes)).await?.used_bytes;
+        if used <= quota_bytes {
+            return Ok(Vec::new());
+        }
+
+        let completed = SyncStatus::CompletedSynced.to_string();
+        let mut candidates: Vec<CachedRecording> = db.get_all_recordings().await?
+            .into_iter()
+            .filter(|r| r.sync_status == completed && !r.keep_offline && r.remote_job_id.is_some())
+            .collect();
+        candidates.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+        let mut pruned = Vec::new();
+        for recording in candidates {
+            if used <= quota_bytes {
+                break;
+            }
+
+            for (path, bytes) in local_files(&recording) {
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    eprintln!("Failed to prune {:?}: {:?}", path, e);
+                    continue;
+                }
+                used = used.saturating_sub(bytes);
+                pruned.push(PrunedFile {
+                    local_id: recording.local_id.clone(),
+                    title: recording.title.clone(),
+                    path: path.to_string_lossy().to_string(),
+                    bytes,
+                });
+            }
+
+            if recording.local_file_path.is_some() {
+                db.clear_local_file_path(&recording.local_id).await?;
+            }
+            if recording.local_audio_path.is_some() {
+                db.set_local_audio_path(&recording.local_id, None).await?;
+            }
+            if let Ok(updated) = db.get_recording(&recording.local_id).await {
+                LibraryEvent::Updated(updated).emit(app);
+            }
+        }
+
+        if !pruned.is_empty() {
+            let _ = app.emit("storage-pruned", &pruned);
+        }
+
+        Ok(pruned)
+    }
+}
+
+/// Existing local files of a recording with their sizes.
+fn local_files(recording: &CachedRecording) -> Vec<(PathBuf, u64)> {
+    let mut files: Vec<(PathBuf, u64)> = Vec::new();
+    for path in [&recording.local_file_path, &recording.local_audio_path].into_iter().flatten() {
+        let path = PathBuf::from(path);
+        if files.iter().any(|(p, _)| p == &path) {
+            continue;
+        }
+        if let Ok(meta) = std::fs::metadata(&path) {
+            files.push((path, meta.len()));
+        }
+    }
+    files
+}
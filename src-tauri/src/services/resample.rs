@@ -0,0 +1,105 @@
+/// Converts a device's native interleaved stream to the mixer's rate and
+/// channel count: channels are mapped first (mono is duplicated, extra
+/// channels beyond the target are dropped), then the rate is converted by
+/// linear interpolation. State carries across callbacks, so buffers of any
+/// size can be fed in.
+///
+/// Linear interpolation is crude for music but inaudible on speech, and cheap
+/// enough to run in the audio callback.
+pub struct StreamConverter {
+    in_channels: usize,
+    out_channels: usize,
+    /// Input frames advanced per output frame (`in_rate / out_rate`).
+    step: f64,
+    /// Read position between `prev` (0.0) and the next input frame (1.0).
+    position: f64,
+    prev: Vec<f32>,
+    current: Vec<f32>,
+    primed: bool,
+}
+
+impl StreamConverter {
+    pub fn new(in_rate: u32, in_channels: u16, out_rate: u32, out_channels: u16) -> Result<Self, String> {
+        if in_rate == 0 || out_rate == 0 || in_channels == 0 || out_channels == 0 {
+            return Err(format!(
+                "Can't convert {} Hz x{} to {} Hz x{}",
+                in_rate, in_channels, out_rate, out_channels
+            ));
+        }
+        Ok(Self {
+            in_channels: in_channels as usize,
+            out_channels: out_channels as usize,
+            step: in_rate as f64 / out_rate as f64,
+            position: 0.0,
+            prev: vec![0.0; out_channels as usize],
+            current: vec![0.0; out_channels as usize],
+            primed: false,
+        })
+    }
+
+    /// Feeds interleaved input, calling `emit` for each interleaved output sample.
+    pub fn process(&mut self, input: &[f32], mut emit: impl FnMut(f32)) {
+        for frame in input.chunks_exact(self.in_channels) {
+            for (ch, slot) in self.current.iter_mut().enumerate() {
+                *slot = frame[ch.min(self.in_channels - 1)];
+            }
+
+            if !self.primed {
+                self.prev.copy_from_slice(&self.current);
+                self.primed = true;
+                continue;
+            }
+
+            while self.position < 1.0 {
+                let t = self.position as f32;
+                for ch in 0..self.out_channels {
+                    emit(self.prev[ch] + (self.current[ch] - self.prev[ch]) * t);
+                }
+                self.position += self.step;
+            }
+            self.position -= 1.0;
+            self.prev.copy_from_slice(&self.current);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(converter: &mut StreamConverter, input: &[f32]) -> Vec<f32> {
+        let mut out = Vec::new();
+        converter.process(input, |s| out.push(s));
+        out
+    }
+
+    #[test]
+    fn same_format_passes_through_one_frame_late() {
+        let mut converter = StreamConverter::new(48_000, 2, 48_000, 2).unwrap();
+        let out = convert(&mut converter, &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+        assert_eq!(out, vec![0.1, 0.2, 0.3, 0.4]);
+    }
+
+    #[test]
+    fn mono_is_duplicated_to_stereo() {
+        let mut converter = StreamConverter::new(48_000, 1, 48_000, 2).unwrap();
+        let out = convert(&mut converter, &[0.1, 0.2, 0.3]);
+        assert_eq!(out, vec![0.1, 0.1, 0.2, 0.2]);
+    }
+
+    #[test]
+    fn converts_rate_across_callbacks() {
+        let mut converter = StreamConverter::new(44_100, 1, 48_000, 2).unwrap();
+        let mut out = Vec::new();
+        // One second of input in odd-sized buffers
+        let input: Vec<f32> = (0..44_100).map(|n| (n as f32 / 44_100.0).sin()).collect();
+        for chunk in input.chunks(441 + 7) {
+            converter.process(chunk, |s| out.push(s));
+        }
+
+        let frames = out.len() / 2;
+        assert!((frames as i64 - 48_000).abs() <= 2, "got {} frames", frames);
+        // Interpolated values stay within the input's range
+        assert!(out.iter().all(|s| (0.0..=1.0).contains(s)));
+    }
+}
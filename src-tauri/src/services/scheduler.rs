@@ -0,0 +1,156 @@
+use std::sync::Mutex;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Serialize, Deserialize};
+use specta::Type;
+use uuid::Uuid;
+use crate::error::AppError;
+use crate::services::storage::StorageService;
+
+fn default_capture_system_audio() -> bool {
+    true
+}
+
+/// Same options `start_recording_command` accepts.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScheduledRecordingConfig {
+    pub filename: Option<String>,
+    pub mic_device: Option<String>,
+    #[serde(default = "default_capture_system_audio")]
+    pub capture_system_audio: bool,
+    #[serde(default)]
+    pub target_pids: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScheduledRecording {
+    pub id: String,
+    pub start_at: String, // RFC3339
+    pub stop_after_sec: f64,
+    #[serde(default)]
+    pub repeat_daily: bool,
+    pub config: ScheduledRecordingConfig,
+}
+
+/// A schedule whose start time has been reached, with the moment it should stop.
+pub struct DueSchedule {
+    pub schedule: ScheduledRecording,
+    pub stop_at: DateTime<Utc>,
+}
+
+/// Serializes load → modify → save on schedules.json between the commands and
+/// the once-a-second scheduler tick, so neither overwrites the other's change.
+static SCHEDULES_LOCK: Mutex<()> = Mutex::new(());
+
+pub struct SchedulerService;
+
+impl SchedulerService {
+    pub fn add_schedule(
+        start_at: String,
+        stop_after_sec: f64,
+        repeat_daily: bool,
+        config: ScheduledRecordingConfig,
+    ) -> Result<ScheduledRecording, AppError> {
+        let start = parse_time(&start_at)?;
+        if stop_after_sec.is_nan() || stop_after_sec <= 0.0 {
+            return Err(AppError::Validation("stop_after_sec must be positive".to_string()));
+        }
+        if !repeat_daily && start + duration_from_secs(stop_after_sec) <= Utc::now() {
+            return Err(AppError::Validation("Scheduled time is in the past".to_string()));
+        }
+
+        let schedule = ScheduledRecording {
+            id: Uuid::new_v4().to_string(),
+            start_at: start.to_rfc3339(),
+            stop_after_sec,
+            repeat_daily,
+            config,
+        };
+
+        let _guard = SCHEDULES_LOCK.lock().unwrap();
+        let mut schedules = StorageService::load_schedules()?;
+        schedules.push(schedule.clone());
+        StorageService::save_schedules(&schedules)?;
+
+        Ok(schedule)
+    }
+
+    pub fn list_schedules() -> Result<Vec<ScheduledRecording>, AppError> {
+        let _guard = SCHEDULES_LOCK.lock().unwrap();
+        let mut schedules = StorageService::load_schedules()?;
+        schedules.sort_by(|a, b| a.start_at.cmp(&b.start_at));
+        Ok(schedules)
+    }
+
+    pub fn cancel_schedule(id: &str) -> Result<(), AppError> {
+        let _guard = SCHEDULES_LOCK.lock().unwrap();
+        let mut schedules = StorageService::load_schedules()?;
+        let before = schedules.len();
+        schedules.retain(|s| s.id != id);
+        if schedules.len() == before {
+            return Err(AppError::NotFound(format!("Schedule {} not found", id)));
+        }
+        StorageService::save_schedules(&schedules)
+    }
+
+    /// Pops every schedule whose start time has passed.
+    ///
+    /// One-shot schedules are removed, daily ones are moved to their next
+    /// occurrence. Occurrences whose whole window already elapsed (e.g. the app
+    /// was closed) are dropped rather than started late.
+    pub fn take_due(now: DateTime<Utc>) -> Result<Vec<DueSchedule>, AppError> {
+        let _guard = SCHEDULES_LOCK.lock().unwrap();
+        let schedules = StorageService::load_schedules()?;
+        let mut remaining = Vec::with_capacity(schedules.len());
+        let mut due = Vec::new();
+        let mut changed = false;
+
+        for mut schedule in schedules {
+            let start = match parse_time(&schedule.start_at) {
+                Ok(start) => start,
+                Err(_) => {
+                    eprintln!("Dropping schedule {} with invalid start time", schedule.id);
+                    changed = true;
+                    continue;
+                }
+            };
+
+            if start > now {
+                remaining.push(schedule);
+                continue;
+            }
+
+            changed = true;
+            let stop_at = start + duration_from_secs(schedule.stop_after_sec);
+            if stop_at > now {
+                due.push(DueSchedule { schedule: schedule.clone(), stop_at });
+            } else {
+                eprintln!("Missed scheduled recording {} (window ended at {})", schedule.id, stop_at);
+            }
+
+            if schedule.repeat_daily {
+                let mut next = start + Duration::days(1);
+                while next <= now {
+                    next += Duration::days(1);
+                }
+                schedule.start_at = next.to_rfc3339();
+                remaining.push(schedule);
+            }
+        }
+
+        if changed {
+            StorageService::save_schedules(&remaining)?;
+        }
+
+        Ok(due)
+    }
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, AppError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| AppError::Validation(format!("Invalid start time '{}': {}", value, e)))
+}
+
+fn duration_from_secs(secs: f64) -> Duration {
+    Duration::milliseconds((secs * 1000.0) as i64)
+}
@@ -2,9 +2,13 @@ use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 use specta::Type;
 use crate::error::AppError;
+use crate::services::scheduler::ScheduledRecording;
+use crate::services::encoder::OutputFormat;
+use crate::services::keychain;
 use validator::Validate;
 
 #[derive(Serialize, Deserialize, Clone, Type, Validate)]
+#[serde(default)]
 pub struct Settings {
     #[validate(url(message = "Invalid Scriberr URL"))]
     pub scriberr_url: String,
@@ -12,6 +16,102 @@ pub struct Settings {
     pub api_key: String,
     pub output_path: String,
     pub last_sync_timestamp: Option<String>,
+    pub last_sync_ids: Vec<String>, // Jobs synced at exactly last_sync_timestamp, skipped next time
+    pub auto_title: bool, // Replace timestamp titles with one derived from the transcript
+    pub legacy_output_paths: Vec<String>, // Old output folders still holding files after a partial migration
+    pub upload_on_stop: bool, // Start uploading as soon as a recording is stopped
+    pub stop_on_system_audio_stall: bool, // Auto-stop when system audio stops arriving (e.g. permission revoked)
+    pub system_audio_best_effort: bool, // Fall back to mic-only if system capture can't start
+    pub dc_blocker: bool, // High-pass each source to strip DC offset before mixing
+    #[validate(range(min = 0.1, max = 1.0, message = "Clip ceiling must be between 0.1 and 1.0"))]
+    pub clip_ceiling: f32, // Mixed output is soft-limited to ±clip_ceiling
+    #[validate(range(min = 0.5, max = 30.0, message = "Buffer must be between 0.5 and 30 seconds"))]
+    pub buffer_seconds: f32, // Latency budget of the mixer's per-source ring buffers
+    pub storage_quota_bytes: Option<u64>, // Prune oldest synced local audio above this; None = unlimited
+    pub mic_agc: bool, // Normalize the mic toward agc_target_rms while recording
+    pub agc_target_rms: f32,
+    pub agc_attack_ms: f32,
+    pub agc_release_ms: f32,
+    pub power_save: bool, // Pause sync polling and background uploads, e.g. on battery
+    pub heartbeat_interval_secs: u64, // How often to probe the server for reachability; 0 disables
+    pub heartbeat_path: String, // Cheap endpoint probed with HEAD
+    pub output_format: OutputFormat, // Container/codec for new recordings
+    #[validate(range(min = 0.0, max = 4.0, message = "Gain must be between 0 and 4"))]
+    pub mic_gain: f32, // Multiplier on the mic before mixing
+    #[validate(range(min = 0.0, max = 4.0, message = "Gain must be between 0 and 4"))]
+    pub system_gain: f32, // Multiplier on system audio before mixing
+    pub preferred_mic: Option<String>, // Last mic used; the default when none is passed to start
+    #[validate(range(min = 0.0, max = 1.0, message = "Silence threshold must be between 0 and 1"))]
+    pub silence_split_threshold_rms: f32, // Mixed RMS counted as silence by silence_split_sec
+    pub meeting_app_bundle_ids: Vec<String>, // Offered for capture alongside the built-in meeting apps
+    pub audio_cache_max_bytes: u64, // Disk cap for remote audio cached by the proxy; 0 disables the cache
+    pub audio_cache_dir: Option<String>, // Where the proxy caches audio; None uses ~/.config/scriberr-companion/audio-cache
+    pub trash_retention_days: u32, // Trashed recordings older than this are purged along with their files
+    pub api_key_ref: Option<String>, // Keychain account holding api_key, which is left blank on disk
+    pub last_model: Option<String>, // Transcription model of the last upload that picked one
+    pub whisper_binary_path: Option<String>, // Local whisper.cpp CLI for offline transcripts
+    pub whisper_model_path: Option<String>, // ggml model file passed to it
+    #[validate(range(min = 5, max = 86400, message = "Sync interval must be between 5 seconds and a day"))]
+    pub sync_interval_sec: u64, // Background poll interval while nothing is uploading or processing
+}
+
+/// The settings the settings and onboarding screens edit; everything else
+/// has its own command.
+#[derive(Deserialize, Clone, Debug, Type)]
+pub struct SettingsUpdate {
+    pub scriberr_url: String,
+    pub api_key: String,
+    pub output_path: String,
+}
+
+impl SettingsUpdate {
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.scriberr_url = normalize_base_url(&self.scriberr_url);
+        settings.api_key = self.api_key.clone();
+        settings.output_path = self.output_path.clone();
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            scriberr_url: "".to_string(),
+            api_key: "".to_string(),
+            output_path: "".to_string(),
+            last_sync_timestamp: None,
+            last_sync_ids: Vec::new(),
+            auto_title: false,
+            legacy_output_paths: Vec::new(),
+            upload_on_stop: false,
+            stop_on_system_audio_stall: false,
+            system_audio_best_effort: true,
+            dc_blocker: true,
+            clip_ceiling: 1.0,
+            buffer_seconds: 8.0,
+            storage_quota_bytes: None,
+            mic_agc: false,
+            agc_target_rms: 0.1,
+            agc_attack_ms: 50.0,
+            agc_release_ms: 2000.0,
+            power_save: false,
+            heartbeat_interval_secs: 15,
+            heartbeat_path: "/health".to_string(),
+            output_format: OutputFormat::default(),
+            mic_gain: 1.0,
+            system_gain: 1.0,
+            preferred_mic: None,
+            silence_split_threshold_rms: 0.01,
+            meeting_app_bundle_ids: Vec::new(),
+            audio_cache_max_bytes: 1024 * 1024 * 1024,
+            audio_cache_dir: None,
+            trash_retention_days: 30,
+            api_key_ref: None,
+            last_model: None,
+            whisper_binary_path: None,
+            whisper_model_path: None,
+            sync_interval_sec: 30,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Type)]
@@ -25,6 +125,62 @@ pub struct LedgerEntry {
     pub retry_count: u32,
 }
 
+/// Turns what users paste as the server address into the base URL requests
+/// are built on: `https://` is assumed when no scheme is given, and trailing
+/// slashes and an `/api/v1` suffix are dropped, since every path adds its own.
+pub fn normalize_base_url(input: &str) -> String {
+    let input = input.trim();
+    if input.is_empty() {
+        return String::new();
+    }
+    let mut url = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+    // Never strip into the host, e.g. https://api
+    let host_start = url.find("://").map_or(0, |i| i + 3);
+    loop {
+        let trimmed = url.trim_end_matches('/');
+        let stripped = ["/api/v1", "/api"].iter()
+            .filter_map(|suffix| Some((trimmed.len().checked_sub(suffix.len())?, suffix)))
+            .find(|(start, suffix)| *start > host_start && trimmed.get(*start..).is_some_and(|tail| tail.eq_ignore_ascii_case(suffix)))
+            .map(|(start, _)| trimmed[..start].to_string());
+        match stripped {
+            Some(base) => url = base,
+            None => return trimmed.to_string(),
+        }
+    }
+}
+
+/// Progress of an interrupted sync, keyed by the `updated_after` filter it
+/// was running with.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct SyncCursor {
+    pub updated_after: Option<String>,
+    /// Latest `updated_at` processed; every job before it is done.
+    pub last_seen: String,
+    /// The jobs processed that were updated at exactly `last_seen`.
+    #[serde(default)]
+    pub last_seen_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct MigrationFailure {
+    pub file: String,
+    pub error: String,
+}
+
+/// Outcome of moving recordings to a new output folder. `complete` is only
+/// true once the old folder has no files left in it.
+#[derive(Serialize, Deserialize, Clone, Debug, Type)]
+pub struct MigrationReport {
+    pub moved: Vec<String>,
+    pub failed: Vec<MigrationFailure>,
+    pub remaining: Vec<String>,
+    pub complete: bool,
+}
+
 pub struct StorageService;
 
 impl StorageService {
@@ -38,6 +194,21 @@ impl StorageService {
         PathBuf::from(home).join(".config").join("scriberr-companion").join("ledger.json.tmp")
     }
 
+    fn get_schedules_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".config").join("scriberr-companion").join("schedules.json")
+    }
+
+    fn get_schedules_tmp_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".config").join("scriberr-companion").join("schedules.json.tmp")
+    }
+
+    fn get_sync_cursor_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home).join(".config").join("scriberr-companion").join("sync_cursor.json")
+    }
+
     fn get_settings_path() -> PathBuf {
         let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
         PathBuf::from(home).join(".config").join("scriberr-companion").join("settings.json")
@@ -76,6 +247,70 @@ impl StorageService {
         Ok(())
     }
 
+    pub fn load_schedules() -> Result<Vec<ScheduledRecording>, AppError> {
+        let path = Self::get_schedules_path();
+        let tmp_path = Self::get_schedules_tmp_path();
+
+        // A leftover tmp file is an interrupted save and may be truncated;
+        // the last complete write is the one in schedules.json
+        if tmp_path.exists() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let schedules = serde_json::from_str(&content)?;
+        Ok(schedules)
+    }
+
+    pub fn save_schedules(schedules: &[ScheduledRecording]) -> Result<(), AppError> {
+        let path = Self::get_schedules_path();
+        let tmp_path = Self::get_schedules_tmp_path();
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(schedules)?;
+
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, &path)?;
+
+        Ok(())
+    }
+
+    pub fn load_sync_cursor() -> Result<Option<SyncCursor>, AppError> {
+        let path = Self::get_sync_cursor_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content).ok())
+    }
+
+    pub fn save_sync_cursor(cursor: &SyncCursor) -> Result<(), AppError> {
+        let path = Self::get_sync_cursor_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_string_pretty(cursor)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    pub fn clear_sync_cursor() -> Result<(), AppError> {
+        let path = Self::get_sync_cursor_path();
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
     pub fn load_settings(default_path: Option<String>) -> Result<Settings, AppError> {
         let path = Self::get_settings_path();
         // Determine sensible default if not provided
@@ -86,15 +321,24 @@ impl StorageService {
 
         if !path.exists() {
             return Ok(Settings {
-                scriberr_url: "".to_string(),
-                api_key: "".to_string(),
                 output_path: default_output,
-                last_sync_timestamp: None,
+                ..Settings::default()
             });
         }
         
         let content = std::fs::read_to_string(path)?;
         let mut settings: Settings = serde_json::from_str(&content)?;
+        settings.scriberr_url = normalize_base_url(&settings.scriberr_url);
+
+        if !settings.api_key.is_empty() {
+            // Plaintext key from an older version: saving moves it to the keychain.
+            // If that fails the key keeps working from the file and we retry next load.
+            if let Err(e) = Self::save_settings(&settings) {
+                eprintln!("Failed to move API key to the keychain: {:?}", e);
+            }
+        } else if let Some(account) = &settings.api_key_ref {
+            settings.api_key = keychain::get(account)?.unwrap_or_default();
+        }
         
         if settings.output_path.is_empty() {
             settings.output_path = default_output;
@@ -103,26 +347,68 @@ impl StorageService {
         Ok(settings)
     }
 
+    /// Reloads settings from disk, applies `change` and saves them. For
+    /// single-field updates, so fields other writers keep on disk only (e.g.
+    /// the sync baseline) aren't overwritten by a stale in-memory copy.
+    pub fn update_settings(change: impl FnOnce(&mut Settings)) -> Result<(), AppError> {
+        let mut settings = Self::load_settings(None)?;
+        change(&mut settings);
+        Self::save_settings(&settings)
+    }
+
+    /// Writes settings with the API key moved to the keychain, keyed by
+    /// server URL; the file only records which keychain entry to read. The
+    /// entry the file pointed at before is deleted once nothing refers to it,
+    /// i.e. when the key is cleared or the server URL changes.
     pub fn save_settings(settings: &Settings) -> Result<(), AppError> {
         let path = Self::get_settings_path();
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
+        let previous_ref = std::fs::read_to_string(&path).ok()
+            .and_then(|c| serde_json::from_str::<Settings>(&c).ok())
+            .and_then(|s| s.api_key_ref);
+
+        let mut stored = settings.clone();
+        stored.scriberr_url = normalize_base_url(&settings.scriberr_url);
+        if settings.api_key.is_empty() {
+            stored.api_key_ref = None;
+        } else {
+            let account = keychain::api_key_account(&stored.scriberr_url);
+            if keychain::get(&account)?.as_deref() != Some(settings.api_key.as_str()) {
+                keychain::set(&account, &settings.api_key)?;
+            }
+            stored.api_key = String::new();
+            stored.api_key_ref = Some(account);
+        }
         
-        let json = serde_json::to_string_pretty(settings)?;
+        let json = serde_json::to_string_pretty(&stored)?;
         std::fs::write(path, json)?;
+
+        // Only after the file stops pointing at it, so a failed write can't
+        // leave settings referring to a deleted secret
+        if let Some(previous) = previous_ref.filter(|p| stored.api_key_ref.as_ref() != Some(p)) {
+            keychain::delete(&previous)?;
+        }
         Ok(())
     }
-    pub fn migrate_recordings(old_path_str: &str, new_path_str: &str) -> Result<(), AppError> {
+    pub fn migrate_recordings(old_path_str: &str, new_path_str: &str) -> Result<MigrationReport, AppError> {
         let old_path = PathBuf::from(old_path_str);
         let new_path = PathBuf::from(new_path_str);
 
+        let mut report = MigrationReport {
+            moved: Vec::new(),
+            failed: Vec::new(),
+            remaining: Vec::new(),
+            complete: true,
+        };
+
         if old_path.exists() {
              if !new_path.exists() {
                 std::fs::create_dir_all(&new_path)?;
             }
 
-            // Move files physically
+            // Move files physically, carrying on past individual failures
             let entries = std::fs::read_dir(&old_path)?;
             for entry in entries {
                 let entry = entry?;
@@ -130,9 +416,24 @@ impl StorageService {
                 if path.is_file() {
                     let file_name = path.file_name().ok_or(AppError::Unexpected("Invalid filename".into()))?;
                     let new_file_path = new_path.join(file_name);
-                    std::fs::rename(&path, &new_file_path)?;
+                    match std::fs::rename(&path, &new_file_path) {
+                        Ok(()) => report.moved.push(new_file_path.to_string_lossy().to_string()),
+                        Err(e) => report.failed.push(MigrationFailure {
+                            file: path.to_string_lossy().to_string(),
+                            error: e.to_string(),
+                        }),
+                    }
+                }
+            }
+
+            // Only trust the move once the source no longer holds any files
+            for entry in std::fs::read_dir(&old_path)? {
+                let path = entry?.path();
+                if path.is_file() {
+                    report.remaining.push(path.to_string_lossy().to_string());
                 }
             }
+            report.complete = report.failed.is_empty() && report.remaining.is_empty();
         }
 
         if let Ok(mut ledger_entries) = Self::load_ledger() {
@@ -142,6 +443,9 @@ impl StorageService {
                 if entry_path.starts_with(&old_path) {
                     if let Ok(stripped) = entry_path.strip_prefix(&old_path) {
                         let new_entry_path = new_path.join(stripped);
+                        if !new_entry_path.exists() {
+                            continue; // Not moved, still lives in the old folder
+                        }
                         entry.file_path = new_entry_path.to_string_lossy().to_string();
                         changed = true;
                     }
@@ -152,6 +456,22 @@ impl StorageService {
             }
         }
 
-        Ok(())
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_urls_are_normalized() {
+        assert_eq!(normalize_base_url("https://scriberr.example.com/"), "https://scriberr.example.com");
+        assert_eq!(normalize_base_url(" host:8080 "), "https://host:8080");
+        assert_eq!(normalize_base_url("http://10.0.0.5:8080/api/v1/"), "http://10.0.0.5:8080");
+        assert_eq!(normalize_base_url("https://example.com/scriberr/API/"), "https://example.com/scriberr");
+        assert_eq!(normalize_base_url("https://example.com/apiary"), "https://example.com/apiary");
+        assert_eq!(normalize_base_url("https://api/"), "https://api");
+        assert_eq!(normalize_base_url(""), "");
     }
 }
@@ -1,11 +1,20 @@
 use std::sync::Arc;
-use tokio::time::{interval, Duration};
-use crate::services::db::{DatabaseService, SyncStatus};
-use crate::services::storage::StorageService;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::io::AsyncWriteExt;
+use tokio::time::Duration;
+use crate::services::compat::CompatService;
+use crate::services::db::{DatabaseService, SyncStatus, CachedRecording};
+use crate::services::storage::{Settings, StorageService, SyncCursor};
+use crate::services::{audio_cache, encoder, http, integrity, paths};
+use crate::services::events::LibraryEvent;
+use crate::services::quota::QuotaService;
 use crate::error::AppError;
-use serde::Deserialize;
-use tauri::{AppHandle, Emitter};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{AppHandle, Emitter, Runtime, Wry};
 use serde_json::Value;
+use tokio_util::io::{InspectReader, ReaderStream};
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Deserialize)]
 pub struct RemoteJob {
@@ -22,19 +31,274 @@ pub struct RemoteJob {
 
 
 
-pub struct SyncService {
+/// Where a sync has got to in the `updated_at`-ordered job list: every job
+/// updated before `updated_at` is done, and of those updated at exactly that
+/// instant, the ones in `ids`.
+#[derive(Debug, Clone, PartialEq)]
+struct SyncPosition {
+    updated_at: String,
+    ids: Vec<String>,
+}
+
+impl SyncPosition {
+    fn from_settings(settings: &Settings) -> Option<Self> {
+        settings.last_sync_timestamp.clone()
+            .map(|updated_at| Self { updated_at, ids: settings.last_sync_ids.clone() })
+    }
+
+    /// Moves `position` past a job just processed.
+    fn advance(position: &mut Option<Self>, updated_at: &str, id: &str) {
+        if let Some(current) = position {
+            if same_instant(updated_at, &current.updated_at) {
+                current.ids.push(id.to_string());
+                return;
+            }
+            if is_later(&current.updated_at, updated_at) {
+                return;
+            }
+        }
+        *position = Some(Self { updated_at: updated_at.to_string(), ids: vec![id.to_string()] });
+    }
+
+    /// Whether the job updated at `updated_at` was already processed.
+    fn covers(&self, updated_at: &str, id: &str) -> bool {
+        is_later(&self.updated_at, updated_at)
+            || (same_instant(&self.updated_at, updated_at) && self.ids.iter().any(|seen| seen == id))
+    }
+}
+
+/// Name of the event carrying a fresh `SyncQueue` whenever it changes.
+pub const SYNC_QUEUE_EVENT: &str = "sync-queue-changed";
+
+#[derive(Debug, Clone, Copy, Serialize, Type, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferKind {
+    Upload,
+    Download,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct ActiveTransfer {
+    pub local_id: String,
+    pub kind: TransferKind,
+    pub started_at: String,
+}
+
+/// How much work the background sync still has, by recording state.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct SyncQueue {
+    pub pending_upload: usize,
+    pub uploading: usize,
+    pub remote_pending: usize,
+    pub processing_remote: usize,
+    pub failed: usize,
+    pub active_transfers: Vec<ActiveTransfer>,
+}
+
+/// Name of the event sent when sync moves a recording to another status,
+/// e.g. from processing to completed.
+pub const RECORDING_STATUS_EVENT: &str = "recording-status-changed";
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct RecordingStatusChange {
+    pub local_id: String,
+    pub old_status: String,
+    pub new_status: String,
+}
+
+/// Name of the event sent after each recording of `upload_all_pending`.
+pub const BATCH_UPLOAD_PROGRESS_EVENT: &str = "batch-upload-progress";
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct BatchUploadProgress {
+    pub local_id: String,
+    /// 1-based position in the batch.
+    pub index: usize,
+    pub total: usize,
+    pub succeeded: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type, PartialEq)]
+pub struct BatchUploadSummary {
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+/// Name of the event reporting how much of an upload has been sent.
+pub const UPLOAD_PROGRESS_EVENT: &str = "upload-progress";
+
+/// Minimum time between progress events for one transfer (~4/s).
+const PROGRESS_EVENT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct UploadProgress {
+    pub local_id: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+    /// 0-100
+    pub percent: f64,
+}
+
+impl UploadProgress {
+    fn new(local_id: &str, bytes_sent: u64, total_bytes: u64) -> Self {
+        let percent = if total_bytes == 0 { 100.0 } else { bytes_sent as f64 * 100.0 / total_bytes as f64 };
+        Self { local_id: local_id.to_string(), bytes_sent, total_bytes, percent }
+    }
+}
+
+/// Name of the event reporting how much of a download has arrived.
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct DownloadProgress {
+    pub local_id: String,
+    /// Includes bytes from earlier attempts when a download resumes.
+    pub bytes_received: u64,
+    /// `None` when the server doesn't send a length.
+    pub total_bytes: Option<u64>,
+    /// 0-100, when the total is known
+    pub percent: Option<f64>,
+}
+
+impl DownloadProgress {
+    fn new(local_id: &str, bytes_received: u64, total_bytes: Option<u64>) -> Self {
+        let percent = total_bytes.map(|total| if total == 0 { 100.0 } else { (bytes_received as f64 * 100.0 / total as f64).min(100.0) });
+        Self { local_id: local_id.to_string(), bytes_received, total_bytes, percent }
+    }
+}
+
+/// Jobs requested per list page during sync.
+const SYNC_PAGE_SIZE: usize = 50;
+
+/// Poll interval while a recording is uploading or processing on the server.
+const ACTIVE_SYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Poll interval when settings can't be read.
+const DEFAULT_SYNC_INTERVAL_SECS: u64 = 30;
+
+type Transfers = Arc<std::sync::Mutex<Vec<ActiveTransfer>>>;
+
+/// Cancellation handles of in-flight uploads, by local id.
+type UploadCancellations = Arc<std::sync::Mutex<std::collections::HashMap<String, CancellationToken>>>;
+
+/// Generic over the runtime so tests can drive it with tauri's mock runtime.
+pub struct SyncService<R: Runtime = Wry> {
     db: Arc<DatabaseService>,
-    app_handle: AppHandle,
+    app_handle: AppHandle<R>,
+    client: reqwest::Client,
+    /// Battery saver: while set, the sync loop skips its ticks.
+    power_save: Arc<AtomicBool>,
+    /// Last known reachability from the heartbeat; optimistic until told otherwise.
+    online: Arc<AtomicBool>,
+    transfers: Transfers,
+    uploads: UploadCancellations,
+    /// Wakes the sync loop for an immediate poll.
+    poll_now: Arc<tokio::sync::Notify>,
 }
 
-impl SyncService {
-    pub fn new(db: Arc<DatabaseService>, app_handle: AppHandle) -> Self {
-        Self { db, app_handle }
+impl<R: Runtime> SyncService<R> {
+    pub fn new(db: Arc<DatabaseService>, app_handle: AppHandle<R>) -> Self {
+        Self { db, app_handle, client: http::build_client().expect("Failed to build HTTP client"), power_save: Arc::new(AtomicBool::new(false)), online: Arc::new(AtomicBool::new(true)), transfers: Transfers::default(), uploads: UploadCancellations::default(), poll_now: Arc::new(tokio::sync::Notify::new()) }
+    }
+
+    /// Cancels the in-flight upload of `local_id`; false if there is none.
+    /// The upload returns `AppError::Cancelled` and the recording goes back
+    /// to `DraftReady`.
+    pub fn abort_upload(&self, local_id: &str) -> bool {
+        match self.uploads.lock().unwrap().get(local_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Runs the sync loop's next poll now instead of waiting out its interval.
+    /// Still skipped while offline or in power save.
+    pub fn trigger_sync(&self) {
+        self.poll_now.notify_one();
+    }
+
+    pub fn set_power_save(&self, enabled: bool) {
+        self.power_save.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_power_save(&self) -> bool {
+        self.power_save.load(Ordering::SeqCst)
+    }
+
+    /// The flag the heartbeat keeps current.
+    pub fn online_flag(&self) -> Arc<AtomicBool> {
+        self.online.clone()
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online.load(Ordering::SeqCst)
+    }
+
+    pub async fn queue(&self) -> Result<SyncQueue, AppError> {
+        Self::queue_snapshot(&self.db, &self.transfers).await
+    }
+
+    async fn queue_snapshot(db: &DatabaseService, transfers: &Transfers) -> Result<SyncQueue, AppError> {
+        let mut queue = SyncQueue {
+            pending_upload: 0,
+            uploading: 0,
+            remote_pending: 0,
+            processing_remote: 0,
+            failed: 0,
+            active_transfers: transfers.lock().unwrap().clone(),
+        };
+        for recording in db.get_all_recordings().await? {
+            match SyncStatus::from(recording.sync_status) {
+                SyncStatus::DraftReady | SyncStatus::LocalTranscribed => queue.pending_upload += 1,
+                SyncStatus::Uploading => queue.uploading += 1,
+                SyncStatus::RemotePending => queue.remote_pending += 1,
+                SyncStatus::ProcessingRemote => queue.processing_remote += 1,
+                SyncStatus::Failed => queue.failed += 1,
+                SyncStatus::CompletedSynced => {}
+            }
+        }
+        Ok(queue)
+    }
+
+    async fn emit_queue(db: &DatabaseService, app: &AppHandle<R>, transfers: &Transfers) {
+        match Self::queue_snapshot(db, transfers).await {
+            Ok(queue) => {
+                let _ = app.emit(SYNC_QUEUE_EVENT, queue);
+            }
+            Err(e) => eprintln!("Failed to compute sync queue: {:?}", e),
+        }
+    }
+
+    fn begin_transfer(&self, local_id: &str, kind: TransferKind) {
+        self.transfers.lock().unwrap().push(ActiveTransfer {
+            local_id: local_id.to_string(),
+            kind,
+            started_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    fn end_transfer(&self, local_id: &str, kind: TransferKind) {
+        self.transfers.lock().unwrap().retain(|t| !(t.local_id == local_id && t.kind == kind));
+    }
+
+    /// Replaces the HTTP client used for every server call.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
     }
 
     pub fn start(&self) {
         let db = self.db.clone();
         let app = self.app_handle.clone();
+        let client = self.client.clone();
+        let power_save = self.power_save.clone();
+        let online = self.online.clone();
+        let transfers = self.transfers.clone();
+        let poll_now = self.poll_now.clone();
         
         // We need a way to clone self to call instance methods, but we can't easily clone SyncService if it's not Clone.
         // Usually we wrap SyncService in Arc, but here structure is: AppState has Arc<SyncService>.
@@ -46,125 +310,390 @@ impl SyncService {
         // using a "perform_delta_sync_internal" that takes db/app.
         
         tauri::async_runtime::spawn(async move {
-            let mut ticker = interval(Duration::from_secs(30)); // Poll less frequently for delta sync
+            // The first poll runs right away
+            let mut delay = Duration::ZERO;
             loop {
-                ticker.tick().await;
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = poll_now.notified() => {}
+                }
                 // We need to load settings inside the loop
-                 if let Ok(settings) = StorageService::load_settings(None) {
+                let settings = StorageService::load_settings(None).ok();
+                let paused = power_save.load(Ordering::SeqCst) || !online.load(Ordering::SeqCst);
+                if let Some(settings) = settings.clone().filter(|_| !paused) {
+                    if let Err(e) = Self::purge_trashed(&db, &app, &client, settings.trash_retention_days).await {
+                        eprintln!("Trash purge failed: {:?}", e);
+                    }
                     if !settings.api_key.is_empty() && !settings.scriberr_url.is_empty() {
-                         let last_sync = settings.last_sync_timestamp.clone();
+                         let last_sync = SyncPosition::from_settings(&settings);
                          // Logic below
-                         if let Err(e) = Self::sync_jobs_internal(db.clone(), app.clone(), settings, last_sync).await {
+                         let quota = settings.storage_quota_bytes;
+                         let synced = Self::sync_jobs_internal(db.clone(), app.clone(), client.clone(), settings, last_sync).await
+                             .and_then(Self::save_sync_timestamp);
+                         if let Err(e) = synced {
                              eprintln!("Auto-sync error: {:?}", e);
                          }
+                         if let Some(quota) = quota {
+                             if let Err(e) = QuotaService::enforce(&db, &app, quota).await {
+                                 eprintln!("Storage quota enforcement failed: {:?}", e);
+                             }
+                         }
+                         Self::emit_queue(&db, &app, &transfers).await;
                     }
-                 }
+                }
+                delay = Self::poll_interval(&db, &transfers, settings.as_ref()).await;
             }
         });
     }
 
+    /// `ACTIVE_SYNC_INTERVAL` while anything is uploading or being transcribed,
+    /// so results show up promptly, otherwise the configured `sync_interval_sec`.
+    async fn poll_interval(db: &DatabaseService, transfers: &Transfers, settings: Option<&Settings>) -> Duration {
+        let busy = match Self::queue_snapshot(db, transfers).await {
+            Ok(queue) => queue.uploading > 0 || queue.processing_remote > 0,
+            Err(_) => false,
+        };
+        if busy {
+            return ACTIVE_SYNC_INTERVAL;
+        }
+        let secs = settings.map_or(DEFAULT_SYNC_INTERVAL_SECS, |s| s.sync_interval_sec);
+        Duration::from_secs(secs.max(1))
+    }
+
+    /// Refetches every job. The delta baseline is dropped first, so a full
+    /// sync that fails leaves the next delta sync to do a full one too.
     pub async fn perform_full_sync(&self) -> Result<(), AppError> {
         let mut settings = StorageService::load_settings(None)?;
-        Self::sync_jobs_internal(self.db.clone(), self.app_handle.clone(), settings.clone(), None).await?;
-        
-        settings.last_sync_timestamp = Some(chrono::Utc::now().to_rfc3339());
-        StorageService::save_settings(&settings)?; 
-        Ok(())
+        settings.last_sync_timestamp = None;
+        settings.last_sync_ids.clear();
+        StorageService::save_settings(&settings)?;
+        let last_seen = Self::sync_jobs_internal(self.db.clone(), self.app_handle.clone(), self.client.clone(), settings, None).await?;
+        Self::emit_queue(&self.db, &self.app_handle, &self.transfers).await;
+        Self::save_sync_timestamp(last_seen)
     }
 
     pub async fn perform_delta_sync(&self) -> Result<(), AppError> {
-        let mut settings = StorageService::load_settings(None)?;
-        let last_sync = settings.last_sync_timestamp.clone();
-        
-        Self::sync_jobs_internal(self.db.clone(), self.app_handle.clone(), settings.clone(), last_sync).await?;
+        let settings = StorageService::load_settings(None)?;
+        let last_sync = SyncPosition::from_settings(&settings);
         
-        settings.last_sync_timestamp = Some(chrono::Utc::now().to_rfc3339());
-        StorageService::save_settings(&settings)?;
+        let last_seen = Self::sync_jobs_internal(self.db.clone(), self.app_handle.clone(), self.client.clone(), settings, last_sync).await?;
+        Self::emit_queue(&self.db, &self.app_handle, &self.transfers).await;
+        Self::save_sync_timestamp(last_seen)
+    }
+
+    /// Stores where the sync got to as the next delta sync's baseline. The
+    /// server's clock, not ours, so nothing updated mid-sync is skipped.
+    fn save_sync_timestamp(last_seen: Option<SyncPosition>) -> Result<(), AppError> {
+        // Reloaded so settings saved during the sync aren't overwritten
+        let mut settings = StorageService::load_settings(None)?;
+        if SyncPosition::from_settings(&settings) != last_seen {
+            let (timestamp, ids) = last_seen.map_or((None, Vec::new()), |p| (Some(p.updated_at), p.ids));
+            settings.last_sync_timestamp = timestamp;
+            settings.last_sync_ids = ids;
+            StorageService::save_settings(&settings)?;
+        }
         Ok(())
     }
 
+    /// Walks the jobs updated since `since` oldest first, until a request
+    /// brings nothing new. Each request asks for jobs updated from just before
+    /// the newest `updated_at` processed, so jobs sharing that timestamp but
+    /// cut off by the page limit come back; the ones already processed are
+    /// skipped by id. Jobs updated mid-sync move to the end rather than
+    /// shifting between pages. Servers that send no `updated_at` are paged
+    /// through with `page` instead. Returns where the sync got to, or `since`
+    /// when nothing changed.
     async fn sync_jobs_internal(
         db: Arc<DatabaseService>, 
-        app: AppHandle, 
+        app: AppHandle<R>, 
+        client: reqwest::Client,
         settings: crate::services::storage::Settings, 
-        updated_after: Option<String>
-    ) -> Result<(), AppError> {
-        let client = reqwest::Client::new();
+        since: Option<SyncPosition>
+    ) -> Result<Option<SyncPosition>, AppError> {
         let base_url = settings.scriberr_url.trim_end_matches('/');
-        let mut page = 1;
-        let limit = 50;
-        
-        loop {
-            let mut url = format!("{}/api/v1/transcription/list?page={}&limit={}", base_url, page, limit);
-            if let Some(ref ua) = updated_after {
-                url.push_str(&format!("&updated_after={}", ua));
-            }
+        let list_url = format!("{}/api/v1/transcription/list", base_url);
+        let updated_after = since.as_ref().map(|p| p.updated_at.clone());
 
-            let resp = client.get(&url)
-                .header("X-API-Key", &settings.api_key)
-                .send()
-                .await
-                .map_err(|e| AppError::Network(e.to_string()))?;
+        LibraryEvent::SyncStarted.emit(&app);
 
-            if !resp.status().is_success() {
-                return Err(AppError::Network(format!("Sync failed: {}", resp.status())));
-            }
+        // Every exit, failed or not, closes the run for the UI
+        let result: Result<Option<SyncPosition>, AppError> = async {
+            // Resume an interrupted run of the same sync instead of starting over
+            let mut position = match StorageService::load_sync_cursor() {
+                Ok(Some(saved)) if saved.updated_after == updated_after => {
+                    Some(SyncPosition { updated_at: saved.last_seen, ids: saved.last_seen_ids })
+                }
+                _ => since.clone(),
+            };
+            let mut page = 0;
+            let mut processed = 0;
+            // `page` parameter, past 1 only while `position` can't advance
+            let mut offset_page = 1;
+            let mut previous_ids: Vec<String> = Vec::new();
+
+            loop {
+                page += 1;
+                let mut params = vec![
+                    ("limit", SYNC_PAGE_SIZE.to_string()),
+                    ("sort_by", "updated_at".to_string()),
+                    ("sort_order", "asc".to_string()),
+                ];
+                if let Some(position) = &position {
+                    params.push(("updated_after", just_before(&position.updated_at)));
+                }
+                if offset_page > 1 {
+                    params.push(("page", offset_page.to_string()));
+                }
+                let url = reqwest::Url::parse_with_params(&list_url, &params)
+                    .map_err(|e| AppError::Validation(format!("Invalid Scriberr URL: {}", e)))?;
 
-            // Try to parse as Value first to handle flexible response
-            let body_val: Value = resp.json().await.map_err(|e| AppError::Network(e.to_string()))?;
+                let body_val = Self::fetch_page(&client, url.as_str(), &settings.api_key).await?;
             
-            // Extract jobs array
-            let jobs: Vec<RemoteJob> = if let Some(arr) = body_val.as_array() {
-                serde_json::from_value(Value::Array(arr.clone())).unwrap_or_default()
-            } else if let Some(obj) = body_val.as_object() {
-                if let Some(j) = obj.get("jobs").or(obj.get("data")) {
-                    serde_json::from_value(j.clone()).unwrap_or_default()
+                // Extract jobs array
+                let jobs: Vec<RemoteJob> = if let Some(arr) = body_val.as_array() {
+                    serde_json::from_value(Value::Array(arr.clone())).unwrap_or_default()
+                } else if let Some(obj) = body_val.as_object() {
+                    if let Some(j) = obj.get("jobs").or(obj.get("data")) {
+                        serde_json::from_value(j.clone()).unwrap_or_default()
+                    } else {
+                        vec![]
+                    }
                 } else {
                     vec![]
+                };
+
+                if jobs.is_empty() {
+                    break;
                 }
-            } else {
-                vec![]
-            };
 
-            if jobs.is_empty() {
-                break;
-            }
+                let page_ids: Vec<String> = jobs.iter().map(|job| job.id.clone()).collect();
+                // A server ignoring both updated_after and page would serve this forever
+                if offset_page > 1 && page_ids == previous_ids {
+                    break;
+                }
+                let full_page = jobs.len() >= SYNC_PAGE_SIZE;
+                let timestamped = jobs.iter().any(|job| job.updated_at.is_some());
+                let mut next = position.clone();
 
-            let count = jobs.len();
+                for job in jobs {
+                    if let (Some(position), Some(updated_at)) = (&position, &job.updated_at) {
+                        if position.covers(updated_at, &job.id) {
+                            continue;
+                        }
+                    }
+                    if let Some(updated_at) = &job.updated_at {
+                        SyncPosition::advance(&mut next, updated_at, &job.id);
+                    }
+                    processed += 1;
 
-            for job in jobs {
-                if job.deleted_at.is_some() {
-                    db.delete_remote_recording(&job.id).await?;
-                    let _ = app.emit("recording-deleted-remote", &job.id);
-                } else {
-                    let audio_url = format!("{}/api/v1/transcription/{}/audio", base_url, job.id);
+                    if job.deleted_at.is_some() {
+                        db.delete_remote_recording(&job.id).await?;
+                        LibraryEvent::Deleted { local_id: None, remote_job_id: Some(job.id.clone()) }.emit(&app);
+                    } else {
+                        let audio_url = format!("{}/api/v1/transcription/{}/audio", base_url, job.id);
                     
-                    db.upsert_remote_recording(
-                        &job.id,
-                        job.title.as_deref().unwrap_or("Untitled"),
-                        &job.status,
-                        &job.created_at,
-                        job.transcript.as_deref(),
-                        job.summary.as_deref(),
-                        job.individual_transcripts.as_deref(),
-                        Some(&audio_url)
-                    ).await?;
+                        let (recording, old_status) = db.upsert_remote_recording(
+                            &job.id,
+                            job.title.as_deref().unwrap_or("Untitled"),
+                            &job.status,
+                            &job.created_at,
+                            job.transcript.as_deref(),
+                            job.summary.as_deref(),
+                            job.individual_transcripts.as_deref(),
+                            Some(&audio_url)
+                        ).await?;
+
+                        // Trashed rows stay hidden until restored or purged
+                        if recording.deleted_at.is_some() {
+                            continue;
+                        }
+                        if let Some(old_status) = old_status {
+                            let _ = app.emit(RECORDING_STATUS_EVENT, RecordingStatusChange {
+                                local_id: recording.local_id.clone(),
+                                old_status,
+                                new_status: recording.sync_status.clone(),
+                            });
+                        }
+                        if settings.auto_title {
+                            Self::apply_auto_title(&db, &app, recording).await?;
+                        } else {
+                            LibraryEvent::Updated(recording).emit(&app);
+                        }
+                    }
+                }
+
+                LibraryEvent::SyncProgress { page, processed }.emit(&app);
+                previous_ids = page_ids;
+
+                if let Some(advanced) = next.filter(|next| Some(next) != position.as_ref()) {
+                    let saved = SyncCursor {
+                        updated_after: updated_after.clone(),
+                        last_seen: advanced.updated_at.clone(),
+                        last_seen_ids: advanced.ids.clone(),
+                    };
+                    if let Err(e) = StorageService::save_sync_cursor(&saved) {
+                        eprintln!("Failed to persist sync cursor: {:?}", e);
+                    }
+                    position = Some(advanced);
+                    offset_page = 1;
+                } else if full_page || !timestamped {
+                    // A full page of jobs already processed (more share one
+                    // timestamp than fit a page), or no timestamps to go by
+                    offset_page += 1;
+                } else {
+                    break;
                 }
             }
 
-            if count < limit {
-                break;
+            StorageService::clear_sync_cursor()?;
+
+            // After the pages, so the titles the server just sent can't undo the push
+            Self::push_dirty_titles(&db, &app, &client, &settings).await?;
+
+            Ok(position)
+        }
+        .await;
+
+        LibraryEvent::SyncCompleted.emit(&app);
+        result
+    }
+
+    /// Fetches one list page, retrying transient failures (connection errors,
+    /// 5xx, 429) with exponential backoff.
+    async fn fetch_page(client: &reqwest::Client, url: &str, api_key: &str) -> Result<Value, AppError> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = client.get(url)
+                .header("X-API-Key", api_key)
+                .send()
+                .await;
+
+            let error = match result {
+                Ok(resp) if resp.status().is_success() => {
+                    // Try to parse as Value first to handle flexible response
+                    return http::read_json(resp).await;
+                }
+                Ok(resp) => {
+                    let status = resp.status();
+                    let transient = status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    let error = if http::is_html(&resp) {
+                        http::unexpected_body(resp).await
+                    } else {
+                        AppError::http(status, "Sync failed")
+                    };
+                    if !transient {
+                        return Err(error);
+                    }
+                    error
+                }
+                Err(e) => e.into(),
+            };
+
+            if attempt >= SYNC_FETCH_ATTEMPTS {
+                return Err(error);
             }
-            page += 1;
+
+            let delay = SYNC_RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+            eprintln!("Sync page request failed (attempt {}), retrying in {:?}: {:?}", attempt, delay, error);
+            tokio::time::sleep(delay).await;
         }
+    }
 
-        // Notify frontend that sync is done (optional, but helpful to refresh list)
-        let _ = app.emit("sync-completed", ());
-        
+    /// Replaces a timestamp/placeholder title with one derived from the summary
+    /// or transcript, then publishes the row. Titles the user typed are never touched.
+    /// The new title stays local: it's marked `title_auto` so the server's
+    /// placeholder doesn't come back with every sync page, but it's never pushed.
+    async fn apply_auto_title(db: &DatabaseService, app: &AppHandle<R>, recording: CachedRecording) -> Result<(), AppError> {
+        if !is_generated_title(&recording.title) {
+            LibraryEvent::Updated(recording).emit(app);
+            return Ok(());
+        }
+
+        let Some(title) = title_from_content(recording.summary_text.as_deref(), recording.transcript_text.as_deref()) else {
+            LibraryEvent::Updated(recording).emit(app);
+            return Ok(());
+        };
+
+        db.set_auto_title(&recording.local_id, &title).await?;
+        let updated = db.get_recording(&recording.local_id).await?;
+        LibraryEvent::Updated(updated).emit(app);
         Ok(())
     }
 
-    pub async fn upload_recording(&self, local_id: &str) -> Result<crate::services::db::CachedRecording, AppError> {
+    /// Uploads the recording, transcribed with `model` when one is given. The
+    /// model must be one the server lists, or nothing is sent.
+    pub async fn upload_recording(&self, local_id: &str, model: Option<&str>) -> Result<crate::services::db::CachedRecording, AppError> {
+        let cancel = CancellationToken::new();
+        self.uploads.lock().unwrap().insert(local_id.to_string(), cancel.clone());
+        self.begin_transfer(local_id, TransferKind::Upload);
+        Self::emit_queue(&self.db, &self.app_handle, &self.transfers).await;
+
+        let result = self.upload_recording_inner(local_id, model, &cancel).await;
+        self.end_transfer(local_id, TransferKind::Upload);
+        self.uploads.lock().unwrap().remove(local_id);
+
+        // Status moved (uploading -> processing/failed) either way
+        if let Ok(recording) = self.db.get_recording(local_id).await {
+            LibraryEvent::Updated(recording).emit(&self.app_handle);
+        }
+        Self::emit_queue(&self.db, &self.app_handle, &self.transfers).await;
+
+        result
+    }
+
+    /// Uploads every draft and failed upload that never reached the server,
+    /// one at a time. Drafts whose file is gone are marked failed and skipped.
+    pub async fn upload_all_pending(&self) -> Result<BatchUploadSummary, AppError> {
+        if !self.is_online() {
+            return Err(AppError::Offline);
+        }
+
+        // Oldest first, the order they were recorded in
+        let pending: Vec<_> = self.db.get_all_recordings().await?.into_iter().rev()
+            .filter(|r| r.remote_job_id.is_none())
+            .filter(|r| matches!(
+                SyncStatus::from(r.sync_status.clone()),
+                SyncStatus::DraftReady | SyncStatus::LocalTranscribed | SyncStatus::Failed
+            ))
+            .collect();
+
+        let total = pending.len();
+        let mut summary = BatchUploadSummary { succeeded: 0, failed: 0 };
+        for (index, recording) in pending.into_iter().enumerate() {
+            let has_file = recording.local_file_path.as_deref()
+                .is_some_and(|p| std::path::Path::new(p).exists());
+            let result = if has_file {
+                self.upload_recording(&recording.local_id, None).await.map(|_| ())
+            } else {
+                self.db.update_sync_status(&recording.local_id, SyncStatus::Failed).await?;
+                if let Ok(updated) = self.db.get_recording(&recording.local_id).await {
+                    LibraryEvent::Updated(updated).emit(&self.app_handle);
+                }
+                Err(AppError::NotFound("File not found on disk".to_string()))
+            };
+
+            match &result {
+                Ok(()) => summary.succeeded += 1,
+                Err(_) => summary.failed += 1,
+            }
+            let _ = self.app_handle.emit(BATCH_UPLOAD_PROGRESS_EVENT, BatchUploadProgress {
+                local_id: recording.local_id,
+                index: index + 1,
+                total,
+                succeeded: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+        Ok(summary)
+    }
+
+    async fn upload_recording_inner(&self, local_id: &str, model: Option<&str>, cancel: &CancellationToken) -> Result<crate::services::db::CachedRecording, AppError> {
+        // Fail fast rather than waiting on a connect timeout
+        if !self.is_online() {
+            return Err(AppError::Offline);
+        }
+
         // 1. Load Settings
         let settings = StorageService::load_settings(None)?;
         if settings.scriberr_url.is_empty() || settings.api_key.is_empty() {
@@ -182,41 +711,109 @@ impl SyncService {
              return Err(AppError::NotFound("File not found on disk".to_string()));
         }
 
+        if let Some(model) = model {
+            let models = CompatService::list_models(&self.client, &settings.scriberr_url, &settings.api_key).await?;
+            if !models.iter().any(|m| m.id == model) {
+                return Err(AppError::Validation(format!("The server doesn't offer the model \"{}\"", model)));
+            }
+        }
+
+        // Identical audio that already has a server job is linked, not sent again
+        let hash = match recording.file_hash.clone() {
+            Some(hash) => hash,
+            None => {
+                let path = file_path.clone();
+                let hash = tokio::task::spawn_blocking(move || integrity::hash_file(&path)).await
+                    .map_err(|e| AppError::Unexpected(e.to_string()))??;
+                self.db.set_file_hash(local_id, &hash).await?;
+                hash
+            }
+        };
+        if let Some(existing) = self.db.find_uploaded_by_hash(&hash, local_id).await? {
+            let remote_id = existing.remote_job_id.unwrap_or_default();
+            println!("{} has the same audio as {}, linking to job {} instead of uploading", local_id, existing.local_id, remote_id);
+            self.db.finalize_upload(local_id, &remote_id).await?;
+            if !recording.keep_offline {
+                let _ = tokio::fs::remove_file(&file_path).await;
+            }
+            return Ok(self.db.get_recording(local_id).await?);
+        }
+
         // 4. Update Status
         self.db.update_sync_status(local_id, SyncStatus::Uploading).await?;
+        if let Ok(uploading) = self.db.get_recording(local_id).await {
+            LibraryEvent::Updated(uploading).emit(&self.app_handle);
+        }
 
         // 5. Prepare Client
-        let client = reqwest::Client::new();
+        let client = &self.client;
         let base_url = settings.scriberr_url.trim_end_matches('/');
         let endpoint = format!("{}/api/v1/transcription/upload", base_url);
 
-        // 6. Read File
-        let file_bytes = tokio::fs::read(&file_path).await?;
+        // 6. Stream File (long recordings would otherwise be held in memory in full)
+        let file = tokio::fs::File::open(&file_path).await?;
+        let file_len = file.metadata().await?.len();
         let filename = file_path.file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("recording.wav")
             .to_string();
 
-        let part = reqwest::multipart::Part::bytes(file_bytes).file_name(filename.clone());
-        let form = reqwest::multipart::Form::new()
+        // The last bytes are only reported once the server has accepted the upload
+        let progress = {
+            let app = self.app_handle.clone();
+            let local_id = local_id.to_string();
+            let mut sent = 0u64;
+            let mut last_report = std::time::Instant::now();
+            move |chunk: &[u8]| {
+                sent += chunk.len() as u64;
+                if sent < file_len && last_report.elapsed() >= PROGRESS_EVENT_INTERVAL {
+                    last_report = std::time::Instant::now();
+                    let _ = app.emit(UPLOAD_PROGRESS_EVENT, UploadProgress::new(&local_id, sent, file_len));
+                }
+            }
+        };
+        let body = reqwest::Body::wrap_stream(ReaderStream::new(InspectReader::new(file, progress)));
+        let part = reqwest::multipart::Part::stream_with_length(body, file_len)
+            .file_name(filename.clone())
+            .mime_str(encoder::mime_for_path(&file_path))?;
+        let mut form = reqwest::multipart::Form::new()
             .part("audio", part)
-            .text("title", recording.title.clone());
+            .text("title", recording.title.clone())
+            // Lets the server detect a truncated upload
+            .text("sha256", hash.clone());
+        if let Some(model) = model {
+            form = form.text("model", model.to_string());
+        }
 
-        // 7. Send Request
-        let response = client.post(&endpoint)
+        // 7. Send Request; dropping the future on abort closes the connection
+        let request = client.post(&endpoint)
             .header("X-API-Key", &settings.api_key)
             .multipart(form)
-            .send()
-            .await;
+            .timeout(http::TRANSFER_TIMEOUT)
+            .send();
+        let response = tokio::select! {
+            response = request => response,
+            _ = cancel.cancelled() => {
+                self.db.update_sync_status(local_id, SyncStatus::DraftReady).await?;
+                return Err(AppError::Cancelled);
+            }
+        };
 
         match response {
             Ok(resp) => {
                 if resp.status().is_success() {
-                    let body: serde_json::Value = resp.json().await?;
+                    let body: serde_json::Value = match http::read_json(resp).await {
+                        Ok(body) => body,
+                        Err(e) => {
+                            self.db.update_sync_status(local_id, SyncStatus::Failed).await?;
+                            return Err(e);
+                        }
+                    };
                     let remote_id = body.get("id").and_then(|v| v.as_str())
-                        .ok_or(AppError::Network("Invalid response from server".to_string()))?;
+                        .ok_or(AppError::Server("Upload response is missing the job id".to_string()))?;
                     
                     self.db.finalize_upload(local_id, remote_id).await?;
+                    let _ = self.app_handle.emit(UPLOAD_PROGRESS_EVENT, UploadProgress::new(local_id, file_len, file_len));
                     
                     // Prune local file if not kept offline
                     if !recording.keep_offline {
@@ -224,16 +821,421 @@ impl SyncService {
                     }
                 } else {
                     self.db.update_sync_status(local_id, SyncStatus::Failed).await?;
-                    return Err(AppError::Network(format!("Upload failed: {}", resp.status())));
+                    if http::is_html(&resp) {
+                        return Err(http::unexpected_body(resp).await);
+                    }
+                    return Err(AppError::http(resp.status(), "Upload failed"));
                 }
             },
             Err(e) => {
                 self.db.update_sync_status(local_id, SyncStatus::Failed).await?;
-                return Err(AppError::Network(e.to_string()));
+                return Err(e.into());
             }
         }
 
         Ok(self.db.get_recording(local_id).await?)
         
     }
+
+    /// Deletes a job on the server. A 404 counts as success: it's already gone.
+    pub async fn delete_remote(&self, remote_job_id: &str) -> Result<(), AppError> {
+        Self::delete_remote_job(&self.client, remote_job_id).await
+    }
+
+    async fn delete_remote_job(client: &reqwest::Client, remote_job_id: &str) -> Result<(), AppError> {
+        let settings = StorageService::load_settings(None)?;
+        if settings.scriberr_url.is_empty() || settings.api_key.is_empty() {
+            return Err(AppError::Validation("Settings not configured".to_string()));
+        }
+
+        let url = format!("{}/api/v1/transcription/{}", settings.scriberr_url.trim_end_matches('/'), remote_job_id);
+        let resp = client.delete(&url)
+            .header("X-API-Key", &settings.api_key)
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if status.is_success() || status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        if http::is_html(&resp) {
+            return Err(http::unexpected_body(resp).await);
+        }
+        Err(AppError::http(status, "Remote delete failed"))
+    }
+
+    /// Permanently deletes recordings trashed more than `days` ago, along with
+    /// their local files and server jobs. Returns how many were removed.
+    pub async fn purge_trashed_older_than(&self, days: u32) -> Result<usize, AppError> {
+        Self::purge_trashed(&self.db, &self.app_handle, &self.client, days).await
+    }
+
+    /// A recording whose server job can't be deleted stays in the trash for
+    /// the next pass; otherwise the next sync would bring it back.
+    async fn purge_trashed(db: &DatabaseService, app: &AppHandle<R>, client: &reqwest::Client, days: u32) -> Result<usize, AppError> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
+        let mut purged = 0;
+
+        for recording in db.get_trashed_recordings().await? {
+            let expired = recording.deleted_at.as_deref()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| t < cutoff);
+            if !expired {
+                continue;
+            }
+
+            if let Some(remote_job_id) = recording.remote_job_id.as_deref() {
+                if let Err(e) = Self::delete_remote_job(client, remote_job_id).await {
+                    eprintln!("Failed to delete trashed job {} on server: {:?}", remote_job_id, e);
+                    continue;
+                }
+            }
+            for path in [&recording.local_file_path, &recording.local_audio_path].into_iter().flatten() {
+                let _ = tokio::fs::remove_file(path).await;
+            }
+            db.delete_recording(&recording.local_id).await?;
+            LibraryEvent::Deleted { local_id: Some(recording.local_id), remote_job_id: recording.remote_job_id }.emit(app);
+            purged += 1;
+        }
+        Ok(purged)
+    }
+
+    /// Renames a recording locally and, once uploaded, on the server too. If
+    /// the server can't be updated the local title stays and `title_dirty` is
+    /// set, so the next sync pushes it again.
+    pub async fn rename_recording(&self, local_id: &str, title: &str) -> Result<CachedRecording, AppError> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(AppError::Validation("Title cannot be empty".to_string()));
+        }
+
+        let recording = self.db.get_recording(local_id).await?;
+        self.db.update_title(local_id, title).await?;
+
+        if let Some(remote_job_id) = recording.remote_job_id.as_deref() {
+            let pushed = match StorageService::load_settings(None) {
+                Ok(settings) => Self::patch_title(&self.client, &settings, remote_job_id, title).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = &pushed {
+                eprintln!("Rename of {} not pushed to server, will retry on sync: {:?}", local_id, e);
+            }
+            self.db.set_title_dirty(local_id, pushed.is_err()).await?;
+        }
+
+        let updated = self.db.get_recording(local_id).await?;
+        LibraryEvent::Updated(updated.clone()).emit(&self.app_handle);
+        Ok(updated)
+    }
+
+    /// Retries renames that couldn't reach the server when they were made.
+    async fn push_dirty_titles(db: &DatabaseService, app: &AppHandle<R>, client: &reqwest::Client, settings: &crate::services::storage::Settings) -> Result<(), AppError> {
+        for recording in db.get_title_dirty_recordings().await? {
+            let Some(remote_job_id) = recording.remote_job_id.as_deref() else {
+                continue;
+            };
+            if let Err(e) = Self::patch_title(client, settings, remote_job_id, &recording.title).await {
+                eprintln!("Rename of {} still not pushed: {:?}", recording.local_id, e);
+                continue;
+            }
+            db.set_title_dirty(&recording.local_id, false).await?;
+            LibraryEvent::Updated(db.get_recording(&recording.local_id).await?).emit(app);
+        }
+        Ok(())
+    }
+
+    async fn patch_title(client: &reqwest::Client, settings: &crate::services::storage::Settings, remote_job_id: &str, title: &str) -> Result<(), AppError> {
+        if settings.scriberr_url.is_empty() || settings.api_key.is_empty() {
+            return Err(AppError::Validation("Settings not configured".to_string()));
+        }
+
+        let url = format!("{}/api/v1/transcription/{}", settings.scriberr_url.trim_end_matches('/'), remote_job_id);
+        let resp = client.patch(&url)
+            .header("X-API-Key", &settings.api_key)
+            .json(&serde_json::json!({ "title": title }))
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        if http::is_html(&resp) {
+            return Err(http::unexpected_body(resp).await);
+        }
+        Err(AppError::http(status, "Remote rename failed"))
+    }
+
+    /// Downloads the server's audio for a synced recording into `folder` and
+    /// records it as the local copy. The extension follows the audio's actual
+    /// format (magic bytes, then Content-Type), not an assumed `.wav`.
+    pub async fn download_recording(&self, local_id: &str, folder: &std::path::Path) -> Result<CachedRecording, AppError> {
+        self.begin_transfer(local_id, TransferKind::Download);
+        Self::emit_queue(&self.db, &self.app_handle, &self.transfers).await;
+
+        let result = self.download_recording_inner(local_id, folder).await;
+        self.end_transfer(local_id, TransferKind::Download);
+        Self::emit_queue(&self.db, &self.app_handle, &self.transfers).await;
+        result
+    }
+
+    async fn download_recording_inner(&self, local_id: &str, folder: &std::path::Path) -> Result<CachedRecording, AppError> {
+        let settings = StorageService::load_settings(None)?;
+        if settings.scriberr_url.is_empty() {
+            return Err(AppError::Validation("Settings not configured".to_string()));
+        }
+
+        let recording = self.db.get_recording(local_id).await
+            .map_err(|_| AppError::NotFound("Recording not found".to_string()))?;
+
+        // Check if valid URL exists
+        let url = recording.remote_audio_url.clone().ok_or(AppError::Validation("No remote audio URL available".to_string()))?;
+
+        // The extension is only known once the audio arrives. Named by id, as
+        // titles aren't unique and another recording's partial file must not
+        // be resumed onto this one
+        let stem = paths::sanitize_filename(&recording.title);
+        let part_path = folder.join(format!("{}.part", local_id));
+
+        // Download into a .part file, resuming from its size after transient failures
+        let mut attempt = 0;
+        let content_type = loop {
+            attempt += 1;
+            let error = match self.download_to_part(local_id, &url, &settings.api_key, &part_path).await {
+                Ok(content_type) => break content_type,
+                Err(DownloadFailure::Fatal(e)) => return Err(e),
+                Err(DownloadFailure::Transient(e)) => e,
+            };
+
+            if attempt >= DOWNLOAD_ATTEMPTS {
+                return Err(error);
+            }
+
+            let delay = SYNC_RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+            eprintln!("Download of {} failed (attempt {}), retrying in {:?}: {:?}", local_id, attempt, delay, error);
+            tokio::time::sleep(delay).await;
+        };
+
+        // A download of audio we recorded must be byte-for-byte what we uploaded
+        if let Some(expected) = recording.file_hash.clone() {
+            let path = part_path.clone();
+            let actual = tokio::task::spawn_blocking(move || integrity::hash_file(&path)).await
+                .map_err(|e| AppError::Unexpected(e.to_string()))??;
+            if !actual.eq_ignore_ascii_case(&expected) {
+                let _ = tokio::fs::remove_file(&part_path).await;
+                return Err(AppError::Logic(format!("Downloaded audio for {} doesn't match its hash; the file is corrupt or incomplete", recording.title)));
+            }
+        }
+
+        // Without a hash to compare, at least make sure it's a complete audio file
+        if !integrity::has_valid_audio_header(&part_path)? {
+            let _ = tokio::fs::remove_file(&part_path).await;
+            return Err(AppError::Logic(format!("Downloaded audio for {} isn't a readable audio file; try downloading it again", recording.title)));
+        }
+
+        let head = integrity::read_head(&part_path, 64)?;
+        let extension = integrity::audio_extension(content_type.as_deref(), &head);
+        let final_path = paths::unique_path(folder, &stem, extension);
+        tokio::fs::rename(&part_path, &final_path).await?;
+
+        // set_local_audio_path also flips keep_offline
+        self.db.set_local_audio_path(local_id, Some(final_path.to_string_lossy().to_string())).await?;
+
+        let updated = self.db.get_recording(local_id).await?;
+        LibraryEvent::Updated(updated.clone()).emit(&self.app_handle);
+        Ok(updated)
+    }
+
+    /// One download attempt. Sends a Range request when a partial file exists and
+    /// appends if the server honours it, otherwise starts the file over.
+    /// Returns the response's content type. Emits `download-progress` as it goes.
+    async fn download_to_part(&self, local_id: &str, url: &str, api_key: &str, part_path: &std::path::Path) -> Result<Option<String>, DownloadFailure> {
+        let resume_from = tokio::fs::metadata(part_path).await.map(|m| m.len()).unwrap_or(0);
+
+        let mut request = self.client.get(url)
+            .header("X-API-Key", api_key)
+            .timeout(http::TRANSFER_TIMEOUT);
+        if resume_from > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+        }
+
+        let mut resp = request.send().await
+            .map_err(|e| DownloadFailure::Transient(e.into()))?;
+        let status = resp.status();
+
+        // The .part file is already complete (or stale); start over next attempt
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            let _ = tokio::fs::remove_file(part_path).await;
+            return Err(DownloadFailure::Transient(AppError::http(status, "Download failed")));
+        }
+
+        // Reverse proxy error pages (and login pages served as 200) come back as HTML
+        if http::is_html(&resp) {
+            return Err(DownloadFailure::Fatal(http::unexpected_body(resp).await));
+        }
+
+        if !status.is_success() {
+            let error = AppError::http(status, "Download failed");
+            return Err(if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                DownloadFailure::Transient(error)
+            } else {
+                DownloadFailure::Fatal(error)
+            });
+        }
+
+        let content_type = resp.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let append = status == reqwest::StatusCode::PARTIAL_CONTENT;
+        if append {
+            // Only bytes starting where the .part file ends can be appended
+            let range_start = resp.headers()
+                .get(reqwest::header::CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(audio_cache::parse_content_range)
+                .map(|(start, _, _)| start);
+            if range_start != Some(resume_from) {
+                let _ = tokio::fs::remove_file(part_path).await;
+                return Err(DownloadFailure::Transient(AppError::Server(format!(
+                    "Server resumed the download at {:?} instead of byte {}", range_start, resume_from
+                ))));
+            }
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(part_path)
+            .await
+            .map_err(|e| DownloadFailure::Fatal(e.into()))?;
+
+        let mut received = if append { resume_from } else { 0 };
+        let total = resp.content_length().map(|len| received + len);
+        let mut last_report = std::time::Instant::now();
+        let _ = self.app_handle.emit(DOWNLOAD_PROGRESS_EVENT, DownloadProgress::new(local_id, received, total));
+
+        loop {
+            match resp.chunk().await {
+                Ok(Some(chunk)) => {
+                    file.write_all(&chunk).await.map_err(|e| DownloadFailure::Fatal(e.into()))?;
+                    received += chunk.len() as u64;
+                    if last_report.elapsed() >= PROGRESS_EVENT_INTERVAL {
+                        last_report = std::time::Instant::now();
+                        let _ = self.app_handle.emit(DOWNLOAD_PROGRESS_EVENT, DownloadProgress::new(local_id, received, total));
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = file.flush().await;
+                    return Err(DownloadFailure::Transient(e.into()));
+                }
+            }
+        }
+
+        file.flush().await.map_err(|e| DownloadFailure::Fatal(e.into()))?;
+
+        // Short reads resume from the .part file; a body longer than announced can't be trusted
+        if let Some(total) = total.filter(|&total| total != received) {
+            if received < total {
+                return Err(DownloadFailure::Transient(AppError::Server(format!("Download ended after {} of {} bytes", received, total))));
+            }
+            let _ = tokio::fs::remove_file(part_path).await;
+            return Err(DownloadFailure::Fatal(AppError::Server(format!("Download sent {} bytes but announced {}", received, total))));
+        }
+        let _ = self.app_handle.emit(DOWNLOAD_PROGRESS_EVENT, DownloadProgress::new(local_id, received, Some(received)));
+        Ok(content_type)
+    }
+}
+
+/// Outcome of a failed download attempt: transient failures are retried.
+enum DownloadFailure {
+    Transient(AppError),
+    Fatal(AppError),
+}
+
+const DOWNLOAD_ATTEMPTS: u32 = 4;
+const SYNC_FETCH_ATTEMPTS: u32 = 4;
+const SYNC_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+const SUMMARY_TITLE_WORDS: usize = 6;
+const TRANSCRIPT_TITLE_WORDS: usize = 8;
+
+/// Matches titles we (or the server) generate: `recording_YYYY-MM-DD_HH-MM-SS[.wav]`
+/// from `toggle_recording`, and the "Untitled" sync fallback.
+fn is_generated_title(title: &str) -> bool {
+    let title = title.trim();
+    if title.is_empty() || title == "Untitled" {
+        return true;
+    }
+
+    let stem = title.strip_suffix(".wav").unwrap_or(title);
+    let Some(stamp) = stem.strip_prefix("recording_") else {
+        return false;
+    };
+
+    // YYYY-MM-DD_HH-MM-SS
+    stamp.len() == 19
+        && stamp.chars().enumerate().all(|(i, c)| match i {
+            4 | 7 | 13 | 16 => c == '-',
+            10 => c == '_',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+fn title_from_content(summary: Option<&str>, transcript: Option<&str>) -> Option<String> {
+    let from_summary = summary
+        .and_then(|s| first_words(s, SUMMARY_TITLE_WORDS));
+    if from_summary.is_some() {
+        return from_summary;
+    }
+
+    let transcript = transcript?;
+    let first_sentence = transcript
+        .split(|c| c == '.' || c == '!' || c == '?' || c == '\n')
+        .map(str::trim)
+        .find(|s| s.split_whitespace().count() >= 2)?;
+    first_words(first_sentence, TRANSCRIPT_TITLE_WORDS)
+}
+
+fn first_words(text: &str, count: usize) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().take(count).collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let title = words.join(" ");
+    let title = title.trim_end_matches(|c: char| c.is_ascii_punctuation());
+    if title.is_empty() {
+        return None;
+    }
+
+    let mut chars = title.chars();
+    let first = chars.next()?;
+    Some(first.to_uppercase().chain(chars).collect())
+}
+
+/// Whether timestamp `a` is after `b`, comparing as RFC 3339 when both parse.
+fn is_later(a: &str, b: &str) -> bool {
+    match (chrono::DateTime::parse_from_rfc3339(a), chrono::DateTime::parse_from_rfc3339(b)) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => a > b,
+    }
+}
+
+fn same_instant(a: &str, b: &str) -> bool {
+    !is_later(a, b) && !is_later(b, a)
+}
+
+/// `updated_after` value that still matches jobs updated at `timestamp`: the
+/// server's filter is strict, so step back a second. Unparseable timestamps
+/// are sent as they are.
+fn just_before(timestamp: &str) -> String {
+    match chrono::DateTime::parse_from_rfc3339(timestamp) {
+        Ok(t) => (t - chrono::Duration::seconds(1))
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+        Err(_) => timestamp.to_string(),
+    }
 }
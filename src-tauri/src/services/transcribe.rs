@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::error::AppError;
+use crate::services::storage::Settings;
+
+/// Turns an audio file into plain transcript text without the server.
+pub trait Transcriber: Send + Sync {
+    /// Blocks until the transcript is ready; run it off the async runtime.
+    fn transcribe(&self, audio: &Path) -> Result<String, AppError>;
+}
+
+/// Runs a local whisper.cpp CLI (`whisper-cli`, or `main` in older builds).
+/// It decodes WAV, MP3 and FLAC and resamples to 16 kHz itself.
+pub struct WhisperCpp {
+    binary: PathBuf,
+    model: PathBuf,
+}
+
+impl WhisperCpp {
+    pub fn new(binary: PathBuf, model: PathBuf) -> Self {
+        Self { binary, model }
+    }
+
+    /// The binary and model configured in settings, both of which must exist.
+    pub fn from_settings(settings: &Settings) -> Result<Self, AppError> {
+        let configured = |path: &Option<String>, what: &str| {
+            let path = path.as_deref()
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+                .ok_or(AppError::Validation(format!("No whisper.cpp {} configured", what)))?;
+            if !path.exists() {
+                return Err(AppError::NotFound(format!("whisper.cpp {} not found at {}", what, path.display())));
+            }
+            Ok(path)
+        };
+        Ok(Self::new(
+            configured(&settings.whisper_binary_path, "binary")?,
+            configured(&settings.whisper_model_path, "model")?,
+        ))
+    }
+}
+
+impl Transcriber for WhisperCpp {
+    fn transcribe(&self, audio: &Path) -> Result<String, AppError> {
+        // -nt drops the timestamps, -np the progress and system info
+        let output = Command::new(&self.binary)
+            .arg("-m").arg(&self.model)
+            .arg("-f").arg(audio)
+            .args(["-nt", "-np"])
+            .output()
+            .map_err(|e| AppError::Unexpected(format!("Failed to run whisper.cpp: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let detail = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("no output");
+            return Err(AppError::Unexpected(format!("whisper.cpp exited with {}: {}", output.status, detail.trim())));
+        }
+
+        Ok(join_lines(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// whisper.cpp prints one line per segment; the transcript is them joined up.
+fn join_lines(stdout: &str) -> String {
+    stdout.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_lines_are_joined_into_one_transcript() {
+        let stdout = "\n Hello there.\n\n How are you?  \n";
+        assert_eq!(join_lines(stdout), "Hello there. How are you?");
+    }
+
+    #[test]
+    fn unconfigured_whisper_is_a_validation_error() {
+        let settings = Settings { whisper_binary_path: Some("  ".to_string()), ..Settings::default() };
+        assert!(matches!(WhisperCpp::from_settings(&settings), Err(AppError::Validation(_))));
+
+        let settings = Settings {
+            whisper_binary_path: Some("/nonexistent/whisper-cli".to_string()),
+            whisper_model_path: Some("/nonexistent/ggml-base.bin".to_string()),
+            ..Settings::default()
+        };
+        assert!(matches!(WhisperCpp::from_settings(&settings), Err(AppError::NotFound(_))));
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use specta::Type;
+use crate::services::db::{CachedRecording, CachedSpeakerMap};
+
+/// Colors handed out to speakers in order of first appearance.
+pub const SPEAKER_PALETTE: [&str; 10] = [
+    "#3B82F6", "#EF4444", "#10B981", "#F59E0B", "#8B5CF6",
+    "#EC4899", "#14B8A6", "#F97316", "#6366F1", "#84CC16",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct TranscriptSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    pub speaker: Option<String>,
+}
+
+/// A segment with its speaker resolved through the recording's speaker map.
+#[derive(Debug, Clone, Serialize, Type)]
+pub struct LabeledSegment {
+    #[serde(flatten)]
+    pub segment: TranscriptSegment,
+    pub display_name: Option<String>,
+    pub color: Option<String>,
+}
+
+/// Palette color for the `index`-th speaker of a recording.
+pub fn palette_color(index: usize) -> &'static str {
+    SPEAKER_PALETTE[index % SPEAKER_PALETTE.len()]
+}
+
+/// Splits a transcript as the server sends it into plain text and, when it
+/// has timing, a JSON array of segments. Accepts plain text, `{ "text": ...,
+/// "segments": [...] }`, a bare array of segments, and either wrapped in
+/// `{ "result": ... }`. JSON of any other shape is kept as text.
+pub fn parse_transcript(raw: &str) -> (Option<String>, Option<String>) {
+    let start = raw.trim_start();
+    if !start.starts_with('{') && !start.starts_with('[') {
+        return (Some(raw.to_string()), None);
+    }
+    serde_json::from_str::<Value>(raw).ok()
+        .and_then(|value| transcript_parts(&value))
+        .unwrap_or_else(|| (Some(raw.to_string()), None))
+}
+
+fn transcript_parts(value: &Value) -> Option<(Option<String>, Option<String>)> {
+    match value {
+        Value::Array(items) if items.iter().all(|item| item.get("text").is_some()) => {
+            if items.is_empty() {
+                return Some((None, None));
+            }
+            Some((Some(segment_text(items)), Some(value.to_string())))
+        }
+        Value::Object(fields) => {
+            if let Some(result) = fields.get("result") {
+                return transcript_parts(result);
+            }
+            let segments = fields.get("segments").and_then(|s| s.as_array()).filter(|s| !s.is_empty());
+            let text = fields.get("text").and_then(|t| t.as_str()).map(str::to_string)
+                .or_else(|| segments.map(|s| segment_text(s)));
+            if text.is_none() && segments.is_none() {
+                return None;
+            }
+            Some((text, segments.map(|s| Value::Array(s.clone()).to_string())))
+        }
+        _ => None,
+    }
+}
+
+/// The segments' text joined up, for transcripts that come without it.
+fn segment_text(segments: &[Value]) -> String {
+    segments.iter()
+        .filter_map(|s| s.get("text").and_then(|t| t.as_str()))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Extracts timed segments the same way the transcript view does: a JSON
+/// transcript with a `segments` array wins, otherwise the per-track JSON.
+pub fn parse_segments(recording: &CachedRecording) -> Vec<TranscriptSegment> {
+    if let Some(text) = recording.transcript_text.as_deref() {
+        if text.trim_start().starts_with('{') {
+            if let Ok(parsed) = serde_json::from_str::<Value>(text) {
+                if let Some(segments) = parsed.get("segments") {
+                    let segments: Vec<TranscriptSegment> = serde_json::from_value(segments.clone()).unwrap_or_default();
+                    if !segments.is_empty() {
+                        return segments;
+                    }
+                }
+            }
+        }
+    }
+
+    recording.individual_transcripts_json.as_deref()
+        .and_then(|json| serde_json::from_str::<Vec<TranscriptSegment>>(json).ok())
+        .unwrap_or_default()
+}
+
+/// The plain transcript text, unwrapping `{ "text": ... }` JSON transcripts.
+pub fn plain_text(recording: &CachedRecording) -> Option<String> {
+    let text = recording.transcript_text.as_deref()?;
+    if text.trim_start().starts_with('{') {
+        if let Some(inner) = serde_json::from_str::<Value>(text).ok()
+            .and_then(|v| v.get("text").and_then(|t| t.as_str()).map(str::to_string))
+        {
+            return Some(inner);
+        }
+    }
+    Some(text.to_string())
+}
+
+/// Fallback for transcripts without timing: the whole text as one segment
+/// spanning the recording, so segment-based rendering works uniformly.
+pub fn single_segment(text: &str, duration_sec: f64) -> Vec<TranscriptSegment> {
+    vec![TranscriptSegment {
+        start: 0.0,
+        end: duration_sec.max(0.0),
+        text: text.trim().to_string(),
+        speaker: None,
+    }]
+}
+
+/// Speaker labels in order of first appearance.
+pub fn speaker_labels(segments: &[TranscriptSegment]) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for speaker in segments.iter().filter_map(|s| s.speaker.as_ref()) {
+        if !labels.contains(speaker) {
+            labels.push(speaker.clone());
+        }
+    }
+    labels
+}
+
+pub fn label_segments(segments: Vec<TranscriptSegment>, maps: &[CachedSpeakerMap]) -> Vec<LabeledSegment> {
+    segments.into_iter().map(|segment| {
+        let map = segment.speaker.as_ref()
+            .and_then(|speaker| maps.iter().find(|m| &m.original_speaker_label == speaker));
+        LabeledSegment {
+            display_name: map.map(|m| m.display_name.clone()),
+            color: map.and_then(|m| m.color.clone()),
+            segment,
+        }
+    }).collect()
+}
+
+/// Segments spoken by `speaker`, matched against either the original label
+/// (`SPEAKER_00`) or the display name the user gave it, ignoring case.
+pub fn segments_by_speaker(segments: Vec<LabeledSegment>, speaker: &str) -> Vec<LabeledSegment> {
+    let wanted = speaker.trim();
+    segments.into_iter().filter(|s| {
+        let label = s.segment.speaker.as_deref().is_some_and(|l| l.eq_ignore_ascii_case(wanted));
+        let name = s.display_name.as_deref().is_some_and(|n| n.trim().eq_ignore_ascii_case(wanted));
+        label || name
+    }).collect()
+}
+
+/// Accepts `#RGB` and `#RRGGBB` hex colors.
+pub fn is_valid_color(color: &str) -> bool {
+    let Some(hex) = color.strip_prefix('#') else {
+        return false;
+    };
+    (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Trims `text` to at most `max_chars`, backing up to the last word boundary
+/// and marking the cut with an ellipsis.
+pub fn preview(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let cut: String = text.chars().take(max_chars).collect();
+    let trimmed = match cut.rfind(char::is_whitespace) {
+        Some(idx) if idx > 0 => &cut[..idx],
+        _ => cut.as_str(),
+    };
+    format!("{}…", trimmed.trim_end_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEGMENTS: &str = r#"[{"start":0.0,"end":1.5,"text":" Hello","speaker":"SPEAKER_00"},{"start":1.5,"end":3.0,"text":"there.","speaker":null}]"#;
+
+    fn segment_count(json: &str) -> usize {
+        serde_json::from_str::<Vec<TranscriptSegment>>(json).unwrap().len()
+    }
+
+    #[test]
+    fn plain_text_is_kept_as_is() {
+        assert_eq!(parse_transcript("Hello there."), (Some("Hello there.".to_string()), None));
+    }
+
+    #[test]
+    fn object_with_text_and_segments_is_split() {
+        let raw = format!(r#"{{"text":"Hello there.","segments":{}}}"#, SEGMENTS);
+        let (text, segments) = parse_transcript(&raw);
+        assert_eq!(text.as_deref(), Some("Hello there."));
+        assert_eq!(segment_count(&segments.unwrap()), 2);
+    }
+
+    #[test]
+    fn array_of_segments_gets_its_text_joined() {
+        let (text, segments) = parse_transcript(SEGMENTS);
+        assert_eq!(text.as_deref(), Some("Hello there."));
+        assert_eq!(segment_count(&segments.unwrap()), 2);
+        assert_eq!(parse_transcript("[]"), (None, None));
+    }
+
+    #[test]
+    fn result_wrapper_is_unwrapped() {
+        let raw = format!(r#"{{"result":{{"segments":{}}}}}"#, SEGMENTS);
+        let (text, segments) = parse_transcript(&raw);
+        assert_eq!(text.as_deref(), Some("Hello there."));
+        assert_eq!(segment_count(&segments.unwrap()), 2);
+
+        let raw = format!(r#"{{"result":{}}}"#, SEGMENTS);
+        assert_eq!(parse_transcript(&raw).0.as_deref(), Some("Hello there."));
+    }
+
+    #[test]
+    fn unrecognized_json_stays_text() {
+        let raw = r#"{"language":"en"}"#;
+        assert_eq!(parse_transcript(raw), (Some(raw.to_string()), None));
+        assert_eq!(parse_transcript("{not json"), (Some("{not json".to_string()), None));
+    }
+}
@@ -0,0 +1,885 @@
+//! Drives `SyncService` against a mock Scriberr server.
+//!
+//! Settings and the sync cursor are read from `$HOME/.config/scriberr-companion`,
+//! so every test points `HOME` at its own temp dir and holds `ENV_LOCK` while
+//! it runs.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use scriberr_companion_lib::error::AppError;
+use scriberr_companion_lib::services::compat::{CompatService, ConnectionProblem};
+use scriberr_companion_lib::services::db::{DatabaseService, SyncStatus};
+use scriberr_companion_lib::services::keychain;
+use scriberr_companion_lib::services::storage::{Settings, StorageService};
+use scriberr_companion_lib::services::sync::{BatchUploadSummary, SyncService};
+use serde_json::{json, Value};
+use tauri::test::{mock_app, MockRuntime};
+use tokio::sync::{Mutex, MutexGuard};
+use wiremock::matchers::{body_string_contains, header, method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const API_KEY: &str = "test-key";
+
+static ENV_LOCK: Mutex<()> = Mutex::const_new(());
+
+struct Harness {
+    server: MockServer,
+    db: Arc<DatabaseService>,
+    sync: SyncService<MockRuntime>,
+    dir: PathBuf,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl Harness {
+    async fn new() -> Self {
+        let guard = ENV_LOCK.lock().await;
+
+        let dir = std::env::temp_dir().join(format!("scriberr-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::env::set_var("HOME", &dir);
+        keychain::use_memory_store();
+
+        let server = MockServer::start().await;
+        StorageService::save_settings(&Settings {
+            scriberr_url: server.uri(),
+            api_key: API_KEY.to_string(),
+            output_path: dir.to_string_lossy().to_string(),
+            ..Settings::default()
+        })
+        .unwrap();
+
+        let db = Arc::new(DatabaseService::new_in_memory().await.unwrap());
+        let app = mock_app();
+        let sync = SyncService::new(db.clone(), app.handle().clone()).with_client(reqwest::Client::new());
+
+        // Sync stops at the first empty page; anything not mounted is one
+        Mock::given(method("GET"))
+            .and(path("/api/v1/transcription/list"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jobs": [] })))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+
+        Self { server, db, sync, dir, _guard: guard }
+    }
+
+    /// Serves `jobs` from the list endpoint to requests for jobs updated after
+    /// `after`, or to the first request of a full sync when `None`.
+    async fn mount_list_page(&self, after: Option<&str>, jobs: Value) {
+        let mock = Mock::given(method("GET"))
+            .and(path("/api/v1/transcription/list"))
+            .and(query_param("sort_by", "updated_at"))
+            .and(query_param("sort_order", "asc"))
+            .and(header("X-API-Key", API_KEY));
+        let mock = match after {
+            Some(after) => mock.and(query_param("updated_after", after)),
+            None => mock.and(query_param_is_missing("updated_after")),
+        };
+        mock.respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jobs": jobs })))
+            .mount(&self.server)
+            .await;
+    }
+
+    async fn mount_job_and_models(&self, id: &str) {
+        Mock::given(method("GET"))
+            .and(path(format!("/api/v1/transcription/{}", id)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(job(id, "completed")))
+            .mount(&self.server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/transcription/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "models": ["base", "small"] })))
+            .mount(&self.server)
+            .await;
+    }
+
+    fn write_wav(&self, name: &str) -> PathBuf {
+        let path = self.dir.join(name);
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..1600 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        path
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn job(id: &str, status: &str) -> Value {
+    json!({
+        "id": id,
+        "title": format!("Meeting {}", id),
+        "status": status,
+        "created_at": "2024-12-01T10:00:00Z",
+        "updated_at": "2024-12-01T11:00:00Z",
+        "transcript": "Hello there.",
+        "summary": null,
+        "individual_transcripts": null
+    })
+}
+
+fn jobs(prefix: &str, count: usize) -> Value {
+    Value::Array((0..count).map(|i| job(&format!("{}-{}", prefix, i), "completed")).collect())
+}
+
+#[tokio::test]
+async fn api_key_is_kept_out_of_the_settings_file() {
+    let h = Harness::new().await;
+    let settings_path = h.dir.join(".config/scriberr-companion/settings.json");
+    assert!(!std::fs::read_to_string(&settings_path).unwrap().contains(API_KEY));
+    assert_eq!(StorageService::load_settings(None).unwrap().api_key, API_KEY);
+
+    // A plaintext key written by an older version moves on first load
+    let mut legacy: Value = serde_json::from_str(&std::fs::read_to_string(&settings_path).unwrap()).unwrap();
+    legacy["api_key"] = json!("legacy-key");
+    legacy["api_key_ref"] = Value::Null;
+    std::fs::write(&settings_path, legacy.to_string()).unwrap();
+
+    assert_eq!(StorageService::load_settings(None).unwrap().api_key, "legacy-key");
+    assert!(!std::fs::read_to_string(&settings_path).unwrap().contains("legacy-key"));
+    assert_eq!(StorageService::load_settings(None).unwrap().api_key, "legacy-key");
+}
+
+#[tokio::test]
+async fn replaced_or_cleared_api_keys_are_removed_from_the_keychain() {
+    let h = Harness::new().await;
+    let mut settings = StorageService::load_settings(None).unwrap();
+    let old_account = keychain::api_key_account(&h.server.uri());
+    assert_eq!(keychain::get(&old_account).unwrap().as_deref(), Some(API_KEY));
+
+    // A new server URL files the key under a new account
+    settings.scriberr_url = "https://other.example.com".to_string();
+    StorageService::save_settings(&settings).unwrap();
+    let new_account = keychain::api_key_account("https://other.example.com");
+    assert_eq!(keychain::get(&old_account).unwrap(), None);
+    assert_eq!(keychain::get(&new_account).unwrap().as_deref(), Some(API_KEY));
+
+    settings.api_key.clear();
+    StorageService::save_settings(&settings).unwrap();
+    assert_eq!(keychain::get(&new_account).unwrap(), None);
+    assert_eq!(StorageService::load_settings(None).unwrap().api_key, "");
+}
+
+#[tokio::test]
+async fn diagnose_reports_models_version_and_auth_failures() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/models"))
+        .and(header("X-API-Key", API_KEY))
+        .respond_with(ResponseTemplate::new(200).insert_header("x-scriberr-version", "1.2.0").set_body_json(json!({ "models": ["base", "small"] })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/models"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+
+    let ok = CompatService::diagnose(&server.uri(), API_KEY).await.unwrap();
+    assert!(ok.reachable);
+    assert_eq!(ok.problem, None);
+    assert_eq!(ok.model_count, Some(2));
+    assert_eq!(ok.server_version.as_deref(), Some("1.2.0"));
+    assert!(ok.latency_ms.is_some());
+
+    let denied = CompatService::diagnose(&server.uri(), "wrong").await.unwrap();
+    assert_eq!(denied.problem, Some(ConnectionProblem::Unauthorized));
+    assert_eq!(denied.status, Some(401));
+
+    let dead = CompatService::diagnose("http://127.0.0.1:9", API_KEY).await.unwrap();
+    assert!(!dead.reachable);
+    assert_eq!(dead.problem, Some(ConnectionProblem::Unreachable));
+}
+
+#[tokio::test]
+async fn slow_responses_time_out_and_unreachable_servers_are_offline() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/models"))
+        .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(2)).set_body_json(json!({ "models": [] })))
+        .mount(&server)
+        .await;
+    let client = reqwest::Client::builder().timeout(std::time::Duration::from_millis(200)).build().unwrap();
+
+    let slow = CompatService::list_models(&client, &server.uri(), API_KEY).await;
+    assert!(matches!(slow, Err(AppError::Timeout)), "{:?}", slow);
+
+    let dead = CompatService::list_models(&client, "http://127.0.0.1:9", API_KEY).await;
+    assert!(matches!(dead, Err(AppError::Offline)), "{:?}", dead);
+}
+
+#[tokio::test]
+async fn full_sync_follows_updated_at_cursor() {
+    let h = Harness::new().await;
+    let with_updated_at = |prefix: &str, count: usize, hour: u32| -> Value {
+        Value::Array((0..count).map(|i| {
+            let mut job = job(&format!("{}-{}", prefix, i), "completed");
+            job["updated_at"] = json!(format!("2024-12-01T{:02}:00:{:02}Z", hour, i));
+            job
+        }).collect())
+    };
+    h.mount_list_page(None, with_updated_at("a", 50, 11)).await;
+    // Asked from just before the newest updated_at, as jobs sharing it may follow
+    h.mount_list_page(Some("2024-12-01T11:00:48Z"), with_updated_at("b", 3, 12)).await;
+
+    h.sync.perform_full_sync().await.unwrap();
+
+    let all = h.db.get_all_recordings().await.unwrap();
+    assert_eq!(all.len(), 53);
+    let rec = all.iter().find(|r| r.remote_job_id.as_deref() == Some("b-2")).unwrap();
+    assert_eq!(rec.title, "Meeting b-2");
+    assert_eq!(rec.transcript_text.as_deref(), Some("Hello there."));
+    assert_eq!(
+        rec.remote_audio_url.as_deref(),
+        Some(format!("{}/api/v1/transcription/b-2/audio", h.server.uri()).as_str())
+    );
+    assert!(StorageService::load_sync_cursor().unwrap().is_none());
+    // The server's newest updated_at, not the local clock, is the next baseline
+    let settings = StorageService::load_settings(None).unwrap();
+    assert_eq!(settings.last_sync_timestamp.as_deref(), Some("2024-12-01T12:00:02Z"));
+}
+
+#[tokio::test]
+async fn sync_removes_jobs_deleted_on_server() {
+    let h = Harness::new().await;
+    h.db.upsert_remote_recording("gone", "Old", "completed", "2024-12-01T10:00:00Z", None, None, None, None)
+        .await
+        .unwrap();
+
+    let mut deleted = job("gone", "completed");
+    deleted["deleted_at"] = json!("2024-12-02T10:00:00Z");
+    h.mount_list_page(None, json!([deleted, job("kept", "processing")])).await;
+
+    h.sync.perform_full_sync().await.unwrap();
+
+    let all = h.db.get_all_recordings().await.unwrap();
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].remote_job_id.as_deref(), Some("kept"));
+    assert_eq!(all[0].sync_status, SyncStatus::ProcessingRemote.to_string());
+}
+
+#[tokio::test]
+async fn sync_retries_transient_server_errors() {
+    let h = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&h.server)
+        .await;
+    h.mount_list_page(None, jobs("a", 2)).await;
+
+    h.sync.perform_full_sync().await.unwrap();
+
+    assert_eq!(h.db.get_all_recordings().await.unwrap().len(), 2);
+}
+
+/// Serves two jobs updated at 11:00 as the first page of a full sync, exactly
+/// once, and returns the `updated_after` the second page is requested with.
+async fn mount_first_page_once(h: &Harness) -> &'static str {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .and(query_param_is_missing("updated_after"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jobs": jobs("a", 2) })))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+    "2024-12-01T10:59:59Z"
+}
+
+fn second_page() -> Value {
+    let mut later = job("b-0", "completed");
+    later["updated_at"] = json!("2024-12-01T12:00:00Z");
+    json!([later])
+}
+
+#[tokio::test]
+async fn sync_retries_a_failed_page_without_refetching_earlier_ones() {
+    let h = Harness::new().await;
+    let after = mount_first_page_once(&h).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .and(query_param("updated_after", after))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&h.server)
+        .await;
+    h.mount_list_page(Some(after), second_page()).await;
+
+    h.sync.perform_full_sync().await.unwrap();
+
+    assert_eq!(h.db.get_all_recordings().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn interrupted_sync_resumes_from_the_saved_cursor() {
+    let h = Harness::new().await;
+    let after = mount_first_page_once(&h).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .and(query_param("updated_after", after))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(4)
+        .with_priority(1)
+        .mount(&h.server)
+        .await;
+
+    // Every retry of page 2 fails, so the sync gives up after page 1
+    assert!(h.sync.perform_full_sync().await.is_err());
+    let cursor = StorageService::load_sync_cursor().unwrap().unwrap();
+    assert_eq!(cursor.last_seen, "2024-12-01T11:00:00Z");
+    assert_eq!(cursor.last_seen_ids, ["a-0", "a-1"]);
+
+    h.mount_list_page(Some(after), second_page()).await;
+    h.sync.perform_full_sync().await.unwrap();
+
+    assert_eq!(h.db.get_all_recordings().await.unwrap().len(), 3);
+    assert!(StorageService::load_sync_cursor().unwrap().is_none());
+}
+
+#[tokio::test]
+async fn jobs_sharing_a_timestamp_across_a_page_boundary_are_all_synced() {
+    let h = Harness::new().await;
+    let at = |id: &str, updated_at: &str| {
+        let mut job = job(id, "completed");
+        job["updated_at"] = json!(updated_at);
+        job
+    };
+    let tied = |count: usize| Value::Array((0..count).map(|i| at(&format!("t-{}", i), "2024-12-01T11:00:50Z")).collect());
+
+    // The limit cuts the run of jobs updated at 11:00:50 after t-2
+    let mut first = (0..47).map(|i| at(&format!("a-{}", i), &format!("2024-12-01T11:00:{:02}Z", i))).collect::<Vec<_>>();
+    first.extend(tied(3).as_array().unwrap().iter().cloned());
+    h.mount_list_page(None, Value::Array(first)).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .and(query_param("updated_after", "2024-12-01T11:00:49Z"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jobs": tied(5) })))
+        .expect(2)
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+
+    assert_eq!(h.db.get_all_recordings().await.unwrap().len(), 52);
+    let settings = StorageService::load_settings(None).unwrap();
+    assert_eq!(settings.last_sync_timestamp.as_deref(), Some("2024-12-01T11:00:50Z"));
+    assert_eq!(settings.last_sync_ids.len(), 5);
+
+    // A job updated at the baseline instant after the last sync isn't missed
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .and(query_param("updated_after", "2024-12-01T11:00:49Z"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jobs": tied(6) })))
+        .with_priority(1)
+        .mount(&h.server)
+        .await;
+    h.sync.perform_delta_sync().await.unwrap();
+
+    let all = h.db.get_all_recordings().await.unwrap();
+    assert_eq!(all.len(), 53);
+    assert!(all.iter().any(|r| r.remote_job_id.as_deref() == Some("t-5")));
+}
+
+#[tokio::test]
+async fn jobs_without_updated_at_are_paged_through() {
+    let h = Harness::new().await;
+    let untimed = |prefix: &str, count: usize| {
+        let mut page = jobs(prefix, count);
+        for job in page.as_array_mut().unwrap() {
+            job["updated_at"] = Value::Null;
+        }
+        page
+    };
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .and(query_param_is_missing("page"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jobs": untimed("a", 50) })))
+        .mount(&h.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .and(query_param("page", "2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jobs": untimed("b", 3) })))
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+
+    assert_eq!(h.db.get_all_recordings().await.unwrap().len(), 53);
+}
+
+#[tokio::test]
+async fn sync_reports_html_error_pages() {
+    let h = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/html")
+                .set_body_string("<html><title>Sign in</title></html>"),
+        )
+        .mount(&h.server)
+        .await;
+
+    match h.sync.perform_full_sync().await {
+        Err(AppError::Server(msg)) => assert!(msg.contains("Sign in"), "{}", msg),
+        other => panic!("expected a server error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn sync_fails_on_unauthorized() {
+    let h = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/list"))
+        .respond_with(ResponseTemplate::new(401))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    assert!(matches!(h.sync.perform_full_sync().await, Err(AppError::Http { status: 401, .. })));
+}
+
+#[tokio::test]
+async fn upload_sends_multipart_and_records_job_id() {
+    let h = Harness::new().await;
+    h.mount_job_and_models("job-1").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/transcription/upload"))
+        .and(header("X-API-Key", API_KEY))
+        .and(body_string_contains("name=\"audio\""))
+        .and(body_string_contains("name=\"title\""))
+        .and(body_string_contains("Standup"))
+        .and(body_string_contains("name=\"sha256\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "job-1" })))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    let wav = h.write_wav("standup.wav");
+    let draft = h.db.create_draft("Standup".to_string(), 0.1, wav.to_string_lossy().to_string(), None).await.unwrap();
+
+    let uploaded = h.sync.upload_recording(&draft.local_id, None).await.unwrap();
+
+    assert_eq!(uploaded.remote_job_id.as_deref(), Some("job-1"));
+    assert_ne!(uploaded.sync_status, SyncStatus::Failed.to_string());
+}
+
+#[tokio::test]
+async fn upload_sends_chosen_model_and_rejects_unknown_ones() {
+    let h = Harness::new().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{ "id": "large-v3", "name": "Large v3" }, "base"]
+        })))
+        .mount(&h.server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/transcription/upload"))
+        .and(body_string_contains("name=\"model\""))
+        .and(body_string_contains("large-v3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "job-1" })))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    let models = CompatService::list_models(&reqwest::Client::new(), &h.server.uri(), API_KEY).await.unwrap();
+    assert_eq!(models.len(), 2);
+    assert_eq!(models[0].name.as_deref(), Some("Large v3"));
+    assert_eq!(models[1].id, "base");
+
+    let wav = h.write_wav("model.wav");
+    let draft = h.db.create_draft("Model".to_string(), 0.1, wav.to_string_lossy().to_string(), None).await.unwrap();
+
+    let result = h.sync.upload_recording(&draft.local_id, Some("tiny")).await;
+    assert!(matches!(result, Err(AppError::Validation(_))));
+    let untouched = h.db.get_recording(&draft.local_id).await.unwrap();
+    assert_ne!(untouched.sync_status, SyncStatus::Failed.to_string());
+
+    let uploaded = h.sync.upload_recording(&draft.local_id, Some("large-v3")).await.unwrap();
+    assert_eq!(uploaded.remote_job_id.as_deref(), Some("job-1"));
+}
+
+#[tokio::test]
+async fn upload_all_pending_uploads_drafts_and_fails_missing_files() {
+    let h = Harness::new().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/transcription/upload"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "job-1" })))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    let wav = h.write_wav("pending.wav");
+    let draft = h.db.create_draft("Pending".to_string(), 0.1, wav.to_string_lossy().to_string(), None).await.unwrap();
+    let gone = h.db.create_draft("Gone".to_string(), 0.1, h.dir.join("gone.wav").to_string_lossy().to_string(), None).await.unwrap();
+
+    let summary = h.sync.upload_all_pending().await.unwrap();
+
+    assert_eq!(summary, BatchUploadSummary { succeeded: 1, failed: 1 });
+    let uploaded = h.db.get_recording(&draft.local_id).await.unwrap();
+    assert_eq!(uploaded.remote_job_id.as_deref(), Some("job-1"));
+    let missing = h.db.get_recording(&gone.local_id).await.unwrap();
+    assert_eq!(missing.sync_status, SyncStatus::Failed.to_string());
+}
+
+#[tokio::test]
+async fn upload_failure_marks_recording_failed() {
+    let h = Harness::new().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/transcription/upload"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&h.server)
+        .await;
+
+    let wav = h.write_wav("broken.wav");
+    let draft = h.db.create_draft("Broken".to_string(), 0.1, wav.to_string_lossy().to_string(), None).await.unwrap();
+
+    assert!(h.sync.upload_recording(&draft.local_id, None).await.is_err());
+
+    let rec = h.db.get_recording(&draft.local_id).await.unwrap();
+    assert_eq!(rec.sync_status, SyncStatus::Failed.to_string());
+    assert!(rec.remote_job_id.is_none());
+    assert!(wav.exists());
+}
+
+#[tokio::test]
+async fn rename_patches_title_of_uploaded_recording() {
+    let h = Harness::new().await;
+    let (rec, _) = h.db.upsert_remote_recording("job-3", "Old", "completed", "2024-12-01T10:00:00Z", None, None, None, None)
+        .await
+        .unwrap();
+    Mock::given(method("PATCH"))
+        .and(path("/api/v1/transcription/job-3"))
+        .and(body_string_contains("Standup"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    let renamed = h.sync.rename_recording(&rec.local_id, "  Standup  ").await.unwrap();
+    assert_eq!(renamed.title, "Standup");
+    assert_eq!(renamed.sync_status, SyncStatus::CompletedSynced.to_string());
+
+    assert!(matches!(h.sync.rename_recording(&rec.local_id, "   ").await, Err(AppError::Validation(_))));
+}
+
+#[tokio::test]
+async fn auto_titles_stay_local_across_syncs() {
+    let h = Harness::new().await;
+    StorageService::update_settings(|settings| settings.auto_title = true).unwrap();
+    let mut placeholder = job("job-5", "completed");
+    placeholder["title"] = json!("recording_2024-12-01_10-00-00.wav");
+    h.mount_list_page(None, json!([placeholder])).await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/v1/transcription/job-5"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(0)
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+    // The server still has its placeholder; the derived title isn't replaced by it
+    h.sync.perform_full_sync().await.unwrap();
+
+    let all = h.db.get_all_recordings().await.unwrap();
+    let rec = all.iter().find(|r| r.remote_job_id.as_deref() == Some("job-5")).unwrap();
+    assert_eq!(rec.title, "Hello there");
+    assert!(rec.title_auto);
+    assert!(!rec.title_dirty);
+}
+
+#[tokio::test]
+async fn failed_remote_rename_is_retried_on_next_sync() {
+    let h = Harness::new().await;
+    let (rec, _) = h.db.upsert_remote_recording("job-4", "Old", "completed", "2024-12-01T10:00:00Z", None, None, None, None)
+        .await
+        .unwrap();
+    Mock::given(method("PATCH"))
+        .and(path("/api/v1/transcription/job-4"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&h.server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/api/v1/transcription/job-4"))
+        .and(body_string_contains("Retro"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    let renamed = h.sync.rename_recording(&rec.local_id, "Retro").await.unwrap();
+    assert_eq!(renamed.title, "Retro");
+    assert!(renamed.title_dirty);
+    assert_eq!(renamed.sync_status, SyncStatus::CompletedSynced.to_string());
+
+    // The server still has the old title; the pending rename wins, then is pushed
+    h.mount_list_page(None, json!([job("job-4", "completed")])).await;
+    h.sync.perform_full_sync().await.unwrap();
+
+    let rec = h.db.get_recording(&rec.local_id).await.unwrap();
+    assert_eq!(rec.title, "Retro");
+    assert!(!rec.title_dirty);
+}
+
+#[tokio::test]
+async fn purge_removes_expired_trash_with_files_and_server_job() {
+    let h = Harness::new().await;
+    let wav = h.write_wav("trashed.wav");
+    let draft = h.db.create_draft("Trashed".to_string(), 0.1, wav.to_string_lossy().to_string(), None).await.unwrap();
+    let (synced, _) = h.db.upsert_remote_recording("job-5", "Synced", "completed", "2024-12-01T10:00:00Z", None, None, None, None)
+        .await
+        .unwrap();
+    Mock::given(method("DELETE"))
+        .and(path("/api/v1/transcription/job-5"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    h.db.soft_delete_recording(&draft.local_id).await.unwrap();
+    h.db.soft_delete_recording(&synced.local_id).await.unwrap();
+    assert!(h.db.get_all_recordings().await.unwrap().is_empty());
+    assert_eq!(h.db.get_trashed_recordings().await.unwrap().len(), 2);
+
+    // Nothing has been in the trash for a day yet
+    assert_eq!(h.sync.purge_trashed_older_than(1).await.unwrap(), 0);
+
+    h.db.restore_recording(&draft.local_id).await.unwrap();
+    assert_eq!(h.db.get_all_recordings().await.unwrap().len(), 1);
+    h.db.soft_delete_recording(&draft.local_id).await.unwrap();
+
+    assert_eq!(h.sync.purge_trashed_older_than(0).await.unwrap(), 2);
+    assert!(h.db.get_trashed_recordings().await.unwrap().is_empty());
+    assert!(!wav.exists());
+}
+
+#[tokio::test]
+async fn upload_links_identical_audio_instead_of_resending() {
+    let h = Harness::new().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/transcription/upload"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "job-1" })))
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    let first = h.write_wav("first.wav");
+    let second = h.write_wav("second.wav");
+    let a = h.db.create_draft("First".to_string(), 0.1, first.to_string_lossy().to_string(), None).await.unwrap();
+    let b = h.db.create_draft("Second".to_string(), 0.1, second.to_string_lossy().to_string(), None).await.unwrap();
+
+    h.sync.upload_recording(&a.local_id, None).await.unwrap();
+    let linked = h.sync.upload_recording(&b.local_id, None).await.unwrap();
+
+    assert_eq!(linked.remote_job_id.as_deref(), Some("job-1"));
+    assert!(linked.file_hash.is_some());
+    assert_eq!(linked.sync_status, SyncStatus::ProcessingRemote.to_string());
+}
+
+#[tokio::test]
+async fn aborted_upload_returns_to_draft_ready() {
+    let h = Harness::new().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/transcription/upload"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "job-1" })).set_delay(std::time::Duration::from_secs(30)))
+        .mount(&h.server)
+        .await;
+
+    let wav = h.write_wav("long.wav");
+    let draft = h.db.create_draft("Long".to_string(), 0.1, wav.to_string_lossy().to_string(), None).await.unwrap();
+
+    let (result, aborted) = tokio::join!(h.sync.upload_recording(&draft.local_id, None), async {
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        h.sync.abort_upload(&draft.local_id)
+    });
+
+    assert!(aborted);
+    assert!(matches!(result, Err(AppError::Cancelled)));
+    let rec = h.db.get_recording(&draft.local_id).await.unwrap();
+    assert_eq!(rec.sync_status, SyncStatus::DraftReady.to_string());
+    assert!(!h.sync.abort_upload(&draft.local_id));
+}
+
+#[tokio::test]
+async fn download_writes_audio_and_sets_local_path() {
+    let h = Harness::new().await;
+    let audio = std::fs::read(h.write_wav("source.wav")).unwrap();
+    h.mount_list_page(None, json!([job("job-9", "completed")])).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-9/audio"))
+        .and(header("X-API-Key", API_KEY))
+        .respond_with(ResponseTemplate::new(200).insert_header("content-type", "audio/wav").set_body_bytes(audio.clone()))
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+    let rec = h.db.get_all_recordings().await.unwrap().remove(0);
+
+    let downloaded = h.sync.download_recording(&rec.local_id, &h.dir).await.unwrap();
+
+    let local = PathBuf::from(downloaded.local_audio_path.unwrap());
+    assert_eq!(std::fs::read(&local).unwrap(), audio);
+    assert!(downloaded.keep_offline);
+}
+
+#[tokio::test]
+async fn download_rejects_files_that_are_not_audio() {
+    let h = Harness::new().await;
+    h.mount_list_page(None, json!([job("job-11", "completed")])).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-11/audio"))
+        .respond_with(ResponseTemplate::new(200).insert_header("content-type", "audio/wav").set_body_bytes(b"RIFF\xff\xff\x00\x00WAVEfmt ".to_vec()))
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+    let rec = h.db.get_all_recordings().await.unwrap().remove(0);
+
+    let result = h.sync.download_recording(&rec.local_id, &h.dir).await;
+    assert!(matches!(result, Err(AppError::Logic(_))), "{:?}", result);
+    assert!(h.db.get_recording(&rec.local_id).await.unwrap().local_audio_path.is_none());
+    assert!(!h.dir.join(format!("{}.part", rec.local_id)).exists());
+}
+
+#[tokio::test]
+async fn download_rejects_audio_not_matching_the_uploaded_hash() {
+    let h = Harness::new().await;
+    h.mount_list_page(None, json!([job("job-10", "completed")])).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-10/audio"))
+        .respond_with(ResponseTemplate::new(200).insert_header("content-type", "audio/wav").set_body_bytes(b"RIFFtrunc".to_vec()))
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+    let rec = h.db.get_all_recordings().await.unwrap().remove(0);
+    h.db.set_file_hash(&rec.local_id, "00ff").await.unwrap();
+
+    let result = h.sync.download_recording(&rec.local_id, &h.dir).await;
+    assert!(matches!(result, Err(AppError::Logic(_))));
+    assert!(h.db.get_recording(&rec.local_id).await.unwrap().local_audio_path.is_none());
+}
+
+#[tokio::test]
+async fn download_resumes_from_part_file() {
+    let h = Harness::new().await;
+    let audio = std::fs::read(h.write_wav("source.wav")).unwrap();
+    h.mount_list_page(None, json!([job("job-8", "completed")])).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-8/audio"))
+        .and(header("Range", "bytes=4-"))
+        .respond_with(
+            ResponseTemplate::new(206)
+                .insert_header("content-type", "audio/wav")
+                .insert_header("content-range", format!("bytes 4-{}/{}", audio.len() - 1, audio.len()))
+                .set_body_bytes(audio[4..].to_vec()),
+        )
+        .expect(1)
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+    let rec = h.db.get_all_recordings().await.unwrap().remove(0);
+    let part = h.dir.join(format!("{}.part", rec.local_id));
+    std::fs::write(&part, &audio[..4]).unwrap();
+
+    let downloaded = h.sync.download_recording(&rec.local_id, &h.dir).await.unwrap();
+
+    assert_eq!(std::fs::read(downloaded.local_audio_path.unwrap()).unwrap(), audio);
+    assert!(!part.exists());
+}
+
+#[tokio::test]
+async fn download_restarts_when_the_server_resumes_at_the_wrong_offset() {
+    let h = Harness::new().await;
+    let audio = std::fs::read(h.write_wav("source.wav")).unwrap();
+    h.mount_list_page(None, json!([job("job-12", "completed")])).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-12/audio"))
+        .and(header("Range", "bytes=4-"))
+        .respond_with(
+            ResponseTemplate::new(206)
+                .insert_header("content-range", format!("bytes 0-{}/{}", audio.len() - 1, audio.len()))
+                .set_body_bytes(audio.clone()),
+        )
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&h.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-12/audio"))
+        .respond_with(ResponseTemplate::new(200).insert_header("content-type", "audio/wav").set_body_bytes(audio.clone()))
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+    let rec = h.db.get_all_recordings().await.unwrap().remove(0);
+    // Left over from another download; never appended to
+    std::fs::write(h.dir.join(format!("{}.part", rec.local_id)), b"junk").unwrap();
+
+    let downloaded = h.sync.download_recording(&rec.local_id, &h.dir).await.unwrap();
+
+    assert_eq!(std::fs::read(downloaded.local_audio_path.unwrap()).unwrap(), audio);
+}
+
+#[tokio::test]
+async fn download_names_file_after_detected_format() {
+    let h = Harness::new().await;
+    h.mount_list_page(None, json!([job("job-6", "completed")])).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-6/audio"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "application/octet-stream")
+                .set_body_bytes(b"ID3\x04\x00\x00\x00\x00\x00\x00rest".to_vec()),
+        )
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+    let rec = h.db.get_all_recordings().await.unwrap().remove(0);
+
+    let downloaded = h.sync.download_recording(&rec.local_id, &h.dir).await.unwrap();
+
+    assert_eq!(PathBuf::from(downloaded.local_audio_path.unwrap()), h.dir.join("Meeting job-6.mp3"));
+}
+
+#[tokio::test]
+async fn download_rejects_html_responses() {
+    let h = Harness::new().await;
+    h.mount_list_page(None, json!([job("job-7", "completed")])).await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-7/audio"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/html")
+                .set_body_string("<html><title>502 Bad Gateway</title></html>"),
+        )
+        .mount(&h.server)
+        .await;
+
+    h.sync.perform_full_sync().await.unwrap();
+    let rec = h.db.get_all_recordings().await.unwrap().remove(0);
+
+    assert!(matches!(h.sync.download_recording(&rec.local_id, &h.dir).await, Err(AppError::Server(_))));
+    let rec = h.db.get_recording(&rec.local_id).await.unwrap();
+    assert!(rec.local_audio_path.is_none());
+}
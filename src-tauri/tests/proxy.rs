@@ -0,0 +1,129 @@
+//! The audio proxy reads its settings per request, so reconfiguring the server
+//! must take effect without restarting it. Audio it has streamed once is
+//! cached on disk and served from there.
+
+use std::sync::Arc;
+
+use scriberr_companion_lib::services::audio_cache::AudioCache;
+use scriberr_companion_lib::services::proxy::ProxyService;
+use scriberr_companion_lib::services::storage::Settings;
+use tokio::sync::RwLock;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn audio_server(body: &'static [u8]) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-1/audio"))
+        .respond_with(ResponseTemplate::new(200).insert_header("content-type", "audio/wav").set_body_bytes(body.to_vec()))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn settings_for(server: &MockServer) -> Settings {
+    Settings {
+        scriberr_url: server.uri(),
+        api_key: "test-key".to_string(),
+        ..Settings::default()
+    }
+}
+
+#[tokio::test]
+async fn proxy_follows_settings_changes_without_restart() {
+    let old_server = audio_server(b"old").await;
+    let new_server = audio_server(b"new").await;
+
+    let settings = Arc::new(RwLock::new(settings_for(&old_server)));
+    let (_shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    let cache = Arc::new(AudioCache::new(std::env::temp_dir().join(format!("scriberr-proxy-{}", uuid::Uuid::new_v4()))));
+    let port = ProxyService::start(settings.clone(), cache.clone(), shutdown_rx).await.unwrap();
+    let url = ProxyService::stream_url(port, "job-1");
+
+    let first = reqwest::get(&url).await.unwrap().bytes().await.unwrap();
+    assert_eq!(&first[..], b"old");
+
+    // What save_settings_command does with the shared lock
+    *settings.write().await = settings_for(&new_server);
+
+    let second = reqwest::get(&url).await.unwrap().bytes().await.unwrap();
+    assert_eq!(&second[..], b"new");
+
+    let _ = cache.clear();
+}
+
+#[tokio::test]
+async fn proxy_serves_repeat_requests_from_cache() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-1/audio"))
+        .respond_with(ResponseTemplate::new(200).insert_header("content-type", "audio/wav").set_body_bytes(b"0123456789".to_vec()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let settings = Arc::new(RwLock::new(settings_for(&server)));
+    let cache = Arc::new(AudioCache::new(std::env::temp_dir().join(format!("scriberr-proxy-{}", uuid::Uuid::new_v4()))));
+    let (_shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    let port = ProxyService::start(settings, cache.clone(), shutdown_rx).await.unwrap();
+    let url = ProxyService::stream_url(port, "job-1");
+
+    let full = reqwest::get(&url).await.unwrap().bytes().await.unwrap();
+    assert_eq!(&full[..], b"0123456789");
+
+    // The entry is completed just after the body finishes
+    for _ in 0..50 {
+        if cache.get(&server.uri(), "job-1").is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    let partial = reqwest::Client::new().get(&url).header("Range", "bytes=2-5").send().await.unwrap();
+    assert_eq!(partial.status(), reqwest::StatusCode::PARTIAL_CONTENT);
+    assert_eq!(partial.headers()["content-range"], "bytes 2-5/10");
+    assert_eq!(&partial.bytes().await.unwrap()[..], b"2345");
+
+    assert_eq!(cache.clear().unwrap(), 10);
+}
+
+#[tokio::test]
+async fn proxy_serves_ranges_it_has_already_fetched() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/transcription/job-1/audio"))
+        .and(header("Range", "bytes=0-4"))
+        .respond_with(
+            ResponseTemplate::new(206)
+                .insert_header("content-type", "audio/wav")
+                .insert_header("content-range", "bytes 0-4/10")
+                .set_body_bytes(b"01234".to_vec()),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let settings = Arc::new(RwLock::new(settings_for(&server)));
+    let cache = Arc::new(AudioCache::new(std::env::temp_dir().join(format!("scriberr-proxy-{}", uuid::Uuid::new_v4()))));
+    let (_shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+    let port = ProxyService::start(settings, cache.clone(), shutdown_rx).await.unwrap();
+    let url = ProxyService::stream_url(port, "job-1");
+    let client = reqwest::Client::new();
+
+    let first = client.get(&url).header("Range", "bytes=0-4").send().await.unwrap();
+    assert_eq!(&first.bytes().await.unwrap()[..], b"01234");
+
+    for _ in 0..50 {
+        if cache.get_partial(&server.uri(), "job-1").is_some_and(|(_, c)| c.contains(0, 4)) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    let again = client.get(&url).header("Range", "bytes=1-3").send().await.unwrap();
+    assert_eq!(again.status(), reqwest::StatusCode::PARTIAL_CONTENT);
+    assert_eq!(again.headers()["content-range"], "bytes 1-3/10");
+    assert_eq!(&again.bytes().await.unwrap()[..], b"123");
+
+    let _ = cache.clear();
+}
@@ -23,7 +23,9 @@ import {
 import { useQueryClient } from "@tanstack/react-query";
 import { useVirtualizer } from "@tanstack/react-virtual";
 import { Tooltip } from "../../components/ui/Tooltip";
-import type { LedgerEntry } from "../../types";
+import type { BatchUploadSummary, LedgerEntry, LibraryEvent } from "../../types";
+import { toast } from "sonner";
+import { isTimeout, isUnauthorized } from "../../lib/errors";
 
 // Inline formatDuration if not exists
 const formatDuration = (seconds?: number) => {
@@ -55,6 +57,7 @@ export function RecordingList({ onSelect }: RecordingListProps) {
 	} | null>(null);
 	const [deleteId, setDeleteId] = useState<string | null>(null);
 	const [isSyncing, setIsSyncing] = useState(false);
+	const [isUploadingAll, setIsUploadingAll] = useState(false);
 	const queryClient = useQueryClient();
 
 	// Virtualizer setup
@@ -67,45 +70,47 @@ export function RecordingList({ onSelect }: RecordingListProps) {
 	});
 
 	useEffect(() => {
-		const unlistenPromise = listen<LedgerEntry>("recording-added", (event) => {
-			queryClient.setQueryData<LedgerEntry[]>(["recordings"], (old) => {
-				if (!old) return [event.payload];
-				if (old.some((r) => r.local_id === event.payload.local_id)) return old;
-				return [event.payload, ...old];
-			});
-		});
-
-		const unlistenUpdatedPromise = listen<LedgerEntry>(
-			"recording-updated",
-			(event) => {
-				queryClient.setQueryData<LedgerEntry[]>(["recordings"], (old) => {
-					if (!old) return old;
-					return old.map((rec) =>
-						rec.local_id === event.payload.local_id ? event.payload : rec,
-					);
-				});
-			},
-		);
-
-		const unlistenDeletedPromise = listen<string>(
-			"recording-deleted-remote",
-			(event) => {
-				queryClient.setQueryData<LedgerEntry[]>(["recordings"], (old) => {
-					if (!old) return old;
-					return old.filter((rec) => rec.remote_job_id !== event.payload);
-				});
-			},
-		);
-
-		const unlistenSyncPromise = listen<void>("sync-completed", () => {
-			refetch();
+		const unlistenPromise = listen<LibraryEvent>("library-event", (event) => {
+			const libraryEvent = event.payload;
+			switch (libraryEvent.kind) {
+				case "added":
+				case "updated":
+				case "stopped": {
+					const recording = libraryEvent.data;
+					queryClient.setQueryData<LedgerEntry[]>(["recordings"], (old) => {
+						if (!old) return [recording];
+						if (!old.some((r) => r.local_id === recording.local_id)) {
+							return [recording, ...old];
+						}
+						return old.map((rec) =>
+							rec.local_id === recording.local_id ? recording : rec,
+						);
+					});
+					break;
+				}
+				case "deleted": {
+					const { local_id, remote_job_id } = libraryEvent.data;
+					queryClient.setQueryData<LedgerEntry[]>(["recordings"], (old) => {
+						if (!old) return old;
+						return old.filter(
+							(rec) =>
+								!(local_id && rec.local_id === local_id) &&
+								!(remote_job_id && rec.remote_job_id === remote_job_id),
+						);
+					});
+					break;
+				}
+				case "sync_started":
+				case "sync_progress":
+					break;
+				case "sync_completed":
+					refetch();
+					break;
+			}
 		});
 
 		return () => {
 			unlistenPromise.then((unlisten) => unlisten());
-			unlistenUpdatedPromise.then((unlisten) => unlisten());
-			unlistenDeletedPromise.then((unlisten) => unlisten());
-			unlistenSyncPromise.then((unlisten) => unlisten());
 		};
 	}, [queryClient, refetch]);
 
@@ -161,8 +166,13 @@ export function RecordingList({ onSelect }: RecordingListProps) {
 			onSuccess: () => {
 				setUploadingId(null);
 			},
-			onError: () => {
+			onError: (error) => {
 				setUploadingId(null);
+				if (isUnauthorized(error)) {
+					toast.error("Scriberr rejected the API key. Update it in Settings.");
+				} else if (isTimeout(error)) {
+					toast.error("Scriberr took too long to respond. Try again later.");
+				}
 				refetch();
 			},
 		});
@@ -174,11 +184,44 @@ export function RecordingList({ onSelect }: RecordingListProps) {
 			await invoke("sync_now_command");
 		} catch (error) {
 			console.error("Sync failed:", error);
+			if (isUnauthorized(error)) {
+				toast.error("Scriberr rejected the API key. Update it in Settings.");
+			} else if (isTimeout(error)) {
+				toast.error("Scriberr took too long to respond. Try again later.");
+			}
 		} finally {
 			setIsSyncing(false);
 		}
 	};
 
+	const pendingUploads = recordings.filter(
+		(r) =>
+			!r.remote_job_id &&
+			["DRAFT_READY", "LOCAL_TRANSCRIBED", "FAILED"].includes(r.sync_status),
+	).length;
+
+	const handleUploadAll = async () => {
+		setIsUploadingAll(true);
+		try {
+			const { succeeded, failed } = await invoke<BatchUploadSummary>("upload_all_pending_command");
+			if (failed > 0) {
+				toast.error(`Uploaded ${succeeded}, ${failed} failed`);
+			} else {
+				toast.success(`Uploaded ${succeeded} recording${succeeded === 1 ? "" : "s"}`);
+			}
+		} catch (error) {
+			console.error("Batch upload failed:", error);
+			if (isUnauthorized(error)) {
+				toast.error("Scriberr rejected the API key. Update it in Settings.");
+			} else {
+				toast.error("Couldn't upload pending recordings");
+			}
+		} finally {
+			setIsUploadingAll(false);
+			refetch();
+		}
+	};
+
 	// --- Loading State ---
 	if (isLoading) {
 		return (
@@ -230,6 +273,21 @@ export function RecordingList({ onSelect }: RecordingListProps) {
 					Recordings ({recordings.length})
 				</span>
 				<div className="flex items-center gap-1">
+					{pendingUploads > 0 && (
+						<Tooltip content={`Upload ${pendingUploads} pending`}>
+							<button
+								onClick={handleUploadAll}
+								disabled={isUploadingAll}
+								className="text-[var(--color-text-muted)] hover:text-white transition-colors p-1.5 rounded-md hover:bg-white/5 disabled:opacity-50"
+							>
+								{isUploadingAll ? (
+									<Loader2 size={12} className="animate-spin" />
+								) : (
+									<CloudUpload size={12} />
+								)}
+							</button>
+						</Tooltip>
+					)}
 					<Tooltip content="Sync with Cloud">
 						<button
 							onClick={handleSync}
@@ -360,8 +418,8 @@ export function RecordingList({ onSelect }: RecordingListProps) {
 											</Tooltip>
 										)}
 
-										{rec.sync_status === "DRAFT_READY" && !isUploading && (
-											<Tooltip content="Not Uploaded (Local Only)">
+										{(rec.sync_status === "DRAFT_READY" || rec.sync_status === "LOCAL_TRANSCRIBED") && !isUploading && (
+											<Tooltip content={rec.sync_status === "LOCAL_TRANSCRIBED" ? "Transcribed Locally (Not Uploaded)" : "Not Uploaded (Local Only)"}>
 												<div className="p-1.5 rounded-md text-[var(--color-text-disabled)] opacity-50">
 													<CloudOff size={16} />
 												</div>
@@ -386,8 +444,8 @@ export function RecordingList({ onSelect }: RecordingListProps) {
 											</button>
 										</Tooltip>
 
-										{/* Upload Button - ONLY for DRAFT_READY */}
-										{rec.sync_status === "DRAFT_READY" && !isUploading && (
+										{/* Upload Button - ONLY for recordings not yet on the server */}
+										{(rec.sync_status === "DRAFT_READY" || rec.sync_status === "LOCAL_TRANSCRIBED") && !isUploading && (
 											<Tooltip content="Upload to Scriberr">
 												<button
 													onClick={(e) => {
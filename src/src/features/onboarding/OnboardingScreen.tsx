@@ -66,8 +66,8 @@ export function OnboardingScreen({ onComplete }: OnboardingScreenProps) {
 
         // 1. Test Connection
         testConnectionMutation.mutate({ url, apiKey }, {
-            onSuccess: (success) => {
-                if (success) {
+            onSuccess: (check) => {
+                if (check.api_key_valid) {
                     setStatus('saving');
                     setMessage('Saving configuration...');
 
@@ -92,7 +92,7 @@ export function OnboardingScreen({ onComplete }: OnboardingScreenProps) {
 
                 } else {
                     setStatus('error');
-                    setMessage('Connection failed. Check URL/Key.');
+                    setMessage(check.error ?? 'Connection failed. Check URL/Key.');
                 }
             },
             onError: () => {
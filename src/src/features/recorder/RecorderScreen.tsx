@@ -9,6 +9,8 @@ import { Mic, Pencil } from "lucide-react";
 import { AnimatePresence, motion } from "framer-motion";
 import { Visualizer } from "./Visualizer";
 import { toast } from "sonner";
+import { listen } from "@tauri-apps/api/event";
+import type { AppError, BufferOverrun } from "../../types";
 
 export function RecorderScreen() {
 	// 1. Fetch Backend Status
@@ -31,10 +33,33 @@ export function RecorderScreen() {
 	// Initial mic selection
 	useEffect(() => {
 		if (mics.length > 0 && !selectedMic) {
-			setSelectedMic(mics[0].name);
+			setSelectedMic(mics[0].id);
 		}
 	}, [mics, selectedMic]);
 
+	// The backend stops and saves the recording itself when writing fails
+	useEffect(() => {
+		const unlistenPromise = listen<AppError>("recording-error", (event) => {
+			const error = event.payload;
+			toast.error("Recording stopped", {
+				description: "message" in error && typeof error.message === "string"
+					? error.message
+					: "Writing audio to disk failed",
+			});
+		});
+		// Reuses one toast so a struggling disk doesn't stack them up
+		const unlistenOverrun = listen<BufferOverrun>("buffer-overrun", () => {
+			toast.warning("Some audio was dropped", {
+				id: "buffer-overrun",
+				description: "The disk couldn't keep up. A larger buffer in Settings can help.",
+			});
+		});
+		return () => {
+			unlistenPromise.then((unlisten) => unlisten());
+			unlistenOverrun.then((unlisten) => unlisten());
+		};
+	}, []);
+
 	// Derived State
 	const isRecording = status?.is_recording ?? false;
 	const isPaused = status?.is_paused ?? false;
@@ -168,7 +193,7 @@ export function RecorderScreen() {
 				{/* Mic Selector & Info */}
 				<div className="w-full flex flex-col gap-3">
 					<MicSelector
-						devices={mics.map((m) => ({ deviceId: m.name, label: m.name }))}
+						devices={mics.map((m) => ({ deviceId: m.id, label: m.name }))}
 						selectedDevice={selectedMic}
 						onSelect={(mic) => {
 							setSelectedMic(mic);
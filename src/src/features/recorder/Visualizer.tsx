@@ -1,5 +1,6 @@
 import { useEffect, useRef } from 'react';
 import { listen } from '@tauri-apps/api/event';
+import type { AudioLevel } from '../../types';
 
 interface VisualizerProps {
     isActive: boolean;
@@ -15,8 +16,8 @@ export function Visualizer({ isActive }: VisualizerProps) {
 
     useEffect(() => {
         // Listen to audio level events from Rust
-        const unlistenPromise = listen<number>('audio-level', (event) => {
-            const raw = event.payload;
+        const unlistenPromise = listen<AudioLevel>('audio-level', (event) => {
+            const raw = event.payload.mixed_rms;
             // Strong curve for responsiveness
             const amplified = Math.min(Math.pow(raw, 0.4) * 1.8, 2.0);
             audioLevelRef.current = amplified;
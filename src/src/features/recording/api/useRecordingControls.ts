@@ -6,6 +6,9 @@ interface StartRecordingParams {
     filename?: string;
     micDevice?: string;
     captureSystemAudio?: boolean;
+    preservePauseGaps?: boolean;
+    silenceSplitSec?: number;
+    dualTrack?: boolean;
 }
 
 export const useRecordingControls = () => {
@@ -19,7 +22,10 @@ export const useRecordingControls = () => {
             await invoke('start_recording_command', {
                 filename: args.filename || null,
                 mic_device: args.micDevice || null,
-                captureSystemAudio: args.captureSystemAudio ?? true // Default to true if not provided
+                captureSystemAudio: args.captureSystemAudio ?? true, // Default to true if not provided
+                preservePauseGaps: args.preservePauseGaps ?? false,
+                silenceSplitSec: args.silenceSplitSec ?? null,
+                dualTrack: args.dualTrack ?? false
             });
         },
         onSuccess: () => {
@@ -5,6 +5,7 @@ export interface RecordingStatus {
     is_recording: boolean;
     is_paused: boolean;
     start_time_ms: number | null;
+    elapsed_ms: number;
 }
 
 export const useRecordingStatus = () => {
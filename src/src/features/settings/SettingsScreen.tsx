@@ -85,13 +85,13 @@ export function SettingsScreen({ }: SettingsScreenProps) {
         setMessage('Testing connection...');
 
         testConnectionMutation.mutate({ url, apiKey }, {
-            onSuccess: (success) => {
-                if (success) {
+            onSuccess: (check) => {
+                if (check.api_key_valid) {
                     setStatus('success');
-                    setMessage('Connection verified');
+                    setMessage(check.latency_ms != null ? `Connection verified (${check.latency_ms} ms)` : 'Connection verified');
                 } else {
                     setStatus('error');
-                    setMessage('Connection failed');
+                    setMessage(check.error ?? 'Connection failed');
                 }
             },
             onError: () => {
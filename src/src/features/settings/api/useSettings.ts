@@ -1,6 +1,6 @@
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query';
 import { invoke } from '@tauri-apps/api/core';
-import type { Settings } from '../../../types';
+import type { ConnectionCheck, Settings, SettingsUpdate } from '../../../types';
 
 export const useSettings = () => {
     return useQuery({
@@ -14,12 +14,13 @@ export const useSettings = () => {
 export const useSaveSettings = () => {
     const queryClient = useQueryClient();
     return useMutation({
-        mutationFn: async (settings: Settings) => {
-            await invoke('save_settings_command', { settings });
+        mutationFn: async (update: SettingsUpdate) => {
+            await invoke('save_settings_command', { update });
         },
         onSuccess: (_data, variables) => {
-            // Update cache immediately
-            queryClient.setQueryData(['settings'], variables);
+            // Update cache immediately, then pick up what the backend merged in
+            queryClient.setQueryData<Settings>(['settings'], (old) => old ? { ...old, ...variables } : old);
+            queryClient.invalidateQueries({ queryKey: ['settings'] });
         },
     });
 };
@@ -27,7 +28,7 @@ export const useSaveSettings = () => {
 export const useTestConnection = () => {
     return useMutation({
         mutationFn: async (credentials: { url: string; apiKey: string }) => {
-            return await invoke<boolean>('check_connection_command', {
+            return await invoke<ConnectionCheck>('check_connection_command', {
                 url: credentials.url,
                 apiKey: credentials.apiKey
             });
@@ -40,10 +41,11 @@ export const useConnectivity = () => {
         queryFn: async () => {
             const settings = await invoke<Settings>('load_settings_command');
             if (settings.scriberr_url && settings.api_key) {
-                return await invoke<boolean>('check_connection_command', {
+                const check = await invoke<ConnectionCheck>('check_connection_command', {
                     url: settings.scriberr_url,
                     apiKey: settings.api_key
                 });
+                return check.api_key_valid;
             }
             return false;
         },
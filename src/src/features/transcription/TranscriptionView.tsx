@@ -1,9 +1,12 @@
 import { useState, useMemo } from "react";
 import { useRecordings } from "../library/api/useRecordings";
-import { Loader2, AlignLeft, List, FileText, User } from "lucide-react";
+import { Loader2, AlignLeft, List, FileText, User, Download } from "lucide-react";
 import { useProxyUrl } from "../audio/useProxyUrl";
 import { EmberPlayer } from "../audio/EmberPlayer";
 import clsx from "clsx";
+import { toast } from "sonner";
+import { exportTranscript } from "./exportTranscript";
+import type { ExportFormat } from "../../types";
 
 interface TranscriptionViewProps {
 	recordingId: string;
@@ -68,6 +71,21 @@ export function TranscriptionView({ recordingId }: TranscriptionViewProps) {
 		segments.length > 0 ? "segments" : "paragraph",
 	);
 
+	const exportFormats: ExportFormat[] =
+		segments.length > 0 ? ["txt", "md", "srt", "vtt"] : ["txt", "md"];
+
+	const handleExport = async (format: ExportFormat) => {
+		if (!recording) return;
+		try {
+			if (await exportTranscript(recording.local_id, recording.title || "Transcript", format)) {
+				toast.success(`Exported ${format.toUpperCase()}`);
+			}
+		} catch (e) {
+			console.error("Export failed", e);
+			toast.error("Failed to export transcript");
+		}
+	};
+
 	if (!recording) {
 		return (
 			<div className="h-full w-full flex items-center justify-center text-[var(--color-text-muted)]">
@@ -100,34 +118,53 @@ export function TranscriptionView({ recordingId }: TranscriptionViewProps) {
 							</div>
 						</div>
 
-						{/* View Toggle (Recessed Input Style) */}
-						<div className="flex bg-[var(--color-glass-input)] rounded-lg p-1 border border-white/5 shadow-inner backdrop-blur-md">
-							<button
-								onClick={() => setViewMode("paragraph")}
-								className={clsx(
-									"p-1.5 rounded-md transition-all focus:outline-none cursor-pointer",
-									viewMode === "paragraph"
-										? "bg-[var(--color-glass-highlight)] text-white shadow-sm"
-										: "text-[var(--color-text-muted)] hover:text-[var(--color-text-main)]",
-								)}
-								title="Paragraph View"
-							>
-								<AlignLeft size={16} />
-							</button>
-							<button
-								onClick={() => setViewMode("segments")}
-								disabled={segments.length === 0}
-								className={clsx(
-									"p-1.5 rounded-md transition-all focus:outline-none cursor-pointer",
-									viewMode === "segments"
-										? "bg-[var(--color-glass-highlight)] text-white shadow-sm"
-										: "text-[var(--color-text-muted)] hover:text-[var(--color-text-main)]",
-									segments.length === 0 && "opacity-30 cursor-not-allowed",
-								)}
-								title="Segmented View"
-							>
-								<List size={16} />
-							</button>
+						<div className="flex items-center gap-2">
+							{/* Export (subtitle formats need timed segments) */}
+							{text.trim().length > 0 && (
+								<div className="flex bg-[var(--color-glass-input)] rounded-lg p-1 border border-white/5 shadow-inner backdrop-blur-md">
+									{exportFormats.map((format) => (
+										<button
+											key={format}
+											onClick={() => handleExport(format)}
+											className="flex items-center gap-1 px-1.5 py-1 rounded-md text-[10px] font-bold uppercase tracking-widest text-[var(--color-text-muted)] hover:text-[var(--color-text-main)] transition-all focus:outline-none cursor-pointer"
+											title={`Export ${format.toUpperCase()}`}
+										>
+											<Download size={12} />
+											{format}
+										</button>
+									))}
+								</div>
+							)}
+
+							{/* View Toggle (Recessed Input Style) */}
+							<div className="flex bg-[var(--color-glass-input)] rounded-lg p-1 border border-white/5 shadow-inner backdrop-blur-md">
+								<button
+									onClick={() => setViewMode("paragraph")}
+									className={clsx(
+										"p-1.5 rounded-md transition-all focus:outline-none cursor-pointer",
+										viewMode === "paragraph"
+											? "bg-[var(--color-glass-highlight)] text-white shadow-sm"
+											: "text-[var(--color-text-muted)] hover:text-[var(--color-text-main)]",
+									)}
+									title="Paragraph View"
+								>
+									<AlignLeft size={16} />
+								</button>
+								<button
+									onClick={() => setViewMode("segments")}
+									disabled={segments.length === 0}
+									className={clsx(
+										"p-1.5 rounded-md transition-all focus:outline-none cursor-pointer",
+										viewMode === "segments"
+											? "bg-[var(--color-glass-highlight)] text-white shadow-sm"
+											: "text-[var(--color-text-muted)] hover:text-[var(--color-text-main)]",
+										segments.length === 0 && "opacity-30 cursor-not-allowed",
+									)}
+									title="Segmented View"
+								>
+									<List size={16} />
+								</button>
+							</div>
 						</div>
 					</div>
 
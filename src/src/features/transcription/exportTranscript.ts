@@ -0,0 +1,23 @@
+import { invoke } from '@tauri-apps/api/core';
+import { save } from '@tauri-apps/plugin-dialog';
+import type { ExportFormat } from '../../types';
+
+const FORMAT_NAMES: Record<ExportFormat, string> = {
+    srt: 'SubRip Subtitles',
+    vtt: 'WebVTT Subtitles',
+    txt: 'Plain Text',
+    md: 'Markdown',
+};
+
+// Asks where to save and writes the transcript there. Resolves false if the
+// dialog was cancelled.
+export async function exportTranscript(localId: string, title: string, format: ExportFormat): Promise<boolean> {
+    const path = await save({
+        defaultPath: `${title.replace(/[\\/:*?"<>|]/g, '_')}.${format}`,
+        filters: [{ name: FORMAT_NAMES[format], extensions: [format] }],
+    });
+    if (!path) return false;
+
+    await invoke('export_transcript_command', { localId, format, path });
+    return true;
+}
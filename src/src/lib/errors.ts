@@ -0,0 +1,12 @@
+import type { AppError } from '../types';
+
+const isAppError = (error: unknown): error is AppError =>
+    typeof error === 'object' && error !== null && 'code' in error;
+
+// The server rejected the API key; the user needs to re-enter credentials
+export const isUnauthorized = (error: unknown): boolean =>
+    isAppError(error) && error.code === 'Http' && (error.message.status === 401 || error.message.status === 403);
+
+// The server accepted the connection but took too long to answer
+export const isTimeout = (error: unknown): boolean =>
+    isAppError(error) && error.code === 'Timeout';
@@ -11,7 +11,7 @@ export interface CachedRecording {
     title: string;
     duration_sec: number;
     created_at: string;
-    sync_status: string; // 'DRAFT_READY', 'UPLOADING', 'PROCESSING_REMOTE', 'COMPLETED_SYNCED', 'FAILED'
+    sync_status: string; // 'DRAFT_READY', 'LOCAL_TRANSCRIBED', 'UPLOADING', 'PROCESSING_REMOTE', 'COMPLETED_SYNCED', 'FAILED'
     local_file_path: string | null;
     remote_audio_url: string | null;
     local_audio_path: string | null;
@@ -19,13 +19,185 @@ export interface CachedRecording {
     transcript_text: string | null;
     summary_text: string | null;
     individual_transcripts_json: string | null;
+    source_app: string | null;
+    mic_only: boolean;
+    deleted_at: string | null;
+    title_dirty: boolean;
+    title_auto: boolean;
+}
+
+// Result of search_recordings_command; matched terms in `snippet` are wrapped in <mark>
+export interface SearchResult {
+    recording: CachedRecording;
+    snippet: string | null;
+}
+
+export interface Tag {
+    id: number;
+    name: string;
+}
+
+export type SortField = 'created_at' | 'title' | 'duration';
+export type SortOrder = 'asc' | 'desc';
+
+// Result of get_recordings_page_command; page starts at 1
+export interface RecordingsPage {
+    recordings: CachedRecording[];
+    total: number;
+    page: number;
+    limit: number;
 }
 
 export type LedgerEntry = CachedRecording; // Alias for backward compatibility during migration
 
+// Payload of the backend's single `library-event` stream
+export type LibraryEvent =
+    | { kind: 'added'; data: CachedRecording }
+    | { kind: 'updated'; data: CachedRecording }
+    | { kind: 'stopped'; data: CachedRecording }
+    | { kind: 'deleted'; data: { local_id: string | null; remote_job_id: string | null } }
+    | { kind: 'sync_started' }
+    | { kind: 'sync_progress'; data: { page: number; processed: number } }
+    | { kind: 'sync_completed' };
+
+// Payload of `audio-level`, roughly every 50 ms while recording
+export interface AudioLevel {
+    mic_peak: number;
+    sys_peak: number;
+    mic_rms: number;
+    mixed_rms: number;
+}
+
+// Payload of `buffer-overrun`: samples dropped so far this recording, sent at most once a second
+export interface BufferOverrun {
+    mic_samples_dropped: number;
+    sys_samples_dropped: number;
+}
+
+// Payload of `upload-progress`; bytes_sent reaches total_bytes once the server accepts the upload
+export interface UploadProgress {
+    local_id: string;
+    bytes_sent: number;
+    total_bytes: number;
+    percent: number;
+}
+
+// Sent by upload_all_pending_command after each recording
+export interface BatchUploadProgress {
+    local_id: string;
+    index: number; // 1-based
+    total: number;
+    succeeded: boolean;
+    error: string | null;
+}
+
+export interface BatchUploadSummary {
+    succeeded: number;
+    failed: number;
+}
+
+// Payload of `download-progress`; the total is unknown without a Content-Length
+export interface DownloadProgress {
+    local_id: string;
+    bytes_received: number;
+    total_bytes: number | null;
+    percent: number | null;
+}
+
+export type ConnectionProblem = 'dns' | 'unreachable' | 'timeout' | 'unauthorized' | 'not_scriberr' | 'server_error';
+
+// How commands reject: AppError serialized with #[serde(tag = "code", content = "message")]
+export type AppError =
+    | { code: 'Http'; message: { status: number; message: string } }
+    | { code: 'Offline' }
+    | { code: 'Timeout' }
+    | { code: 'Cancelled' }
+    | {
+        code: 'Io' | 'Network' | 'Server' | 'Serialization' | 'Audio' | 'Validation'
+            | 'NotFound' | 'Logic' | 'Unexpected' | 'Database';
+        message: string;
+    };
+
+// Result of check_connection_command
+export interface ConnectionCheck {
+    reachable: boolean;
+    status_code: number | null;
+    api_key_valid: boolean;
+    server_version: string | null;
+    latency_ms: number | null;
+    error: string | null;
+}
+
+// Result of diagnose_connection_command
+export interface ConnectionDiagnosis {
+    reachable: boolean;
+    problem: ConnectionProblem | null;
+    status: number | null;
+    server_version: string | null;
+    model_count: number | null;
+    latency_ms: number | null;
+    message: string | null;
+}
+
+// Entry of list_models_command; id is what upload_recording_command takes
+export interface TranscriptionModel {
+    id: string;
+    name: string | null;
+}
+
+// Payload of the recording-status-changed event, emitted during sync
+export interface RecordingStatusChange {
+    local_id: string;
+    old_status: string;
+    new_status: string;
+}
+
+export type OutputFormat = 'wav' | 'mp3' | 'flac';
+
+// Transcript formats export_transcript_command can write
+export type ExportFormat = 'srt' | 'vtt' | 'txt' | 'md';
+
+// What save_settings_command applies; the rest of Settings is kept as stored
+export interface SettingsUpdate {
+    scriberr_url: string;
+    api_key: string;
+    output_path: string;
+}
+
 export interface Settings {
     scriberr_url: string;
     api_key: string;
     output_path: string;
     last_sync_timestamp?: string | null;
+    last_sync_ids?: string[];
+    auto_title?: boolean;
+    legacy_output_paths?: string[];
+    upload_on_stop?: boolean;
+    stop_on_system_audio_stall?: boolean;
+    system_audio_best_effort?: boolean;
+    dc_blocker?: boolean;
+    clip_ceiling?: number;
+    buffer_seconds?: number;
+    storage_quota_bytes?: number | null;
+    mic_agc?: boolean;
+    agc_target_rms?: number;
+    agc_attack_ms?: number;
+    agc_release_ms?: number;
+    power_save?: boolean;
+    heartbeat_interval_secs?: number;
+    heartbeat_path?: string;
+    output_format?: OutputFormat;
+    mic_gain?: number;
+    system_gain?: number;
+    preferred_mic?: string | null;
+    silence_split_threshold_rms?: number;
+    meeting_app_bundle_ids?: string[];
+    audio_cache_max_bytes?: number;
+    audio_cache_dir?: string | null;
+    trash_retention_days?: number;
+    api_key_ref?: string | null;
+    last_model?: string | null;
+    whisper_binary_path?: string | null;
+    whisper_model_path?: string | null;
+    sync_interval_sec?: number;
 }